@@ -0,0 +1,70 @@
+use rc201_8::block::BlockEngine;
+use rc201_8::cpu::Cpu;
+use rc201_8::display::{Display, DisplayBuffer};
+use rc201_8::emu::Emu;
+use rc201_8::generator::{GenConfig, Generator};
+use std::time::{Duration, Instant};
+
+/// Instructions run by each measure
+const INSTRUCTIONS: usize = 10_000_000;
+
+/// Instructions per call of `BlockEngine::run`, those of a frame
+const IPF: usize = 1000;
+
+/// Arithmetic loop: add, mix and shift registers, step I, then jump back
+const LOOP: [u8; 18] = [
+    0x60, 0x00, 0x61, 0x01, 0x80, 0x14, 0x82, 0x03, 0x73, 0x01, 0x84, 0x26, 0xA3, 0x00, 0xF3, 0x1E,
+    0x12, 0x04,
+];
+
+/// Emulator with a ROM loaded
+fn emu(rom: &[u8]) -> Emu<DisplayBuffer> {
+    let mut emu = Emu::new(DisplayBuffer::new());
+    emu.rng_seed(0);
+    emu.load_rom(rom).unwrap();
+    emu
+}
+
+/// Time the interpreter over the instructions
+fn interpret(rom: &[u8]) -> Duration {
+    let mut emu = emu(rom);
+    let start = Instant::now();
+    for _ in 0..INSTRUCTIONS {
+        emu.step().unwrap();
+    }
+    start.elapsed()
+}
+
+/// Time the block engine over the instructions
+fn translate(rom: &[u8]) -> Duration {
+    let mut emu = emu(rom);
+    let mut engine = BlockEngine::new(&mut emu);
+    let start = Instant::now();
+    for _ in 0..INSTRUCTIONS / IPF {
+        engine.run(IPF).unwrap();
+    }
+    start.elapsed()
+}
+
+/// Print the instructions per second of both engines on a ROM
+fn compare(name: &str, rom: &[u8]) {
+    let rate = |d: Duration| INSTRUCTIONS as f64 / d.as_secs_f64() / 1e6;
+    let step = interpret(rom);
+    let block = translate(rom);
+    println!(
+        "{:<10} step {:>7.1} M/s  block {:>7.1} M/s  speedup {:.2}",
+        name,
+        rate(step),
+        rate(block),
+        step.as_secs_f64() / block.as_secs_f64()
+    );
+}
+
+fn main() {
+    compare("loop", &LOOP);
+
+    // Generated program, spending most of the time on its final jump to
+    // itself
+    let mut gen = Generator::new(0xB10C, GenConfig::default()).unwrap();
+    compare("generated", &gen.program());
+}
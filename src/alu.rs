@@ -0,0 +1,181 @@
+use crate::quirk::Quirks;
+
+///
+/// Result of an arithmetic or logic operation
+///
+/// The value must be written to VX before the flag is written to VF, so
+/// that the flag wins when X is F.
+///
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct AluOutput {
+    /// New value of VX
+    pub value: u8,
+
+    /// New value of VF, if the operation sets it
+    pub flag: Option<u8>,
+}
+
+///
+/// Signature shared by the `8XYN` operations
+///
+pub type AluOp = fn(u8, u8, &Quirks) -> AluOutput;
+
+///
+/// Return the `8XYN` operation for a given N, if defined
+///
+/// # Example
+///
+/// ```
+/// use rc201_8::alu;
+/// use rc201_8::quirk::Quirks;
+///
+/// let add = alu::op(0x4).unwrap();
+/// let out = add(0xFF, 0x02, &Quirks::default());
+///
+/// assert_eq!(out.value, 0x01);
+/// assert_eq!(out.flag, Some(1));
+/// assert!(alu::op(0x8).is_none());
+/// ```
+///
+pub fn op(n: u8) -> Option<AluOp> {
+    match n {
+        0x0 => Some(assign),
+        0x1 => Some(or),
+        0x2 => Some(and),
+        0x3 => Some(xor),
+        0x4 => Some(add),
+        0x5 => Some(sub),
+        0x6 => Some(shr),
+        0x7 => Some(subn),
+        0xE => Some(shl),
+        _ => None,
+    }
+}
+
+/// Flag of the logic operations, reset by the `vf_reset` quirk
+fn logic_flag(quirks: &Quirks) -> Option<u8> {
+    if quirks.vf_reset {
+        Some(0)
+    } else {
+        None
+    }
+}
+
+/// Operand of the shifts, VY under the `shift_vy` quirk
+fn shift_operand(vx: u8, vy: u8, quirks: &Quirks) -> u8 {
+    if quirks.shift_vy {
+        vy
+    } else {
+        vx
+    }
+}
+
+///
+/// `7XNN`: VX + NN, wrapping, leaving VF untouched
+///
+pub fn add_nn(vx: u8, nn: u8) -> u8 {
+    vx.wrapping_add(nn)
+}
+
+///
+/// `8XY0`: VY
+///
+pub fn assign(_vx: u8, vy: u8, _quirks: &Quirks) -> AluOutput {
+    AluOutput {
+        value: vy,
+        flag: None,
+    }
+}
+
+///
+/// `8XY1`: VX | VY
+///
+pub fn or(vx: u8, vy: u8, quirks: &Quirks) -> AluOutput {
+    AluOutput {
+        value: vx | vy,
+        flag: logic_flag(quirks),
+    }
+}
+
+///
+/// `8XY2`: VX & VY
+///
+pub fn and(vx: u8, vy: u8, quirks: &Quirks) -> AluOutput {
+    AluOutput {
+        value: vx & vy,
+        flag: logic_flag(quirks),
+    }
+}
+
+///
+/// `8XY3`: VX ^ VY
+///
+pub fn xor(vx: u8, vy: u8, quirks: &Quirks) -> AluOutput {
+    AluOutput {
+        value: vx ^ vy,
+        flag: logic_flag(quirks),
+    }
+}
+
+///
+/// `8XY4`: VX + VY, wrapping, with VF set to the carry
+///
+pub fn add(vx: u8, vy: u8, _quirks: &Quirks) -> AluOutput {
+    let (value, carry) = vx.overflowing_add(vy);
+    AluOutput {
+        value,
+        flag: Some(carry as u8),
+    }
+}
+
+///
+/// `8XY5`: VX - VY, wrapping, with VF set to 1 when there is no borrow
+///
+/// # Example
+///
+/// ```
+/// use rc201_8::alu;
+/// use rc201_8::quirk::Quirks;
+///
+/// let out = alu::sub(0x10, 0x30, &Quirks::default());
+///
+/// assert_eq!(out.value, 0xE0);
+/// assert_eq!(out.flag, Some(0));
+/// ```
+///
+pub fn sub(vx: u8, vy: u8, _quirks: &Quirks) -> AluOutput {
+    let (value, borrow) = vx.overflowing_sub(vy);
+    AluOutput {
+        value,
+        flag: Some(!borrow as u8),
+    }
+}
+
+///
+/// `8XY7`: VY - VX, wrapping, with VF set to 1 when there is no borrow
+///
+pub fn subn(vx: u8, vy: u8, quirks: &Quirks) -> AluOutput {
+    sub(vy, vx, quirks)
+}
+
+///
+/// `8XY6`: operand >> 1, with VF set to the bit shifted out
+///
+pub fn shr(vx: u8, vy: u8, quirks: &Quirks) -> AluOutput {
+    let v = shift_operand(vx, vy, quirks);
+    AluOutput {
+        value: v >> 1,
+        flag: Some(v & 0x1),
+    }
+}
+
+///
+/// `8XYE`: operand << 1, with VF set to the bit shifted out
+///
+pub fn shl(vx: u8, vy: u8, quirks: &Quirks) -> AluOutput {
+    let v = shift_operand(vx, vy, quirks);
+    AluOutput {
+        value: v << 1,
+        flag: Some(v >> 7),
+    }
+}
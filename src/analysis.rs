@@ -0,0 +1,446 @@
+use crate::emu::{MEM_SIZE, PRG_INCR, PRG_START, REG_SIZE};
+use crate::oper::{Oper, OperCode};
+use std::collections::{BTreeMap, BTreeSet};
+use std::io::{self, Write};
+use std::ops::Range;
+
+///
+/// Kind of a control-flow edge
+///
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum EdgeKind {
+    ///
+    /// Next operation, including the return address of a call
+    ///
+    Next,
+
+    ///
+    /// Target of `Flow1NNN`
+    ///
+    Jump,
+
+    ///
+    /// Operation after the skipped one of a condition
+    ///
+    Skip,
+
+    ///
+    /// Entry of the subroutine called by `Flow2NNN`
+    ///
+    Call,
+}
+
+///
+/// Control-flow edge to the start of a block
+///
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct Edge {
+    /// Target address
+    pub target: u16,
+
+    /// Kind of the transfer
+    pub kind: EdgeKind,
+}
+
+///
+/// Reason for a block to end
+///
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Terminator {
+    ///
+    /// The next operation starts another block, or lies outside the ROM
+    ///
+    Fallthrough,
+
+    ///
+    /// `Flow1NNN`
+    ///
+    Jump,
+
+    ///
+    /// `Flow2NNN`
+    ///
+    Call,
+
+    ///
+    /// A condition such as `Cond3XNN`
+    ///
+    Skip,
+
+    ///
+    /// `Flow00EE`
+    ///
+    Return,
+
+    ///
+    /// `FlowBNNN`, whose target depends on a register
+    ///
+    Indirect,
+
+    ///
+    /// Unknown operation
+    ///
+    Invalid,
+}
+
+///
+/// Straight sequence of operations with a single entry
+///
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct BasicBlock {
+    /// Address of the first operation
+    pub start: u16,
+
+    /// Address after the last operation
+    pub end: u16,
+
+    /// Operations and their addresses, in order
+    pub opers: Vec<(u16, OperCode)>,
+
+    /// Reason for the block to end
+    pub terminator: Terminator,
+
+    /// Outgoing edges
+    pub successors: Vec<Edge>,
+}
+
+///
+/// Subroutine called by `Flow2NNN`
+///
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Subroutine {
+    /// Entry address
+    pub entry: u16,
+
+    /// Start of the blocks reached from the entry without following
+    /// calls, in address order
+    pub blocks: Vec<u16>,
+}
+
+///
+/// Static control-flow graph of a ROM
+///
+/// The analysis starts from `PRG_START` and follows jumps, calls,
+/// returns and conditions, which skip exactly one operation. `FlowBNNN`
+/// ends its block as an indirect jump without successors, so the code
+/// it reaches only through it is reported as unreachable.
+///
+/// # Example
+///
+/// ```
+/// use rc201_8::analysis::{Cfg, EdgeKind, Terminator};
+///
+/// // Call a subroutine skipping on V0, then loop forever. The last
+/// // two bytes are never executed
+/// let cfg = Cfg::new(&[
+///     0x22, 0x04, 0x12, 0x02, 0x30, 0x00, 0x00, 0xE0, 0x00, 0xEE, 0xFF, 0xFF,
+/// ]);
+///
+/// assert_eq!(cfg.blocks().len(), 5);
+/// assert_eq!(cfg.block(0x200).unwrap().terminator, Terminator::Call);
+/// let skip = cfg.block(0x204).unwrap();
+/// assert_eq!(skip.successors[1].kind, EdgeKind::Skip);
+/// assert_eq!(skip.successors[1].target, 0x208);
+/// assert_eq!(cfg.subroutines()[0].blocks, vec![0x204, 0x206, 0x208]);
+/// assert_eq!(cfg.unreachable(), &[0x20A..0x20C]);
+/// ```
+///
+#[derive(Debug, Clone)]
+pub struct Cfg {
+    /// Blocks by start address
+    blocks: BTreeMap<u16, BasicBlock>,
+
+    /// Subroutines, in entry order
+    subroutines: Vec<Subroutine>,
+
+    /// ROM ranges never reached as operations
+    unreachable: Vec<Range<u16>>,
+}
+
+impl Cfg {
+    ///
+    /// Returns the control-flow graph of a ROM loaded on `PRG_START`
+    ///
+    pub fn new(rom: &[u8]) -> Cfg {
+        let base = PRG_START as u16;
+        let rom = &rom[..rom.len().min(MEM_SIZE - PRG_START)];
+        let fetch = |address: u16| -> Option<OperCode> {
+            let offset = usize::from(address.checked_sub(base)?);
+            let code = u16::from(*rom.get(offset)?) << 8 | u16::from(*rom.get(offset + 1)?);
+            Some(Oper::from_code(&code, &REG_SIZE))
+        };
+
+        // Operations reachable from the entry, and the block leaders
+        let mut reached = BTreeMap::new();
+        let mut leaders = BTreeSet::new();
+        let mut calls = BTreeSet::new();
+        let mut pending = vec![base];
+        leaders.insert(base);
+        while let Some(address) = pending.pop() {
+            if reached.contains_key(&address) {
+                continue;
+            }
+            let oper = match fetch(address) {
+                Some(oper) => oper,
+                None => continue,
+            };
+            reached.insert(address, oper);
+            let edges = successors(address, &oper);
+            for edge in &edges {
+                if terminator(&oper) != Terminator::Fallthrough {
+                    leaders.insert(edge.target);
+                }
+                if edge.kind == EdgeKind::Call {
+                    calls.insert(edge.target);
+                }
+                pending.push(edge.target);
+            }
+        }
+
+        let mut blocks = BTreeMap::new();
+        for start in leaders.iter().filter(|l| reached.contains_key(l)) {
+            let mut opers = Vec::new();
+            let mut address = *start;
+            let (terminator, successors) = loop {
+                let oper = reached[&address];
+                opers.push((address, oper));
+                let kind = terminator(&oper);
+                let next = address.wrapping_add(PRG_INCR);
+                if kind != Terminator::Fallthrough
+                    || leaders.contains(&next)
+                    || !reached.contains_key(&next)
+                {
+                    break (kind, successors(address, &oper));
+                }
+                address = next;
+            };
+            let end = address.wrapping_add(PRG_INCR);
+            blocks.insert(
+                *start,
+                BasicBlock {
+                    start: *start,
+                    end,
+                    opers,
+                    terminator,
+                    successors,
+                },
+            );
+        }
+
+        let subroutines = calls
+            .iter()
+            .filter(|entry| blocks.contains_key(entry))
+            .map(|entry| Subroutine {
+                entry: *entry,
+                blocks: local_blocks(&blocks, *entry),
+            })
+            .collect();
+
+        let mut unreachable: Vec<Range<u16>> = Vec::new();
+        let covered: BTreeSet<u16> = reached
+            .keys()
+            .flat_map(|a| vec![*a, a.wrapping_add(1)])
+            .collect();
+        for address in (0..rom.len()).map(|o| base + o as u16) {
+            if covered.contains(&address) {
+                continue;
+            }
+            match unreachable.last_mut() {
+                Some(range) if range.end == address => range.end += 1,
+                _ => unreachable.push(address..address + 1),
+            }
+        }
+
+        Cfg {
+            blocks,
+            subroutines,
+            unreachable,
+        }
+    }
+
+    ///
+    /// Return the blocks, in address order
+    ///
+    pub fn blocks(&self) -> Vec<&BasicBlock> {
+        self.blocks.values().collect()
+    }
+
+    ///
+    /// Return the block starting on a given address
+    ///
+    pub fn block(&self, start: u16) -> Option<&BasicBlock> {
+        self.blocks.get(&start)
+    }
+
+    ///
+    /// Return the subroutines, in entry order
+    ///
+    pub fn subroutines(&self) -> &[Subroutine] {
+        &self.subroutines
+    }
+
+    ///
+    /// Return the start of the blocks reached from an entry without
+    /// following calls, in address order
+    ///
+    pub fn local_blocks(&self, entry: u16) -> Vec<u16> {
+        local_blocks(&self.blocks, entry)
+    }
+
+    ///
+    /// Return the ROM ranges never reached as operations, which hold
+    /// data or code only reached through `FlowBNNN`
+    ///
+    pub fn unreachable(&self) -> &[Range<u16>] {
+        &self.unreachable
+    }
+
+    ///
+    /// Return the address of every `FlowBNNN` operation
+    ///
+    pub fn indirect_jumps(&self) -> Vec<u16> {
+        self.blocks
+            .values()
+            .filter(|b| b.terminator == Terminator::Indirect)
+            .filter_map(|b| b.opers.last().map(|(a, _)| *a))
+            .collect()
+    }
+
+    ///
+    /// Write the graph in the Graphviz DOT language
+    ///
+    /// Blocks are boxes listing their operations, subroutine entries are
+    /// drawn bold, calls dashed and indirect jumps point to a `?` node.
+    ///
+    /// # Example
+    ///
+    /// ```
+    /// use rc201_8::analysis::Cfg;
+    ///
+    /// // Skip on V0, then loop forever
+    /// let cfg = Cfg::new(&[0x30, 0x00, 0x00, 0xE0, 0x12, 0x04]);
+    /// let mut dot = Vec::new();
+    /// cfg.write_dot(&mut dot).unwrap();
+    /// let dot = String::from_utf8(dot).unwrap();
+    ///
+    /// assert!(dot.starts_with("digraph cfg {\n"));
+    /// assert!(dot.contains("\"0x200\" -> \"0x204\" [label=\"skip\"];"));
+    /// assert!(dot.contains("\"0x204\" -> \"0x204\" [label=\"jump\"];"));
+    /// ```
+    ///
+    pub fn write_dot<W: Write>(&self, sink: &mut W) -> io::Result<()> {
+        let entries: BTreeSet<u16> = self.subroutines.iter().map(|s| s.entry).collect();
+        writeln!(sink, "digraph cfg {{")?;
+        writeln!(sink, "    node [shape=box, fontname=\"monospace\"];")?;
+        for block in self.blocks.values() {
+            let mut label = String::new();
+            if entries.contains(&block.start) {
+                label.push_str(&format!("sub_{:03x}:\\l", block.start));
+            }
+            for (address, oper) in &block.opers {
+                label.push_str(&format!("{:03X}  {}\\l", address, oper));
+            }
+            let style = if entries.contains(&block.start) {
+                ", style=bold"
+            } else {
+                ""
+            };
+            writeln!(
+                sink,
+                "    \"0x{:03X}\" [label=\"{}\"{}];",
+                block.start, label, style
+            )?;
+        }
+        for block in self.blocks.values() {
+            for edge in &block.successors {
+                let (label, style) = match edge.kind {
+                    EdgeKind::Next => ("", ""),
+                    EdgeKind::Jump => ("jump", ""),
+                    EdgeKind::Skip => ("skip", ""),
+                    EdgeKind::Call => ("call", ", style=dashed"),
+                };
+                let attributes = if label.is_empty() {
+                    String::new()
+                } else {
+                    format!(" [label=\"{}\"{}]", label, style)
+                };
+                writeln!(
+                    sink,
+                    "    \"0x{:03X}\" -> \"0x{:03X}\"{};",
+                    block.start, edge.target, attributes
+                )?;
+            }
+            if block.terminator == Terminator::Indirect {
+                writeln!(
+                    sink,
+                    "    \"?0x{:03X}\" [shape=plaintext, label=\"?\"];",
+                    block.start
+                )?;
+                writeln!(
+                    sink,
+                    "    \"0x{:03X}\" -> \"?0x{:03X}\" [style=dotted];",
+                    block.start, block.start
+                )?;
+            }
+        }
+        writeln!(sink, "}}")
+    }
+}
+
+/// Return the reason for an operation to end its block, `Fallthrough`
+/// meaning it does not
+fn terminator(oper: &OperCode) -> Terminator {
+    match oper {
+        OperCode::Flow1NNN(_) => Terminator::Jump,
+        OperCode::Flow2NNN(_) => Terminator::Call,
+        OperCode::Flow00EE => Terminator::Return,
+        OperCode::FlowBNNN(_) => Terminator::Indirect,
+        OperCode::Cond3XNN(_, _)
+        | OperCode::Cond4XNN(_, _)
+        | OperCode::Cond5XY0(_, _)
+        | OperCode::Cond9XY0(_, _)
+        | OperCode::KeyOpEX9E(_)
+        | OperCode::KeyOpEXA1(_) => Terminator::Skip,
+        OperCode::Unknown => Terminator::Invalid,
+        _ => Terminator::Fallthrough,
+    }
+}
+
+/// Return the edges leaving an operation on a given address
+pub(crate) fn successors(address: u16, oper: &OperCode) -> Vec<Edge> {
+    let next = address.wrapping_add(PRG_INCR);
+    let edge = |target, kind| Edge { target, kind };
+    match oper {
+        OperCode::Flow1NNN(n) => vec![edge(*n, EdgeKind::Jump)],
+        OperCode::Flow2NNN(n) => vec![edge(*n, EdgeKind::Call), edge(next, EdgeKind::Next)],
+        OperCode::Flow00EE | OperCode::FlowBNNN(_) | OperCode::Unknown => Vec::new(),
+        _ if terminator(oper) == Terminator::Skip => vec![
+            edge(next, EdgeKind::Next),
+            edge(next.wrapping_add(PRG_INCR), EdgeKind::Skip),
+        ],
+        _ => vec![edge(next, EdgeKind::Next)],
+    }
+}
+
+/// Return the blocks reached from an entry without following calls
+fn local_blocks(blocks: &BTreeMap<u16, BasicBlock>, entry: u16) -> Vec<u16> {
+    let mut seen = BTreeSet::new();
+    let mut pending = vec![entry];
+    while let Some(start) = pending.pop() {
+        if !seen.insert(start) {
+            continue;
+        }
+        if let Some(block) = blocks.get(&start) {
+            pending.extend(
+                block
+                    .successors
+                    .iter()
+                    .filter(|e| e.kind != EdgeKind::Call)
+                    .map(|e| e.target),
+            );
+        }
+    }
+    seen.into_iter()
+        .filter(|s| blocks.contains_key(s))
+        .collect()
+}
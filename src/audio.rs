@@ -0,0 +1,342 @@
+use std::cell::RefCell;
+use std::rc::Rc;
+
+/// Bytes of an XO-CHIP audio pattern
+pub const PATTERN_SIZE: usize = 16_usize;
+
+/// Bits of an XO-CHIP audio pattern
+const PATTERN_BITS: f32 = 128.0_f32;
+
+/// Pitch register value of an XO-CHIP machine at reset
+pub const PITCH_DEFAULT: u8 = 64_u8;
+
+///
+/// Return the playback rate in bits per second of an XO-CHIP audio
+/// pattern for a given pitch register value
+///
+/// # Example
+///
+/// ```
+/// use rc201_8::audio;
+///
+/// assert_eq!(audio::pattern_rate(64), 4000.0);
+/// assert_eq!(audio::pattern_rate(112), 8000.0);
+/// ```
+///
+pub fn pattern_rate(pitch: u8) -> f32 {
+    4000.0 * 2.0_f32.powf((f32::from(pitch) - 64.0) / 48.0)
+}
+
+///
+/// Receiver of the buzzer state, which sounds while the sound timer is
+/// not zero
+///
+pub trait Audio {
+    ///
+    /// The sound timer was set from zero
+    ///
+    fn buzzer_start(&mut self);
+
+    ///
+    /// The sound timer reached zero
+    ///
+    fn buzzer_stop(&mut self);
+
+    ///
+    /// An emulated 60 Hz frame ended, before the timers tick
+    ///
+    fn frame(&mut self) {}
+
+    ///
+    /// An XO-CHIP audio pattern was loaded by `SoundF002`
+    ///
+    /// # Example
+    ///
+    /// ```
+    /// use rc201_8::emu::Emu;
+    /// use rc201_8::cpu::Cpu;
+    /// use rc201_8::mem::Mem;
+    /// use rc201_8::audio::{SquareSynth, SynthConfig};
+    /// use rc201_8::display::{Display, DisplayDummy};
+    /// use std::cell::RefCell;
+    /// use std::rc::Rc;
+    ///
+    /// let config = SynthConfig {
+    ///     sample_rate: 8000,
+    ///     frequency: 440.0,
+    ///     volume: 1.0,
+    ///     attack: 0.0,
+    ///     release: 0.0,
+    /// };
+    /// let synth = Rc::new(RefCell::new(SquareSynth::new(config)));
+    /// let mut emu = Emu::new(DisplayDummy::new());
+    /// emu.audio_set(Box::new(synth.clone()));
+    ///
+    /// // Load the pattern on 0x300, play 8000 bits per second and sound
+    /// emu.load_rom(&[0xA3, 0x00, 0xF0, 0x02, 0x60, 0x70, 0xF0, 0x3A, 0x60, 0x02, 0xF0, 0x18])
+    ///     .unwrap();
+    /// emu.mem_write(0x300..0x302, &[0xF0, 0x0F]).unwrap();
+    /// for _ in 0..6 {
+    ///     emu.step().unwrap();
+    /// }
+    ///
+    /// let mut samples = [0.0; 16];
+    /// synth.borrow_mut().render_f32(&mut samples);
+    /// assert_eq!(&samples[..4], &[1.0; 4]);
+    /// assert_eq!(&samples[4..12], &[-1.0; 8]);
+    /// assert_eq!(&samples[12..], &[1.0; 4]);
+    /// ```
+    ///
+    fn pattern(&mut self, _pattern: &[u8; PATTERN_SIZE]) {}
+
+    ///
+    /// The XO-CHIP pitch register was set by `SoundFX3A`
+    ///
+    fn pitch(&mut self, _pitch: u8) {}
+}
+
+impl<T: Audio> Audio for Rc<RefCell<T>> {
+    fn buzzer_start(&mut self) {
+        self.borrow_mut().buzzer_start();
+    }
+
+    fn buzzer_stop(&mut self) {
+        self.borrow_mut().buzzer_stop();
+    }
+
+    fn frame(&mut self) {
+        self.borrow_mut().frame();
+    }
+
+    fn pattern(&mut self, pattern: &[u8; PATTERN_SIZE]) {
+        self.borrow_mut().pattern(pattern);
+    }
+
+    fn pitch(&mut self, pitch: u8) {
+        self.borrow_mut().pitch(pitch);
+    }
+}
+
+///
+/// Settings of the square wave synthesiser
+///
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct SynthConfig {
+    /// Samples per second
+    pub sample_rate: u32,
+
+    /// Tone frequency in Hz
+    pub frequency: f32,
+
+    /// Peak amplitude, from 0 to 1
+    pub volume: f32,
+
+    /// Seconds to rise from silence to the peak amplitude
+    pub attack: f32,
+
+    /// Seconds to fall from the peak amplitude to silence
+    pub release: f32,
+}
+
+impl Default for SynthConfig {
+    fn default() -> SynthConfig {
+        SynthConfig {
+            sample_rate: 44_100,
+            frequency: 440.0,
+            volume: 0.25,
+            attack: 0.002,
+            release: 0.01,
+        }
+    }
+}
+
+///
+/// Reference square wave synthesiser for the buzzer
+///
+/// The envelope rises and falls linearly, which avoids the clicks of
+/// abrupt starts and stops. Frontends register a shared instance on the
+/// emulator and render its samples into the buffer of their device.
+///
+/// Once an XO-CHIP audio pattern is loaded, the synthesiser plays its
+/// 128 bits in a loop at the rate set by the pitch register, instead of
+/// the square wave.
+///
+/// # Example
+///
+/// ```
+/// use rc201_8::emu::Emu;
+/// use rc201_8::cpu::Cpu;
+/// use rc201_8::audio::{SquareSynth, SynthConfig};
+/// use rc201_8::display::{Display, DisplayDummy};
+/// use std::cell::RefCell;
+/// use std::rc::Rc;
+///
+/// let config = SynthConfig {
+///     sample_rate: 8000,
+///     frequency: 1000.0,
+///     volume: 0.5,
+///     attack: 0.0,
+///     release: 0.0,
+/// };
+/// let synth = Rc::new(RefCell::new(SquareSynth::new(config)));
+/// let mut emu = Emu::new(DisplayDummy::new());
+/// emu.audio_set(Box::new(synth.clone()));
+///
+/// let mut samples = [0.0; 8];
+/// synth.borrow_mut().render_f32(&mut samples);
+/// assert!(samples.iter().all(|s| *s == 0.0));
+///
+/// // Sound the buzzer for a frame
+/// emu.stm_put(1);
+/// synth.borrow_mut().render_f32(&mut samples);
+/// assert_eq!(samples, [0.5, 0.5, 0.5, 0.5, -0.5, -0.5, -0.5, -0.5]);
+///
+/// emu.timers_tick();
+/// let mut samples = [0; 4];
+/// synth.borrow_mut().render_i16(&mut samples);
+/// assert_eq!(samples, [0; 4]);
+/// ```
+///
+#[derive(Debug, Clone)]
+pub struct SquareSynth {
+    /// Settings
+    config: SynthConfig,
+
+    /// Whether the buzzer sounds
+    on: bool,
+
+    /// Position in the wave period, from 0 to 1
+    phase: f32,
+
+    /// Envelope level, from 0 to 1
+    level: f32,
+
+    /// XO-CHIP audio pattern
+    pattern: Option<[u8; PATTERN_SIZE]>,
+
+    /// XO-CHIP pitch register
+    pitch: u8,
+}
+
+impl SquareSynth {
+    ///
+    /// Returns a new silent SquareSynth instance
+    ///
+    pub fn new(config: SynthConfig) -> SquareSynth {
+        SquareSynth {
+            config,
+            on: false,
+            phase: 0.0,
+            level: 0.0,
+            pattern: None,
+            pitch: PITCH_DEFAULT,
+        }
+    }
+
+    ///
+    /// Return the settings
+    ///
+    pub fn config(&self) -> &SynthConfig {
+        &self.config
+    }
+
+    ///
+    /// Replace the settings, keeping the phase and envelope
+    ///
+    pub fn config_put(&mut self, config: SynthConfig) {
+        self.config = config;
+    }
+
+    ///
+    /// Whether the buzzer sounds
+    ///
+    pub fn playing(&self) -> bool {
+        self.on
+    }
+
+    ///
+    /// Return the next sample, from -1 to 1
+    ///
+    pub fn sample(&mut self) -> f32 {
+        let rate = self.config.sample_rate.max(1) as f32;
+        let (target, seconds) = if self.on {
+            (1.0, self.config.attack)
+        } else {
+            (0.0, self.config.release)
+        };
+        let delta = if seconds > 0.0 {
+            1.0 / (seconds * rate)
+        } else {
+            1.0
+        };
+        self.level = if self.level < target {
+            (self.level + delta).min(target)
+        } else {
+            (self.level - delta).max(target)
+        };
+
+        if self.level == 0.0 {
+            self.phase = 0.0;
+            return 0.0;
+        }
+        let wave = match self.pattern {
+            Some(pattern) => {
+                let bit = self.phase as usize;
+                let high = pattern[bit / 8] & (0x80 >> (bit % 8)) != 0;
+                self.phase = (self.phase + pattern_rate(self.pitch) / rate) % PATTERN_BITS;
+                if high {
+                    1.0
+                } else {
+                    -1.0
+                }
+            }
+            None => {
+                let high = self.phase < 0.5;
+                self.phase = (self.phase + self.config.frequency / rate).fract();
+                if high {
+                    1.0
+                } else {
+                    -1.0
+                }
+            }
+        };
+        wave * self.level * self.config.volume
+    }
+
+    ///
+    /// Fill a buffer with floating point samples
+    ///
+    pub fn render_f32(&mut self, buffer: &mut [f32]) {
+        for s in buffer.iter_mut() {
+            *s = self.sample();
+        }
+    }
+
+    ///
+    /// Fill a buffer with signed 16 bit samples
+    ///
+    pub fn render_i16(&mut self, buffer: &mut [i16]) {
+        for s in buffer.iter_mut() {
+            *s = (self.sample() * f32::from(i16::MAX)) as i16;
+        }
+    }
+}
+
+impl Audio for SquareSynth {
+    fn buzzer_start(&mut self) {
+        self.on = true;
+    }
+
+    fn buzzer_stop(&mut self) {
+        self.on = false;
+    }
+
+    fn pattern(&mut self, pattern: &[u8; PATTERN_SIZE]) {
+        self.pattern = Some(*pattern);
+        self.phase = 0.0;
+    }
+
+    fn pitch(&mut self, pitch: u8) {
+        self.pitch = pitch;
+    }
+}
@@ -0,0 +1,306 @@
+use rc201_8::cpu::Cpu;
+use rc201_8::debugger::Register;
+use rc201_8::display::{Display, DisplayBuffer, DisplayEmu};
+use rc201_8::emu::{Emu, MEM_SIZE, PRG_START};
+use rc201_8::keypad::{Key, Keypad};
+use rc201_8::mem::Mem;
+use rc201_8::platform::Detection;
+use rc201_8::quirk::QuirkPreset;
+use std::convert::TryFrom;
+use std::env;
+use std::fs;
+use std::process;
+
+/// Exit code when the run ends as expected
+const EXIT_PASS: i32 = 0_i32;
+
+/// Exit code when a condition is not met, or the cpu faults
+const EXIT_FAIL: i32 = 1_i32;
+
+/// Exit code of invalid arguments and input or output errors
+const EXIT_USAGE: i32 = 2_i32;
+
+const USAGE: &str = "Usage: rc201_8-run [OPTIONS] ROM
+
+Runs a ROM without user interface and exits with 0 on pass, 1 on fail
+and 2 on usage errors. Without conditions, completing the frames passes.
+With conditions, the first one met passes, and running out of frames
+fails.
+
+Options:
+  --frames N          Frames to run (default 600)
+  --ipf N             Instructions per frame (default 10)
+  --until-pc ADDR     Stop when the program counter reaches ADDR
+  --until-mem ADDR=V  Stop when the memory at ADDR holds V
+  --quirks PRESET     Quirk preset: chip8, schip, xochip, or auto to detect
+                      the platform of the ROM
+  --seed N            Seed of the random generator
+  --input FILE        Input script with `FRAME KEY down|up` lines
+  --screenshot FILE   Write the final display as a PBM image
+  --quiet             Do not dump the registers
+";
+
+/// Keys by hexadecimal value
+const KEYS: [Key; 16] = [
+    Key::K0,
+    Key::K1,
+    Key::K2,
+    Key::K3,
+    Key::K4,
+    Key::K5,
+    Key::K6,
+    Key::K7,
+    Key::K8,
+    Key::K9,
+    Key::KA,
+    Key::KB,
+    Key::KC,
+    Key::KD,
+    Key::KE,
+    Key::KF,
+];
+
+/// Key change applied at the start of a frame
+struct KeyEvent {
+    frame: usize,
+    key: Key,
+    pressed: bool,
+}
+
+/// Command line options
+struct Options {
+    rom: String,
+    frames: usize,
+    ipf: usize,
+    until_pc: Option<u16>,
+    until_mem: Option<(usize, u8)>,
+    quirks: Option<QuirkPreset>,
+    detect: bool,
+    seed: Option<u64>,
+    input: Vec<KeyEvent>,
+    screenshot: Option<String>,
+    quiet: bool,
+}
+
+/// Parse a decimal, or a hexadecimal number prefixed by 0x
+fn number(text: &str) -> Result<u64, String> {
+    let parsed = match text.strip_prefix("0x") {
+        Some(hex) => u64::from_str_radix(hex, 16),
+        None => text.parse(),
+    };
+    parsed.map_err(|_| format!("invalid number '{}'", text))
+}
+
+/// Parse a number that must fit a given type
+fn number_in<T: TryFrom<u64>>(text: &str) -> Result<T, String> {
+    T::try_from(number(text)?).map_err(|_| format!("number '{}' out of range", text))
+}
+
+/// Parse the lines of an input script
+fn script(text: &str) -> Result<Vec<KeyEvent>, String> {
+    let mut events = Vec::new();
+    for (n, line) in text.lines().enumerate() {
+        let line = line.split('#').next().unwrap_or("").trim();
+        if line.is_empty() {
+            continue;
+        }
+        let fields: Vec<&str> = line.split_whitespace().collect();
+        let error = || format!("input line {}: expected `FRAME KEY down|up`", n + 1);
+        if fields.len() != 3 {
+            return Err(error());
+        }
+        let frame = number_in(fields[0]).map_err(|_| error())?;
+        let key = usize::from_str_radix(fields[1], 16).map_err(|_| error())?;
+        let key = *KEYS.get(key).ok_or_else(error)?;
+        let pressed = match fields[2] {
+            "down" => true,
+            "up" => false,
+            _ => return Err(error()),
+        };
+        events.push(KeyEvent {
+            frame,
+            key,
+            pressed,
+        });
+    }
+    Ok(events)
+}
+
+/// Parse the command line
+fn options(args: &[String]) -> Result<Options, String> {
+    let mut options = Options {
+        rom: String::new(),
+        frames: 600,
+        ipf: 10,
+        until_pc: None,
+        until_mem: None,
+        quirks: None,
+        detect: false,
+        seed: None,
+        input: Vec::new(),
+        screenshot: None,
+        quiet: false,
+    };
+    let mut args = args.iter();
+    while let Some(arg) = args.next() {
+        let mut value = || {
+            args.next()
+                .cloned()
+                .ok_or_else(|| format!("missing value of {}", arg))
+        };
+        match arg.as_str() {
+            "--frames" => options.frames = number_in(&value()?)?,
+            "--ipf" => options.ipf = number_in(&value()?)?,
+            "--until-pc" => options.until_pc = Some(number_in(&value()?)?),
+            "--until-mem" => {
+                let value = value()?;
+                let mut parts = value.splitn(2, '=');
+                let address = number_in(parts.next().unwrap_or(""))?;
+                let byte = number_in(parts.next().unwrap_or(""))?;
+                options.until_mem = Some((address, byte));
+            }
+            "--quirks" => {
+                let value = value()?;
+                if value == "auto" {
+                    options.detect = true;
+                    continue;
+                }
+                options.quirks = Some(
+                    QuirkPreset::from_name(&value)
+                        .ok_or_else(|| format!("unknown quirk preset '{}'", value))?,
+                );
+            }
+            "--seed" => options.seed = Some(number(&value()?)?),
+            "--input" => {
+                let path = value()?;
+                let text = fs::read_to_string(&path).map_err(|e| format!("{}: {}", path, e))?;
+                options.input = script(&text)?;
+            }
+            "--screenshot" => options.screenshot = Some(value()?),
+            "--quiet" => options.quiet = true,
+            "-h" | "--help" => {
+                print!("{}", USAGE);
+                process::exit(EXIT_PASS);
+            }
+            _ if arg.starts_with('-') => return Err(format!("unknown option {}", arg)),
+            _ if options.rom.is_empty() => options.rom = arg.clone(),
+            _ => return Err(format!("unexpected argument {}", arg)),
+        }
+    }
+    if options.rom.is_empty() {
+        return Err(String::from("missing ROM"));
+    }
+    Ok(options)
+}
+
+/// Whether a stop condition holds
+fn reached<D: Display>(emu: &Emu<D>, options: &Options) -> bool {
+    let pc = options.until_pc.is_some_and(|pc| emu.cnt_get() == pc);
+    let mem = options
+        .until_mem
+        .is_some_and(|(address, value)| emu.mem_get(&address).ok() == Some(&value));
+    pc || mem
+}
+
+/// Run the ROM, returning the exit code
+fn run(options: &Options) -> i32 {
+    let rom = match fs::read(&options.rom) {
+        Ok(rom) => rom,
+        Err(e) => {
+            eprintln!("{}: {}", options.rom, e);
+            return EXIT_USAGE;
+        }
+    };
+    let mut preset = options.quirks;
+    if options.detect {
+        let detection = Detection::new(&rom);
+        if !options.quiet {
+            println!(
+                "platform {} {:.2}",
+                detection.platform.name(),
+                detection.confidence
+            );
+        }
+        if PRG_START + rom.len() > MEM_SIZE {
+            eprintln!(
+                "{}: unsupported platform {}, the ROM exceeds {} bytes of memory",
+                options.rom,
+                detection.platform.name(),
+                MEM_SIZE
+            );
+            return EXIT_USAGE;
+        }
+        preset = Some(detection.platform.preset());
+    }
+    let mut emu = Emu::new(DisplayBuffer::new());
+    if let Err(e) = emu.load_rom(&rom) {
+        eprintln!("{}: {}", options.rom, e);
+        return EXIT_USAGE;
+    }
+    if let Some(preset) = preset {
+        emu.quirks_put(preset.quirks());
+        emu.stack_depth_put(preset.stack_depth());
+    }
+    if let Some(seed) = options.seed {
+        emu.rng_seed(seed);
+    }
+
+    let conditions = options.until_pc.is_some() || options.until_mem.is_some();
+    let mut code = if conditions { EXIT_FAIL } else { EXIT_PASS };
+    let mut frames = 0;
+    'frames: while frames < options.frames {
+        for event in options.input.iter().filter(|e| e.frame == frames) {
+            emu.key_put(&event.key, event.pressed);
+        }
+        for _ in 0..options.ipf {
+            if conditions && reached(&emu, options) {
+                code = EXIT_PASS;
+                break 'frames;
+            }
+            if let Err(e) = emu.step() {
+                eprintln!("fault at frame {}: {}", frames, e);
+                code = EXIT_FAIL;
+                break 'frames;
+            }
+        }
+        emu.timers_tick();
+        frames += 1;
+    }
+    if conditions && code == EXIT_FAIL && frames == options.frames && reached(&emu, options) {
+        code = EXIT_PASS;
+    }
+
+    if !options.quiet {
+        println!("frames {}", frames);
+        for register in Register::all() {
+            let value = register.read(&emu).unwrap_or(0);
+            println!(
+                "{:<3} {:0width$X}",
+                register.name(),
+                value,
+                width = register.size() * 2
+            );
+        }
+        println!("{}", if code == EXIT_PASS { "pass" } else { "fail" });
+    }
+    if let Some(path) = &options.screenshot {
+        if let Err(e) = fs::write(path, emu.get_display().to_pbm()) {
+            eprintln!("{}: {}", path, e);
+            return EXIT_USAGE;
+        }
+    }
+    code
+}
+
+fn main() {
+    let args: Vec<String> = env::args().skip(1).collect();
+    let options = match options(&args) {
+        Ok(options) => options,
+        Err(e) => {
+            eprintln!("rc201_8-run: {}", e);
+            eprint!("{}", USAGE);
+            process::exit(EXIT_USAGE);
+        }
+    };
+    process::exit(run(&options));
+}
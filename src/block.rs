@@ -0,0 +1,333 @@
+use crate::alu::{self, AluOp};
+use crate::cpu::{Cpu, CpuError};
+use crate::display::Display;
+use crate::emu::{Emu, MEM_SIZE, REG_SIZE};
+use crate::mem::{MemAccess, MemAccessKind, MemObserver};
+use crate::oper::{Oper, OperCode};
+use std::cell::RefCell;
+use std::rc::Rc;
+
+/// Most operations translated into a single block
+const BLOCK_SIZE: usize = 64_usize;
+
+/// Bytes of a single operation
+const OPER_SIZE: u16 = 2_u16;
+
+/// Pre-bound operation of a block
+type Thread<D> = Box<dyn Fn(&mut Emu<D>) -> Result<(), CpuError>>;
+
+///
+/// Straight sequence of operations ending on a branch, a skip, a draw or
+/// a memory store
+///
+struct Block<D: Display> {
+    /// Address after the last operation
+    end: u16,
+
+    /// Translated operations, in order
+    threads: Vec<Thread<D>>,
+
+    /// Whether the last operation leaves the program counter untouched
+    fallthrough: bool,
+}
+
+/// Addresses written since the last invalidation
+#[derive(Default)]
+struct Writes(Vec<usize>);
+
+impl MemObserver for Writes {
+    fn mem_access(&mut self, access: &MemAccess) {
+        if access.kind == MemAccessKind::Write {
+            self.0.push(access.address);
+        }
+    }
+}
+
+///
+/// Execution engine translating basic blocks into chains of pre-bound
+/// closures
+///
+/// A block is decoded once on its first visit and reused until one of
+/// its bytes is written. Loads, arithmetic and logic operations run as
+/// specialized closures, while every other operation keeps the one
+/// decoded on translation and runs it through `Emu::oper_exec`, so the
+/// results are the ones of `step`. The instruction fetches of translated
+/// blocks are not reported to memory observers.
+///
+/// # Example
+///
+/// ```
+/// use rc201_8::emu::Emu;
+/// use rc201_8::cpu::Cpu;
+/// use rc201_8::block::BlockEngine;
+/// use rc201_8::display::{Display, DisplayDummy};
+///
+/// let mut emu = Emu::new(DisplayDummy::new());
+/// let mut engine = BlockEngine::new(&mut emu);
+///
+/// // Count V0 up to 0x10 through V1, then loop forever
+/// engine
+///     .emu()
+///     .load_rom(&[0x61, 0x01, 0x80, 0x14, 0x30, 0x10, 0x12, 0x02, 0x12, 0x08])
+///     .unwrap();
+///
+/// assert_eq!(engine.run(100).unwrap(), 100);
+/// assert_eq!(engine.blocks(), 4);
+/// drop(engine);
+///
+/// assert_eq!(emu.reg_get(&0).unwrap(), 0x10);
+/// assert_eq!(emu.cnt_get(), 0x208);
+/// ```
+///
+pub struct BlockEngine<'a, D: Display> {
+    /// Emulator processing the blocks
+    emu: &'a mut Emu<D>,
+
+    /// Translated blocks, indexed by start address
+    blocks: Vec<Option<Block<D>>>,
+
+    /// Addresses written through the emulator memory
+    writes: Rc<RefCell<Writes>>,
+
+    /// Written addresses being invalidated, kept to reuse its capacity
+    pending: Vec<usize>,
+
+    /// Identifier of the write observer, removed on drop
+    observer: usize,
+}
+
+impl<'a, D: Display + 'static> BlockEngine<'a, D> {
+    ///
+    /// Returns a new BlockEngine instance, observing the memory writes
+    /// of a given emulator until it is dropped
+    ///
+    pub fn new(emu: &'a mut Emu<D>) -> BlockEngine<'a, D> {
+        let writes = Rc::new(RefCell::new(Writes::default()));
+        let observer = emu.mem_observer_add(Box::new(writes.clone()));
+        BlockEngine {
+            emu,
+            blocks: (0..MEM_SIZE).map(|_| None).collect(),
+            writes,
+            pending: Vec::new(),
+            observer,
+        }
+    }
+
+    ///
+    /// Return the emulator processing the blocks
+    ///
+    pub fn emu(&mut self) -> &mut Emu<D> {
+        self.emu
+    }
+
+    ///
+    /// Return the number of translated blocks
+    ///
+    pub fn blocks(&self) -> usize {
+        self.blocks.iter().filter(|b| b.is_some()).count()
+    }
+
+    ///
+    /// Process up to `limit` instructions, returning the processed count
+    ///
+    /// Blocks longer than the remaining instructions are processed one
+    /// instruction at a time, so the state after the call matches the
+    /// one after `limit` calls of `step`.
+    ///
+    pub fn run(&mut self, limit: usize) -> Result<usize, CpuError> {
+        let mut done = 0;
+        while done < limit {
+            self.invalidate();
+            let cnt = self.emu.cnt_get();
+            let slot = match self.blocks.get_mut(usize::from(cnt)) {
+                Some(slot) => slot,
+                None => {
+                    self.emu.step()?;
+                    done += 1;
+                    continue;
+                }
+            };
+            if slot.is_none() {
+                *slot = translate(self.emu, cnt);
+            }
+            let block = match slot {
+                Some(block) if block.threads.len() <= limit - done => block,
+                _ => {
+                    self.emu.step()?;
+                    done += 1;
+                    continue;
+                }
+            };
+            for thread in &block.threads {
+                thread(self.emu)?;
+            }
+            if block.fallthrough {
+                self.emu.cnt_put(block.end);
+            }
+            done += block.threads.len();
+        }
+        self.invalidate();
+        Ok(done)
+    }
+
+    /// Drop the blocks overlapping the written addresses
+    fn invalidate(&mut self) {
+        std::mem::swap(&mut self.pending, &mut self.writes.borrow_mut().0);
+        for address in self.pending.drain(..) {
+            let first = address.saturating_sub(BLOCK_SIZE * OPER_SIZE as usize);
+            for slot in &mut self.blocks[first..=address.min(MEM_SIZE - 1)] {
+                if slot.as_ref().is_some_and(|b| usize::from(b.end) > address) {
+                    *slot = None;
+                }
+            }
+        }
+    }
+}
+
+impl<'a, D: Display> Drop for BlockEngine<'a, D> {
+    fn drop(&mut self) {
+        self.emu.mem_observer_remove(self.observer);
+    }
+}
+
+/// Return the operation code on a given address, without notifying the
+/// memory observers
+fn peek<D: Display>(emu: &mut Emu<D>, address: u16) -> Option<u16> {
+    let cnt = emu.cnt_get();
+    emu.cnt_put(address);
+    let code = emu.fetch_opcode().ok();
+    emu.cnt_put(cnt);
+    code
+}
+
+/// Whether an operation ends a block
+fn terminates(oper: &OperCode) -> bool {
+    matches!(
+        oper,
+        OperCode::Flow00EE
+            | OperCode::Flow1NNN(_)
+            | OperCode::Flow2NNN(_)
+            | OperCode::FlowBNNN(_)
+            | OperCode::Cond3XNN(_, _)
+            | OperCode::Cond4XNN(_, _)
+            | OperCode::Cond5XY0(_, _)
+            | OperCode::Cond9XY0(_, _)
+            | OperCode::KeyOpEX9E(_)
+            | OperCode::KeyOpEXA1(_)
+            | OperCode::KeyOpFX0A(_)
+            | OperCode::DisplayDXYN(_, _, _)
+            | OperCode::BcdFX33(_)
+            | OperCode::MemFX55(_)
+            | OperCode::Unknown
+    )
+}
+
+/// Translate the block starting on a given address
+fn translate<D: Display + 'static>(emu: &mut Emu<D>, start: u16) -> Option<Block<D>> {
+    let mut threads = Vec::new();
+    let mut address = start;
+    let mut fallthrough = true;
+    while threads.len() < BLOCK_SIZE {
+        let code = match peek(emu, address) {
+            Some(code) => code,
+            None => break,
+        };
+        let oper = Oper::from_code(&code, &REG_SIZE);
+        let (thread, inline) = bind(oper, code, address + OPER_SIZE);
+        threads.push(thread);
+        address += OPER_SIZE;
+        fallthrough = inline;
+        if terminates(&oper) {
+            break;
+        }
+    }
+    if threads.is_empty() {
+        None
+    } else {
+        Some(Block {
+            end: address,
+            threads,
+            fallthrough,
+        })
+    }
+}
+
+/// Bind an operation to a closure, returning whether the closure leaves
+/// the program counter untouched
+fn bind<D: Display + 'static>(oper: OperCode, code: u16, next: u16) -> (Thread<D>, bool) {
+    match oper {
+        OperCode::Const6XNN(x, v) => (Box::new(move |e| e.reg_put(&x, v)), true),
+        OperCode::Const7XNN(x, v) => (
+            Box::new(move |e| {
+                let vx = e.reg_get(&x)?;
+                e.reg_put(&x, alu::add_nn(vx, v))
+            }),
+            true,
+        ),
+        OperCode::Assign8XY0(x, y) => (alu_thread(alu::assign, x, y), true),
+        OperCode::BitOp8XY1(x, y) => (alu_thread(alu::or, x, y), true),
+        OperCode::BitOp8XY2(x, y) => (alu_thread(alu::and, x, y), true),
+        OperCode::BitOp8XY3(x, y) => (alu_thread(alu::xor, x, y), true),
+        OperCode::Math8XY4(x, y) => (alu_thread(alu::add, x, y), true),
+        OperCode::Math8XY5(x, y) => (alu_thread(alu::sub, x, y), true),
+        OperCode::BitOp8XY6(x, y) => (alu_thread(alu::shr, x, y), true),
+        OperCode::Math8XY7(x, y) => (alu_thread(alu::subn, x, y), true),
+        OperCode::BitOp8XYE(x, y) => (alu_thread(alu::shl, x, y), true),
+        OperCode::Flow1NNN(v) => (
+            Box::new(move |e| {
+                e.cnt_put(v);
+                Ok(())
+            }),
+            false,
+        ),
+        OperCode::MemANNN(v) => (
+            Box::new(move |e| {
+                e.ind_put(v as usize);
+                Ok(())
+            }),
+            true,
+        ),
+        OperCode::TimerFX07(x) => (
+            Box::new(move |e| {
+                let dtm = e.dtm_get();
+                e.reg_put(&x, dtm)
+            }),
+            true,
+        ),
+        OperCode::TimerFX15(x) => (
+            Box::new(move |e| {
+                let vx = e.reg_get(&x)?;
+                e.dtm_put(vx);
+                Ok(())
+            }),
+            true,
+        ),
+        OperCode::SoundFX18(x) => (
+            Box::new(move |e| {
+                let vx = e.reg_get(&x)?;
+                e.stm_put(vx);
+                Ok(())
+            }),
+            true,
+        ),
+        _ => (
+            Box::new(move |e| {
+                e.cnt_put(next);
+                e.oper_exec(oper, code)
+            }),
+            false,
+        ),
+    }
+}
+
+/// Bind an `8XYN` operation to a closure, writing VF after VX
+fn alu_thread<D: Display + 'static>(op: AluOp, x: usize, y: usize) -> Thread<D> {
+    Box::new(move |e| {
+        let out = op(e.reg_get(&x)?, e.reg_get(&y)?, &e.quirks());
+        e.reg_put(&x, out.value)?;
+        if let Some(flag) = out.flag {
+            e.reg_put_vf(flag);
+        }
+        Ok(())
+    })
+}
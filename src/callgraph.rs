@@ -0,0 +1,118 @@
+use crate::cpu::{Cpu, CpuError};
+use std::collections::BTreeMap;
+use std::io::{self, Write};
+
+///
+/// Instruction counts attributed to the chain of active subroutines
+///
+/// Subroutines are tracked by watching the stack pointer: when it grows
+/// the new program counter is the called subroutine, and when it shrinks
+/// the innermost subroutine returned. Each instruction is attributed to
+/// the chain that was active when it was processed.
+///
+/// # Example
+///
+/// ```
+/// use rc201_8::emu::Emu;
+/// use rc201_8::callgraph::CallProfiler;
+/// use rc201_8::display::{Display, DisplayDummy};
+///
+/// let mut emu = Emu::new(DisplayDummy::new());
+/// let mut profiler = CallProfiler::new();
+///
+/// // Call 0x206 and loop; 0x206 assigns V0 and returns
+/// emu.load_rom(&[0x22, 0x06, 0x12, 0x00, 0x00, 0x00, 0x60, 0x01, 0x00, 0xEE])
+///     .unwrap();
+/// for _ in 0..8 {
+///     profiler.step(&mut emu).unwrap();
+/// }
+///
+/// let mut folded = Vec::new();
+/// profiler.write_folded(&mut folded).unwrap();
+///
+/// assert_eq!(
+///     String::from_utf8(folded).unwrap(),
+///     "main 4\nmain;sub_206 4\n"
+/// );
+/// ```
+///
+#[derive(Debug, Default)]
+pub struct CallProfiler {
+    /// Entry address of each active subroutine, outermost first
+    stack: Vec<u16>,
+
+    /// Instructions per chain of active subroutines
+    samples: BTreeMap<Vec<u16>, u64>,
+}
+
+impl CallProfiler {
+    ///
+    /// Returns a new CallProfiler instance with empty counters
+    ///
+    pub fn new() -> CallProfiler {
+        CallProfiler::default()
+    }
+
+    ///
+    /// Return the entry address of each active subroutine, outermost first
+    ///
+    pub fn stack(&self) -> &[u16] {
+        &self.stack
+    }
+
+    ///
+    /// Process a single instruction and attribute it to the active chain
+    ///
+    pub fn step<C: Cpu>(&mut self, cpu: &mut C) -> Result<(), CpuError> {
+        let spt = cpu.spt_get();
+        cpu.step()?;
+        *self.samples.entry(self.stack.clone()).or_insert(0) += 1;
+
+        let depth = cpu.spt_get();
+        if depth > spt {
+            // Frames skipped without a call, such as a stack pointer
+            // written by a debugger, are unknown
+            self.stack.resize(self.stack.len() + depth - spt - 1, 0);
+            self.stack.push(cpu.cnt_get());
+        } else if depth < spt {
+            let len = self.stack.len().saturating_sub(spt - depth);
+            self.stack.truncate(len);
+        }
+        Ok(())
+    }
+
+    ///
+    /// Return the instructions spent in each subroutine, including the
+    /// subroutines it called, most expensive first
+    ///
+    pub fn totals(&self) -> Vec<(u16, u64)> {
+        let mut totals = BTreeMap::new();
+        for (stack, n) in &self.samples {
+            let mut seen = Vec::with_capacity(stack.len());
+            for address in stack {
+                if !seen.contains(address) {
+                    seen.push(*address);
+                    *totals.entry(*address).or_insert(0) += n;
+                }
+            }
+        }
+        let mut totals: Vec<(u16, u64)> = totals.into_iter().collect();
+        totals.sort_by(|a, b| b.1.cmp(&a.1).then(a.0.cmp(&b.0)));
+        totals
+    }
+
+    ///
+    /// Write the counters in the folded-stack format used by flamegraph
+    /// tools: one `main;sub_NNN;sub_NNN count` line per chain
+    ///
+    pub fn write_folded<W: Write>(&self, sink: &mut W) -> io::Result<()> {
+        for (stack, n) in &self.samples {
+            let mut line = String::from("main");
+            for address in stack {
+                line.push_str(&format!(";sub_{:03x}", address));
+            }
+            writeln!(sink, "{} {}", line, n)?;
+        }
+        Ok(())
+    }
+}
@@ -0,0 +1,252 @@
+use crate::cpu::{Cpu, CpuError};
+use crate::emu::REG_SIZE;
+use crate::mem::Mem;
+use crate::oper::{Oper, OperCode};
+use std::io::{self, Write};
+use std::ops::Range;
+
+/// Address was fetched as the start of an instruction
+const COV_EXEC: u8 = 0x1_u8;
+
+/// Address was read as data
+const COV_READ: u8 = 0x2_u8;
+
+/// Address was written
+const COV_WRITE: u8 = 0x4_u8;
+
+///
+/// Execution coverage of the memory
+///
+/// Records the addresses fetched as instructions, the addresses read as
+/// data by `DisplayDXYN` and `MemFX65`, and the addresses written by
+/// `BcdFX33` and `MemFX55`.
+///
+/// # Example
+///
+/// ```
+/// use rc201_8::emu::Emu;
+/// use rc201_8::coverage::Coverage;
+/// use rc201_8::display::{Display, DisplayDummy};
+///
+/// let mut emu = Emu::new(DisplayDummy::new());
+/// let mut coverage = Coverage::new();
+///
+/// // Point I to 0x208, draw a 1 row sprite, store V0 and loop
+/// emu.load_rom(&[0xA2, 0x08, 0xD0, 0x01, 0xF0, 0x55, 0x12, 0x06, 0xFF])
+///     .unwrap();
+/// for _ in 0..5 {
+///     coverage.step(&mut emu).unwrap();
+/// }
+///
+/// assert!(coverage.executed(&0x204));
+/// assert_eq!(coverage.hits(&0x206), 2);
+/// assert!(coverage.read(&0x208));
+/// assert!(coverage.written(&0x208));
+/// assert!(!coverage.executed(&0x208));
+///
+/// let mut listing = Vec::new();
+/// coverage.write_listing(&emu, &mut listing, 0x200..0x209).unwrap();
+/// let listing = String::from_utf8(listing).unwrap();
+/// let lines: Vec<&str> = listing.lines().collect();
+///
+/// assert_eq!(lines[0], "0200 X--        1  A208  LD I, 0x208");
+/// assert_eq!(lines[4], "0208 -RW        0  00");
+/// ```
+///
+#[derive(Debug, Default)]
+pub struct Coverage {
+    /// Access flags per address
+    flags: Vec<u8>,
+
+    /// Executions per address
+    hits: Vec<u64>,
+}
+
+impl Coverage {
+    ///
+    /// Returns a new Coverage instance without recorded accesses
+    ///
+    pub fn new() -> Coverage {
+        Coverage::default()
+    }
+
+    ///
+    /// Process a single instruction and record its memory accesses
+    ///
+    pub fn step<C: Cpu + Mem>(&mut self, cpu: &mut C) -> Result<(), CpuError> {
+        if self.flags.len() < cpu.max_size() {
+            self.flags.resize(cpu.max_size(), 0);
+            self.hits.resize(cpu.max_size(), 0);
+        }
+
+        let cnt = cpu.cnt_get() as usize;
+        let ind = cpu.ind_get();
+        let oper = Oper::from_code(&cpu.fetch_opcode()?, &REG_SIZE);
+        cpu.step()?;
+
+        self.mark(cnt..cnt + 1, COV_EXEC);
+        if let Some(h) = self.hits.get_mut(cnt) {
+            *h += 1;
+        }
+        match oper {
+            OperCode::DisplayDXYN(_, _, n) => self.mark(ind..ind + n as usize, COV_READ),
+            OperCode::MemFX65(x) => self.mark(ind..ind + x + 1, COV_READ),
+            OperCode::MemFX55(x) => self.mark(ind..ind + x + 1, COV_WRITE),
+            OperCode::BcdFX33(_) => self.mark(ind..ind + 3, COV_WRITE),
+            _ => (),
+        }
+        Ok(())
+    }
+
+    /// Set a flag on a range of addresses
+    fn mark(&mut self, range: Range<usize>, flag: u8) {
+        let end = range.end.min(self.flags.len());
+        for f in self.flags.iter_mut().take(end).skip(range.start) {
+            *f |= flag;
+        }
+    }
+
+    /// Check a flag of a given address
+    fn flag(&self, address: &usize, flag: u8) -> bool {
+        self.flags.get(*address).is_some_and(|f| f & flag != 0)
+    }
+
+    ///
+    /// Check if an instruction was fetched from a given address
+    ///
+    pub fn executed(&self, address: &usize) -> bool {
+        self.flag(address, COV_EXEC)
+    }
+
+    ///
+    /// Check if a given address was read as data
+    ///
+    pub fn read(&self, address: &usize) -> bool {
+        self.flag(address, COV_READ)
+    }
+
+    ///
+    /// Check if a given address was written
+    ///
+    pub fn written(&self, address: &usize) -> bool {
+        self.flag(address, COV_WRITE)
+    }
+
+    ///
+    /// Return the times an instruction was fetched from a given address
+    ///
+    pub fn hits(&self, address: &usize) -> u64 {
+        self.hits.get(*address).cloned().unwrap_or(0)
+    }
+
+    ///
+    /// Write an annotated listing of a memory range
+    ///
+    /// Each line holds the address, the `XRW` access flags (executed,
+    /// read, written), the executions and the bytes. Executed addresses
+    /// show the instruction word and its mnemonic, other addresses a
+    /// single byte.
+    ///
+    pub fn write_listing<M: Mem, W: Write>(
+        &self,
+        mem: &M,
+        sink: &mut W,
+        range: Range<usize>,
+    ) -> io::Result<()> {
+        let mut address = range.start;
+        while address < range.end {
+            let byte = mem.mem_get(&address).copied().unwrap_or(0);
+            let flags = format!(
+                "{}{}{}",
+                if self.executed(&address) { 'X' } else { '-' },
+                if self.read(&address) { 'R' } else { '-' },
+                if self.written(&address) { 'W' } else { '-' }
+            );
+            let next = mem.mem_get(&(address + 1)).copied();
+            match next {
+                Ok(next) if self.executed(&address) => {
+                    let code = u16::from(byte) << 8 | u16::from(next);
+                    writeln!(
+                        sink,
+                        "{:04X} {} {:>8}  {:04X}  {}",
+                        address,
+                        flags,
+                        self.hits(&address),
+                        code,
+                        Oper::from_code(&code, &REG_SIZE)
+                    )?;
+                    address += 2;
+                }
+                _ => {
+                    writeln!(
+                        sink,
+                        "{:04X} {} {:>8}  {:02X}",
+                        address,
+                        flags,
+                        self.hits(&address),
+                        byte
+                    )?;
+                    address += 1;
+                }
+            }
+        }
+        Ok(())
+    }
+
+    ///
+    /// Write an LCOV tracefile of a memory range
+    ///
+    /// Line numbers are the instruction addresses. Every executed address
+    /// is reported as a line, odd ones included, and so is every other
+    /// even address, except the ones only accessed as data.
+    ///
+    /// # Example
+    ///
+    /// ```
+    /// use rc201_8::emu::Emu;
+    /// use rc201_8::coverage::Coverage;
+    /// use rc201_8::display::{Display, DisplayDummy};
+    ///
+    /// let mut emu = Emu::new(DisplayDummy::new());
+    /// let mut coverage = Coverage::new();
+    ///
+    /// // Jump over the instruction on 0x202
+    /// emu.load_rom(&[0x12, 0x04, 0x00, 0xE0, 0x12, 0x04]).unwrap();
+    /// for _ in 0..3 {
+    ///     coverage.step(&mut emu).unwrap();
+    /// }
+    ///
+    /// let mut lcov = Vec::new();
+    /// coverage.write_lcov(&mut lcov, "game.ch8", 0x200..0x206).unwrap();
+    ///
+    /// assert_eq!(
+    ///     String::from_utf8(lcov).unwrap(),
+    ///     "TN:\nSF:game.ch8\nDA:512,1\nDA:514,0\nDA:516,2\nLH:2\nLF:3\nend_of_record\n"
+    /// );
+    /// ```
+    ///
+    pub fn write_lcov<W: Write>(
+        &self,
+        sink: &mut W,
+        name: &str,
+        range: Range<usize>,
+    ) -> io::Result<()> {
+        writeln!(sink, "TN:")?;
+        writeln!(sink, "SF:{}", name)?;
+        let (mut found, mut hit) = (0, 0);
+        for address in range {
+            let data = self.read(&address) || self.written(&address);
+            if !self.executed(&address) && (data || address % 2 != 0) {
+                continue;
+            }
+            found += 1;
+            if self.executed(&address) {
+                hit += 1;
+            }
+            writeln!(sink, "DA:{},{}", address, self.hits(&address))?;
+        }
+        writeln!(sink, "LH:{}", hit)?;
+        writeln!(sink, "LF:{}", found)?;
+        writeln!(sink, "end_of_record")
+    }
+}
@@ -1,3 +1,5 @@
+use crate::audio::PATTERN_SIZE;
+use crate::mem::{MemError, MemErrorVariant};
 use std::fmt;
 
 ///
@@ -22,6 +24,69 @@ pub trait Cpu {
     ///
     fn recv_opcode(&mut self, code: &u16) -> Result<(), CpuError>;
 
+    ///
+    /// Return the operation code stored at the program counter
+    ///
+    /// # Example
+    ///
+    /// ```
+    /// use rc201_8::emu::Emu;
+    /// use rc201_8::cpu::Cpu;
+    /// use rc201_8::mem::Mem;
+    /// use rc201_8::display::{Display, DisplayDummy};
+    ///
+    /// let mut emu = Emu::new(DisplayDummy::new());
+    /// emu.mem_write(0..2, &[0x00, 0xE0]).unwrap();
+    ///
+    /// assert_eq!(emu.fetch_opcode().unwrap(), 0x00E0);
+    /// ```
+    ///
+    fn fetch_opcode(&self) -> Result<u16, CpuError>;
+
+    ///
+    /// Fetch the operation code at the program counter, advance the
+    /// program counter and process the operation
+    ///
+    /// # Example
+    ///
+    /// ```
+    /// use rc201_8::emu::Emu;
+    /// use rc201_8::cpu::Cpu;
+    /// use rc201_8::mem::Mem;
+    /// use rc201_8::display::{Display, DisplayDummy};
+    ///
+    /// let mut emu = Emu::new(DisplayDummy::new());
+    ///
+    /// // Set V3 to 0x2A
+    /// emu.mem_write(0..2, &[0x63, 0x2A]).unwrap();
+    /// emu.step().unwrap();
+    ///
+    /// assert_eq!(emu.reg_get(&3).unwrap(), 0x2A);
+    /// assert_eq!(emu.cnt_get(), 2);
+    /// ```
+    ///
+    fn step(&mut self) -> Result<(), CpuError>;
+
+    ///
+    /// Decrement the delay and sound timers, once per 60 Hz frame
+    ///
+    /// # Example
+    ///
+    /// ```
+    /// use rc201_8::emu::Emu;
+    /// use rc201_8::cpu::Cpu;
+    /// use rc201_8::display::{Display, DisplayDummy};
+    ///
+    /// let mut emu = Emu::new(DisplayDummy::new());
+    /// emu.dtm_put(1);
+    ///
+    /// emu.timers_tick();
+    /// emu.timers_tick();
+    /// assert_eq!(emu.dtm_get(), 0);
+    /// ```
+    ///
+    fn timers_tick(&mut self);
+
     ///
     /// Skip next processing instruction
     ///
@@ -47,16 +112,82 @@ pub trait Cpu {
     ///
     fn reg_put_vf(&mut self, value: u8);
 
+    ///
+    /// Return the program counter
+    ///
+    fn cnt_get(&self) -> u16;
+
+    ///
+    /// Put the value on the program counter
+    ///
+    fn cnt_put(&mut self, value: u16);
+
+    ///
+    /// Return the memory pointer
+    ///
+    fn ind_get(&self) -> usize;
+
+    ///
+    /// Put the value on the memory pointer
+    ///
+    fn ind_put(&mut self, value: usize);
+
+    ///
+    /// Return the delay timer
+    ///
+    fn dtm_get(&self) -> u8;
+
+    ///
+    /// Put the value on the delay timer
+    ///
+    fn dtm_put(&mut self, value: u8);
+
+    ///
+    /// Return the sound timer
+    ///
+    fn stm_get(&self) -> u8;
+
+    ///
+    /// Put the value on the sound timer
+    ///
+    fn stm_put(&mut self, value: u8);
+
+    ///
+    /// Return the XO-CHIP pitch register
+    ///
+    fn pit_get(&self) -> u8;
+
+    ///
+    /// Put the value on the XO-CHIP pitch register
+    ///
+    fn pit_put(&mut self, value: u8);
+
+    ///
+    /// Return the XO-CHIP audio pattern, or None if none was loaded
+    ///
+    fn pat_get(&self) -> Option<[u8; PATTERN_SIZE]>;
+
+    ///
+    /// Put the XO-CHIP audio pattern
+    ///
+    fn pat_put(&mut self, pattern: [u8; PATTERN_SIZE]);
+
     ///
     /// Validate a given stack pointer
     ///
     fn spt_validate(&self, spt: &usize) -> Result<usize, CpuError>;
 
     ///
-    /// Return the stack pointer
+    /// Return the stack pointer, which is the number of addresses on the
+    /// stack
     ///
     fn spt_get(&self) -> usize;
 
+    ///
+    /// Put the value on the stack pointer
+    ///
+    fn spt_put(&mut self, spt: usize) -> Result<(), CpuError>;
+
     ///
     /// Increment the stack pointer
     ///
@@ -68,10 +199,15 @@ pub trait Cpu {
     fn spt_dec(&mut self) -> Result<usize, CpuError>;
 
     ///
-    /// Return a stack pointer
+    /// Return the address on top of the stack
     ///
     fn stk_get(&self) -> Result<u16, CpuError>;
 
+    ///
+    /// Return the address stored on a given stack pointer
+    ///
+    fn stk_peek(&self, spt: &usize) -> Result<u16, CpuError>;
+
     ///
     /// Pop the address from the stack
     ///
@@ -81,6 +217,27 @@ pub trait Cpu {
     /// Push the address on the stack
     ///
     fn stk_push(&mut self, address: u16) -> Result<(), CpuError>;
+
+    ///
+    /// Return the return addresses on the stack, outermost first
+    ///
+    /// # Example
+    ///
+    /// ```
+    /// use rc201_8::emu::Emu;
+    /// use rc201_8::cpu::Cpu;
+    /// use rc201_8::display::{Display, DisplayDummy};
+    ///
+    /// let mut emu = Emu::new(DisplayDummy::new());
+    /// emu.load_rom(&[0x22, 0x04, 0x00, 0x00, 0x22, 0x08]).unwrap();
+    /// emu.step().unwrap();
+    /// emu.step().unwrap();
+    ///
+    /// assert_eq!(emu.call_stack(), &[0x202, 0x206]);
+    /// assert_eq!(emu.spt_get(), 2);
+    /// ```
+    ///
+    fn call_stack(&self) -> &[u16];
 }
 
 ///
@@ -102,6 +259,21 @@ pub enum CpuErrorVariant {
     /// Stack overflow
     ///
     StackOverflow(usize),
+
+    ///
+    /// Return or pop with an empty stack
+    ///
+    StackUnderflow,
+
+    ///
+    /// Program counter pointing outside of the memory range
+    ///
+    InvalidProgramCounter(u16),
+
+    ///
+    /// Operation accessing memory outside of the memory range
+    ///
+    InvalidMemoryAccess(usize),
 }
 
 impl fmt::Display for CpuErrorVariant {
@@ -140,9 +312,47 @@ impl CpuError {
                 (param, format!("Illegal operation code '{}'!", a))
             }
             CpuErrorVariant::StackOverflow(a) => (param, format!("Stack overflow '{}'!", a)),
+            CpuErrorVariant::StackUnderflow => (param, String::from("Stack underflow!")),
+            CpuErrorVariant::InvalidProgramCounter(a) => {
+                (param, format!("Illegal program counter '{}'!", a))
+            }
+            CpuErrorVariant::InvalidMemoryAccess(a) => {
+                (param, format!("Illegal memory access '{}'!", a))
+            }
         };
         CpuError { variant, message }
     }
+
+    ///
+    /// Return the variant of the error
+    ///
+    /// # Example
+    ///
+    /// ```
+    /// use rc201_8::emu::Emu;
+    /// use rc201_8::cpu::{Cpu, CpuErrorVariant};
+    /// use rc201_8::display::{Display, DisplayDummy};
+    ///
+    /// let mut emu = Emu::new(DisplayDummy::new());
+    ///
+    /// // Return without a call
+    /// let err = emu.recv_opcode(&0x00EE).unwrap_err();
+    /// assert!(matches!(err.variant(), CpuErrorVariant::StackUnderflow));
+    /// ```
+    ///
+    pub fn variant(&self) -> &CpuErrorVariant {
+        &self.variant
+    }
+}
+
+impl From<MemError> for CpuError {
+    fn from(err: MemError) -> CpuError {
+        let address = match err.variant() {
+            MemErrorVariant::AccessViolation(a) => *a,
+            MemErrorVariant::AccessRangeViolation(a, _) => *a,
+        };
+        CpuError::new(CpuErrorVariant::InvalidMemoryAccess(address))
+    }
 }
 
 impl fmt::Display for CpuError {
@@ -0,0 +1,653 @@
+use crate::cpu::{Cpu, CpuError};
+use crate::debugger::{Debugger, Register, StopReason};
+use crate::display::Display;
+use crate::emu::{Emu, REG_SIZE};
+use crate::oper::{Oper, OperCode};
+use serde_json::{json, Value};
+use std::collections::BTreeMap;
+use std::fs;
+use std::io::{self, BufRead, BufReader, Read, Write};
+use std::net::{TcpListener, ToSocketAddrs};
+use std::sync::mpsc::{self, Receiver, TryRecvError};
+use std::thread;
+
+/// Instructions processed between two request polls while running
+const RESUME_CHUNK: usize = 10_000_usize;
+
+/// Single thread reported to the client
+const THREAD_ID: i64 = 1_i64;
+
+/// Variables reference of the register scope
+const SCOPE_REGISTERS: i64 = 1_i64;
+
+/// Variables reference of the stack scope
+const SCOPE_STACK: i64 = 2_i64;
+
+/// Largest message body accepted from the client
+const CONTENT_MAX: usize = 0x10_0000_usize;
+
+///
+/// Mapping between assembler source lines and program addresses
+///
+/// The textual form has one `line address` pair per line, with the
+/// address in hexadecimal. Empty lines and lines starting with `#` are
+/// ignored.
+///
+/// # Example
+///
+/// ```
+/// use rc201_8::dap::SourceMap;
+///
+/// let map = SourceMap::parse("# main.8o\n3 0x200\n4 202\n").unwrap();
+///
+/// assert_eq!(map.address(&4), Some(0x202));
+/// assert_eq!(map.line(&0x200), Some(3));
+/// assert!(SourceMap::parse("3").is_none());
+/// ```
+///
+#[derive(Debug, Default)]
+pub struct SourceMap {
+    /// Addresses per source line
+    lines: BTreeMap<usize, u16>,
+}
+
+impl SourceMap {
+    ///
+    /// Returns a new SourceMap instance from its textual form
+    ///
+    pub fn parse(text: &str) -> Option<SourceMap> {
+        let mut lines = BTreeMap::new();
+        for entry in text.lines().map(str::trim) {
+            if entry.is_empty() || entry.starts_with('#') {
+                continue;
+            }
+            let mut fields = entry.split_whitespace();
+            let line = fields.next()?.parse().ok()?;
+            let address = fields.next()?;
+            let address = address.strip_prefix("0x").unwrap_or(address);
+            lines.insert(line, u16::from_str_radix(address, 16).ok()?);
+        }
+        Some(SourceMap { lines })
+    }
+
+    ///
+    /// Return the address of a given source line
+    ///
+    pub fn address(&self, line: &usize) -> Option<u16> {
+        self.lines.get(line).cloned()
+    }
+
+    ///
+    /// Return the source line of a given address
+    ///
+    pub fn line(&self, address: &u16) -> Option<usize> {
+        self.lines
+            .iter()
+            .find(|(_, a)| *a == address)
+            .map(|(l, _)| *l)
+    }
+}
+
+///
+/// Debug Adapter Protocol server
+///
+/// Requests are read by a background thread, so `pause` is served while
+/// the program runs. The `launch` request accepts the arguments
+/// `program` (ROM path), `stopOnEntry`, and optionally `source` and
+/// `sourceMap` (see `SourceMap`) for breakpoints by source line.
+/// Instruction breakpoints take the address as `instructionReference`.
+/// The `initialized` event follows a successful `launch`, so the client
+/// sets its breakpoints once the source mapping is known.
+///
+/// # Example
+///
+/// ```
+/// use rc201_8::emu::Emu;
+/// use rc201_8::dap::DapServer;
+/// use rc201_8::display::{Display, DisplayDummy};
+/// use std::io::Cursor;
+///
+/// let mut input = Vec::new();
+/// for body in &[
+///     r#"{"seq":1,"type":"request","command":"initialize","arguments":{}}"#,
+///     r#"{"seq":2,"type":"request","command":"threads"}"#,
+///     r#"{"seq":3,"type":"request","command":"disconnect"}"#,
+/// ] {
+///     input.extend(format!("Content-Length: {}\r\n\r\n{}", body.len(), body).bytes());
+/// }
+///
+/// let mut emu = Emu::new(DisplayDummy::new());
+/// let mut output = Vec::new();
+/// DapServer::new(Cursor::new(input), &mut output)
+///     .serve(&mut emu)
+///     .unwrap();
+/// let output = String::from_utf8(output).unwrap();
+///
+/// assert!(output.contains(r#""supportsConfigurationDoneRequest":true"#));
+/// assert!(output.contains(r#""threads":[{"id":1,"name":"chip8"}]"#));
+/// ```
+///
+pub struct DapServer<W: Write> {
+    /// Client output
+    writer: W,
+
+    /// Requests parsed by the reader thread
+    requests: Receiver<Value>,
+
+    /// Breakpoint and step machinery
+    debugger: Debugger,
+
+    /// Line mapping of the launched program
+    source_map: SourceMap,
+
+    /// Breakpoints set by source line
+    source_breakpoints: Vec<u16>,
+
+    /// Breakpoints set by address
+    instruction_breakpoints: Vec<u16>,
+
+    /// Source file of the launched program
+    source: Option<String>,
+
+    /// Whether the program is running
+    running: bool,
+
+    /// Whether the client asked to stop on entry
+    stop_on_entry: bool,
+
+    /// Whether the session is over
+    finished: bool,
+
+    /// Sequence of the next message
+    seq: i64,
+}
+
+impl<W: Write> DapServer<W> {
+    ///
+    /// Returns a new DapServer instance over a given reader and writer
+    ///
+    pub fn new<R: Read + Send + 'static>(reader: R, writer: W) -> DapServer<W> {
+        let (sender, requests) = mpsc::channel();
+        thread::spawn(move || {
+            let mut reader = BufReader::new(reader);
+            while let Ok(Some(message)) = message_recv(&mut reader) {
+                if sender.send(message).is_err() {
+                    break;
+                }
+            }
+        });
+        DapServer {
+            writer,
+            requests,
+            debugger: Debugger::new(),
+            source_map: SourceMap::default(),
+            source_breakpoints: Vec::new(),
+            instruction_breakpoints: Vec::new(),
+            source: None,
+            running: false,
+            stop_on_entry: false,
+            finished: false,
+            seq: 1,
+        }
+    }
+
+    ///
+    /// Serve requests until the client disconnects
+    ///
+    pub fn serve<D: Display>(&mut self, emu: &mut Emu<D>) -> io::Result<()> {
+        while !self.finished {
+            if self.running {
+                match self.requests.try_recv() {
+                    Ok(request) => self.request_handle(emu, &request)?,
+                    Err(TryRecvError::Empty) => {
+                        let reply = self.debugger.resume(emu, RESUME_CHUNK);
+                        self.stop(reply)?;
+                    }
+                    Err(TryRecvError::Disconnected) => break,
+                }
+            } else {
+                match self.requests.recv() {
+                    Ok(request) => self.request_handle(emu, &request)?,
+                    Err(_) => break,
+                }
+            }
+        }
+        Ok(())
+    }
+
+    /// Dispatch a request and send its response
+    fn request_handle<D: Display>(&mut self, emu: &mut Emu<D>, request: &Value) -> io::Result<()> {
+        let command = request["command"].as_str().unwrap_or("");
+        let arguments = &request["arguments"];
+        let mut launched = false;
+        let body = match command {
+            "initialize" => Ok(json!({
+                "supportsConfigurationDoneRequest": true,
+                "supportsInstructionBreakpoints": true,
+                "supportsSetVariable": true,
+                "supportsSteppingGranularity": true,
+            })),
+            "launch" => {
+                let body = self.launch(emu, arguments);
+                launched = body.is_ok();
+                body
+            }
+            "setBreakpoints" => Ok(self.breakpoints_set(arguments)),
+            "setInstructionBreakpoints" => Ok(self.instruction_breakpoints_set(arguments)),
+            "setExceptionBreakpoints" => Ok(json!({})),
+            "configurationDone" => {
+                self.running = !self.stop_on_entry;
+                Ok(json!({}))
+            }
+            "threads" => Ok(json!({ "threads": [{ "id": THREAD_ID, "name": "chip8" }] })),
+            "stackTrace" => Ok(self.stack_trace(emu)),
+            "scopes" => Ok(json!({ "scopes": [
+                { "name": "Registers", "variablesReference": SCOPE_REGISTERS, "expensive": false },
+                { "name": "Stack", "variablesReference": SCOPE_STACK, "expensive": false },
+            ] })),
+            "variables" => Ok(variables(emu, arguments["variablesReference"].as_i64())),
+            "setVariable" => variable_set(emu, arguments),
+            "continue" => {
+                self.running = true;
+                Ok(json!({ "allThreadsContinued": true }))
+            }
+            "pause" => Ok(json!({})),
+            "next" | "stepIn" | "stepOut" => Ok(json!({})),
+            "disconnect" | "terminate" => {
+                self.finished = true;
+                Ok(json!({}))
+            }
+            _ => Err(format!("Unsupported command '{}'!", command)),
+        };
+        self.response_send(request, command, body)?;
+
+        match command {
+            "configurationDone" if self.stop_on_entry => self.event_stopped("entry", None),
+            // Breakpoints are only configured once the source map is loaded
+            "launch" if launched => self.event_send("initialized", json!({})),
+            "pause" if self.running => {
+                self.running = false;
+                self.event_stopped("pause", None)
+            }
+            "next" => {
+                let reply = step_over(&self.debugger, emu);
+                self.stop(reply)
+            }
+            "stepIn" => {
+                let reply = self.debugger.step(emu);
+                self.stop(reply)
+            }
+            "stepOut" => {
+                let reply = step_out(&self.debugger, emu);
+                self.stop(reply)
+            }
+            "disconnect" | "terminate" => self.event_send("terminated", json!({})),
+            _ => Ok(()),
+        }
+    }
+
+    /// Load the program and the optional source mapping
+    fn launch<D: Display>(&mut self, emu: &mut Emu<D>, arguments: &Value) -> Result<Value, String> {
+        let program = arguments["program"]
+            .as_str()
+            .ok_or_else(|| String::from("Missing 'program' argument!"))?;
+        let rom = fs::read(program).map_err(|e| format!("{}: {}", program, e))?;
+        emu.load_rom(&rom).map_err(|e| e.to_string())?;
+        if let Some(path) = arguments["sourceMap"].as_str() {
+            let text = fs::read_to_string(path).map_err(|e| format!("{}: {}", path, e))?;
+            self.source_map =
+                SourceMap::parse(&text).ok_or_else(|| format!("Invalid source map '{}'!", path))?;
+        }
+        self.source = arguments["source"].as_str().map(String::from);
+        self.stop_on_entry = arguments["stopOnEntry"].as_bool().unwrap_or(false);
+        Ok(json!({}))
+    }
+
+    /// Replace the breakpoints of a source through the source mapping;
+    /// sources other than the launched one are left unverified
+    fn breakpoints_set(&mut self, arguments: &Value) -> Value {
+        let path = arguments["source"]["path"].as_str();
+        let mapped = match self.source.as_deref() {
+            Some(source) => path == Some(source),
+            None => true,
+        };
+        if !mapped {
+            let breakpoints: Vec<Value> = arguments["breakpoints"]
+                .as_array()
+                .map(|b| b.as_slice())
+                .unwrap_or(&[])
+                .iter()
+                .map(|b| json!({ "verified": false, "line": b["line"] }))
+                .collect();
+            return json!({ "breakpoints": breakpoints });
+        }
+        self.source_breakpoints.clear();
+        let breakpoints: Vec<Value> = arguments["breakpoints"]
+            .as_array()
+            .map(|b| b.as_slice())
+            .unwrap_or(&[])
+            .iter()
+            .map(|b| {
+                let line = b["line"].as_u64().unwrap_or(0) as usize;
+                match self.source_map.address(&line) {
+                    Some(address) => {
+                        self.source_breakpoints.push(address);
+                        json!({ "verified": true, "line": line })
+                    }
+                    None => json!({ "verified": false, "line": line }),
+                }
+            })
+            .collect();
+        self.breakpoints_refresh();
+        json!({ "breakpoints": breakpoints })
+    }
+
+    /// Replace the breakpoints set by address
+    fn instruction_breakpoints_set(&mut self, arguments: &Value) -> Value {
+        self.instruction_breakpoints.clear();
+        let breakpoints: Vec<Value> = arguments["breakpoints"]
+            .as_array()
+            .map(|b| b.as_slice())
+            .unwrap_or(&[])
+            .iter()
+            .map(|b| {
+                let offset = b["offset"].as_i64().unwrap_or(0);
+                match b["instructionReference"].as_str().and_then(address_parse) {
+                    Some(address) => {
+                        let address = (i64::from(address) + offset) as u16;
+                        self.instruction_breakpoints.push(address);
+                        json!({ "verified": true, "instructionReference": address_format(address) })
+                    }
+                    None => json!({ "verified": false }),
+                }
+            })
+            .collect();
+        self.breakpoints_refresh();
+        json!({ "breakpoints": breakpoints })
+    }
+
+    /// Rebuild the debugger breakpoints from both kinds
+    fn breakpoints_refresh(&mut self) {
+        self.debugger.breakpoint_clear();
+        for address in self
+            .source_breakpoints
+            .iter()
+            .chain(self.instruction_breakpoints.iter())
+        {
+            self.debugger.breakpoint_insert(*address);
+        }
+    }
+
+    /// Frames for the program counter and the return addresses on the stack
+    fn stack_trace<D: Display>(&self, emu: &Emu<D>) -> Value {
+        let mut addresses = vec![emu.cnt_get()];
+        addresses.extend(emu.call_stack().iter().rev());
+        let frames: Vec<Value> = addresses
+            .iter()
+            .enumerate()
+            .map(|(id, address)| {
+                let mut frame = json!({
+                    "id": id,
+                    "name": address_format(*address),
+                    "instructionPointerReference": address_format(*address),
+                    "line": 0,
+                    "column": 0,
+                });
+                if let (Some(source), Some(line)) = (&self.source, self.source_map.line(address)) {
+                    frame["source"] = json!({ "path": source });
+                    frame["line"] = json!(line);
+                }
+                frame
+            })
+            .collect();
+        json!({ "stackFrames": frames, "totalFrames": addresses.len() })
+    }
+
+    /// Report the outcome of a run or step to the client
+    fn stop(&mut self, reply: Result<StopReason, CpuError>) -> io::Result<()> {
+        match reply {
+            Ok(StopReason::Limit) => Ok(()),
+            Ok(StopReason::Breakpoint(_)) => {
+                self.running = false;
+                self.event_stopped("breakpoint", None)
+            }
+            Ok(StopReason::Step) => self.event_stopped("step", None),
+            Err(e) => {
+                self.running = false;
+                self.event_stopped("exception", Some(e.to_string()))
+            }
+        }
+    }
+
+    /// Send a stopped event
+    fn event_stopped(&mut self, reason: &str, text: Option<String>) -> io::Result<()> {
+        let mut body = json!({
+            "reason": reason,
+            "threadId": THREAD_ID,
+            "allThreadsStopped": true,
+        });
+        if let Some(text) = text {
+            body["text"] = json!(text);
+        }
+        self.event_send("stopped", body)
+    }
+
+    /// Send an event
+    fn event_send(&mut self, event: &str, body: Value) -> io::Result<()> {
+        let message = json!({
+            "seq": self.seq,
+            "type": "event",
+            "event": event,
+            "body": body,
+        });
+        self.message_send(&message)
+    }
+
+    /// Send the response of a request
+    fn response_send(
+        &mut self,
+        request: &Value,
+        command: &str,
+        body: Result<Value, String>,
+    ) -> io::Result<()> {
+        let mut message = json!({
+            "seq": self.seq,
+            "type": "response",
+            "request_seq": request["seq"],
+            "command": command,
+        });
+        match body {
+            Ok(body) => {
+                message["success"] = json!(true);
+                message["body"] = body;
+            }
+            Err(e) => {
+                message["success"] = json!(false);
+                message["message"] = json!(e);
+            }
+        }
+        self.message_send(&message)
+    }
+
+    /// Frame and send a message
+    fn message_send(&mut self, message: &Value) -> io::Result<()> {
+        let content = message.to_string();
+        write!(
+            self.writer,
+            "Content-Length: {}\r\n\r\n{}",
+            content.len(),
+            content
+        )?;
+        self.writer.flush()?;
+        self.seq += 1;
+        Ok(())
+    }
+}
+
+///
+/// Serve a single client over stdin and stdout
+///
+/// # Example
+///
+/// ```no_run
+/// use rc201_8::emu::Emu;
+/// use rc201_8::dap;
+/// use rc201_8::display::{Display, DisplayDummy};
+///
+/// let mut emu = Emu::new(DisplayDummy::new());
+/// dap::stdio(&mut emu).unwrap();
+/// ```
+///
+pub fn stdio<D: Display>(emu: &mut Emu<D>) -> io::Result<()> {
+    DapServer::new(io::stdin(), io::stdout()).serve(emu)
+}
+
+///
+/// Wait for a single client on a given address and serve it
+///
+pub fn listen<D: Display, A: ToSocketAddrs>(emu: &mut Emu<D>, addr: A) -> io::Result<()> {
+    let listener = TcpListener::bind(addr)?;
+    let (stream, _) = listener.accept()?;
+    DapServer::new(stream.try_clone()?, stream).serve(emu)
+}
+
+/// Read the next message, returning None at the end of the input
+fn message_recv<R: BufRead>(reader: &mut R) -> io::Result<Option<Value>> {
+    let mut length = None;
+    loop {
+        let mut header = String::new();
+        if reader.read_line(&mut header)? == 0 {
+            return Ok(None);
+        }
+        let header = header.trim();
+        if header.is_empty() {
+            break;
+        }
+        if let Some(value) = header.strip_prefix("Content-Length:") {
+            length = value.trim().parse::<usize>().ok();
+        }
+    }
+    let length = length.unwrap_or(0);
+    if length > CONTENT_MAX {
+        return Err(io::Error::new(
+            io::ErrorKind::InvalidData,
+            format!("Content length {} exceeds {}!", length, CONTENT_MAX),
+        ));
+    }
+    let mut content = vec![0_u8; length];
+    reader.read_exact(&mut content)?;
+    serde_json::from_slice(&content)
+        .map(Some)
+        .map_err(|e| io::Error::new(io::ErrorKind::InvalidData, e))
+}
+
+/// Parse an address such as `0x200`
+fn address_parse(address: &str) -> Option<u16> {
+    let address = address.strip_prefix("0x").unwrap_or(address);
+    u16::from_str_radix(address, 16).ok()
+}
+
+/// Format an address such as `0x0200`
+fn address_format(address: u16) -> String {
+    format!("0x{:04x}", address)
+}
+
+/// List the variables of a scope
+fn variables<C: Cpu>(cpu: &C, reference: Option<i64>) -> Value {
+    let variables: Vec<Value> = match reference {
+        Some(SCOPE_REGISTERS) => Register::all()
+            .iter()
+            .map(|r| {
+                let value = r.read(cpu).unwrap_or(0);
+                let value = if r.size() == 2 {
+                    format!("0x{:04x}", value)
+                } else {
+                    format!("0x{:02x}", value)
+                };
+                json!({ "name": r.name(), "value": value, "variablesReference": 0 })
+            })
+            .chain(std::iter::once(pattern_variable(cpu)))
+            .collect(),
+        Some(SCOPE_STACK) => cpu
+            .call_stack()
+            .iter()
+            .enumerate()
+            .map(|(i, a)| {
+                json!({
+                    "name": format!("[{}]", i),
+                    "value": address_format(*a),
+                    "variablesReference": 0,
+                })
+            })
+            .collect(),
+        _ => Vec::new(),
+    };
+    json!({ "variables": variables })
+}
+
+/// Describe the XO-CHIP audio pattern as a read-only variable
+fn pattern_variable<C: Cpu>(cpu: &C) -> Value {
+    let value = match cpu.pat_get() {
+        Some(pattern) => pattern.iter().map(|b| format!("{:02x}", b)).collect(),
+        None => String::from("none"),
+    };
+    json!({ "name": "pattern", "value": value, "variablesReference": 0 })
+}
+
+/// Write a register from the register scope
+fn variable_set<C: Cpu>(cpu: &mut C, arguments: &Value) -> Result<Value, String> {
+    if arguments["variablesReference"].as_i64() != Some(SCOPE_REGISTERS) {
+        return Err(String::from("Only registers can be written!"));
+    }
+    let name = arguments["name"].as_str().unwrap_or("");
+    let register = Register::all()
+        .into_iter()
+        .find(|r| r.name() == name)
+        .ok_or_else(|| format!("Unknown register '{}'!", name))?;
+    let value = arguments["value"].as_str().unwrap_or("");
+    let value = match value.strip_prefix("0x") {
+        Some(hex) => u16::from_str_radix(hex, 16),
+        None => value.parse(),
+    }
+    .map_err(|_| format!("Invalid value '{}'!", value))?;
+    register.write(cpu, value).map_err(|e| e.to_string())?;
+    Ok(json!({ "value": arguments["value"] }))
+}
+
+/// Step a single instruction, running subroutine calls to their return
+/// unless a breakpoint is hit inside
+fn step_over<C: Cpu>(debugger: &Debugger, cpu: &mut C) -> Result<StopReason, CpuError> {
+    let code = cpu.fetch_opcode()?;
+    let reply = debugger.step(cpu)?;
+    match (Oper::from_code(&code, &REG_SIZE), reply) {
+        (_, StopReason::Breakpoint(a)) => Ok(StopReason::Breakpoint(a)),
+        (OperCode::Flow2NNN(_), _) => step_until_return(debugger, cpu, cpu.spt_get()),
+        (_, reply) => Ok(reply),
+    }
+}
+
+/// Run until the current subroutine returns
+fn step_out<C: Cpu>(debugger: &Debugger, cpu: &mut C) -> Result<StopReason, CpuError> {
+    match cpu.spt_get() {
+        0 => debugger.step(cpu),
+        spt => step_until_return(debugger, cpu, spt),
+    }
+}
+
+/// Run until the stack pointer drops below a given depth, giving up
+/// after `RESUME_CHUNK` instructions
+fn step_until_return<C: Cpu>(
+    debugger: &Debugger,
+    cpu: &mut C,
+    spt: usize,
+) -> Result<StopReason, CpuError> {
+    for _ in 0..RESUME_CHUNK {
+        if let StopReason::Breakpoint(a) = debugger.step(cpu)? {
+            return Ok(StopReason::Breakpoint(a));
+        }
+        if cpu.spt_get() < spt {
+            break;
+        }
+    }
+    Ok(StopReason::Step)
+}
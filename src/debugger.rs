@@ -0,0 +1,312 @@
+use crate::cpu::{Cpu, CpuError};
+use std::collections::BTreeSet;
+
+///
+/// Registers exposed to debugger frontends
+///
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Register {
+    ///
+    /// General purpose register V0-VF
+    ///
+    V(usize),
+
+    ///
+    /// Memory pointer
+    ///
+    I,
+
+    ///
+    /// Program counter
+    ///
+    PC,
+
+    ///
+    /// Stack pointer
+    ///
+    SP,
+
+    ///
+    /// Delay timer
+    ///
+    DT,
+
+    ///
+    /// Sound timer
+    ///
+    ST,
+
+    ///
+    /// XO-CHIP pitch register
+    ///
+    PITCH,
+}
+
+impl Register {
+    ///
+    /// Returns the register file in the order used by remote debuggers:
+    /// V0-VF, I, PC, SP, DT, ST and PITCH
+    ///
+    /// # Example
+    ///
+    /// ```
+    /// use rc201_8::debugger::Register;
+    ///
+    /// let all = Register::all();
+    ///
+    /// assert_eq!(all.len(), 22);
+    /// assert_eq!(all[16], Register::I);
+    /// ```
+    ///
+    pub fn all() -> Vec<Register> {
+        let mut all: Vec<Register> = (0..16).map(Register::V).collect();
+        all.extend_from_slice(&[
+            Register::I,
+            Register::PC,
+            Register::SP,
+            Register::DT,
+            Register::ST,
+            Register::PITCH,
+        ]);
+        all
+    }
+
+    ///
+    /// Returns the lower case name of the register
+    ///
+    /// # Example
+    ///
+    /// ```
+    /// use rc201_8::debugger::Register;
+    ///
+    /// assert_eq!(Register::V(10).name(), "va");
+    /// assert_eq!(Register::PC.name(), "pc");
+    /// ```
+    ///
+    pub fn name(&self) -> String {
+        match self {
+            Register::V(x) => format!("v{:x}", x),
+            Register::I => String::from("i"),
+            Register::PC => String::from("pc"),
+            Register::SP => String::from("sp"),
+            Register::DT => String::from("dt"),
+            Register::ST => String::from("st"),
+            Register::PITCH => String::from("pitch"),
+        }
+    }
+
+    ///
+    /// Returns the size of the register in bytes
+    ///
+    pub fn size(&self) -> usize {
+        match self {
+            Register::I | Register::PC => 2,
+            _ => 1,
+        }
+    }
+
+    ///
+    /// Read the register value from a given cpu
+    ///
+    /// # Example
+    ///
+    /// ```
+    /// use rc201_8::emu::Emu;
+    /// use rc201_8::cpu::Cpu;
+    /// use rc201_8::debugger::Register;
+    /// use rc201_8::display::{Display, DisplayDummy};
+    ///
+    /// let mut emu = Emu::new(DisplayDummy::new());
+    /// emu.reg_put(&4, 0x12).unwrap();
+    ///
+    /// assert_eq!(Register::V(4).read(&emu).unwrap(), 0x12);
+    /// ```
+    ///
+    pub fn read<C: Cpu>(&self, cpu: &C) -> Result<u16, CpuError> {
+        match self {
+            Register::V(x) => Ok(u16::from(cpu.reg_get(x)?)),
+            Register::I => Ok(cpu.ind_get() as u16),
+            Register::PC => Ok(cpu.cnt_get()),
+            Register::SP => Ok(cpu.spt_get() as u16),
+            Register::DT => Ok(u16::from(cpu.dtm_get())),
+            Register::ST => Ok(u16::from(cpu.stm_get())),
+            Register::PITCH => Ok(u16::from(cpu.pit_get())),
+        }
+    }
+
+    ///
+    /// Write the register value into a given cpu
+    ///
+    /// Values wider than the register are truncated
+    ///
+    /// # Example
+    ///
+    /// ```
+    /// use rc201_8::emu::Emu;
+    /// use rc201_8::cpu::Cpu;
+    /// use rc201_8::debugger::Register;
+    /// use rc201_8::display::{Display, DisplayDummy};
+    ///
+    /// let mut emu = Emu::new(DisplayDummy::new());
+    /// Register::PC.write(&mut emu, 0x200).unwrap();
+    ///
+    /// assert_eq!(emu.cnt_get(), 0x200);
+    /// ```
+    ///
+    pub fn write<C: Cpu>(&self, cpu: &mut C, value: u16) -> Result<(), CpuError> {
+        match self {
+            Register::V(x) => cpu.reg_put(x, value as u8),
+            Register::I => {
+                cpu.ind_put(value as usize);
+                Ok(())
+            }
+            Register::PC => {
+                cpu.cnt_put(value);
+                Ok(())
+            }
+            Register::SP => cpu.spt_put(value as usize),
+            Register::DT => {
+                cpu.dtm_put(value as u8);
+                Ok(())
+            }
+            Register::ST => {
+                cpu.stm_put(value as u8);
+                Ok(())
+            }
+            Register::PITCH => {
+                cpu.pit_put(value as u8);
+                Ok(())
+            }
+        }
+    }
+}
+
+///
+/// Reasons for the debugger to hand the control back
+///
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum StopReason {
+    ///
+    /// A single instruction was processed
+    ///
+    Step,
+
+    ///
+    /// The program counter reached a breakpoint
+    ///
+    Breakpoint(u16),
+
+    ///
+    /// The maximum number of instructions was processed
+    ///
+    Limit,
+}
+
+///
+/// Breakpoint and step machinery shared by the debugger frontends
+///
+/// # Example
+///
+/// ```
+/// use rc201_8::emu::Emu;
+/// use rc201_8::cpu::Cpu;
+/// use rc201_8::mem::Mem;
+/// use rc201_8::debugger::{Debugger, StopReason};
+/// use rc201_8::display::{Display, DisplayDummy};
+///
+/// let mut emu = Emu::new(DisplayDummy::new());
+/// let mut debugger = Debugger::new();
+///
+/// // Three assignments followed by an infinite loop
+/// emu.mem_write(0..8, &[0x60, 0x01, 0x61, 0x02, 0x62, 0x03, 0x10, 0x06])
+///     .unwrap();
+/// debugger.breakpoint_insert(4);
+///
+/// assert_eq!(debugger.resume(&mut emu, 100).unwrap(), StopReason::Breakpoint(4));
+/// assert_eq!(debugger.step(&mut emu).unwrap(), StopReason::Step);
+/// assert_eq!(debugger.resume(&mut emu, 100).unwrap(), StopReason::Limit);
+/// assert_eq!(emu.reg_get(&2).unwrap(), 3);
+/// ```
+///
+#[derive(Debug, Default)]
+pub struct Debugger {
+    /// Software breakpoints
+    breakpoints: BTreeSet<u16>,
+}
+
+impl Debugger {
+    ///
+    /// Returns a new Debugger instance without breakpoints
+    ///
+    pub fn new() -> Debugger {
+        Debugger {
+            breakpoints: BTreeSet::new(),
+        }
+    }
+
+    ///
+    /// Insert a breakpoint, returning false if it was already set
+    ///
+    pub fn breakpoint_insert(&mut self, address: u16) -> bool {
+        self.breakpoints.insert(address)
+    }
+
+    ///
+    /// Remove a breakpoint, returning false if it was not set
+    ///
+    pub fn breakpoint_remove(&mut self, address: u16) -> bool {
+        self.breakpoints.remove(&address)
+    }
+
+    ///
+    /// Remove all breakpoints
+    ///
+    pub fn breakpoint_clear(&mut self) {
+        self.breakpoints.clear();
+    }
+
+    ///
+    /// Check if there is a breakpoint on a given address
+    ///
+    pub fn breakpoint_contains(&self, address: &u16) -> bool {
+        self.breakpoints.contains(address)
+    }
+
+    ///
+    /// Return the breakpoint addresses in ascending order
+    ///
+    pub fn breakpoints(&self) -> Vec<u16> {
+        self.breakpoints.iter().cloned().collect()
+    }
+
+    ///
+    /// Process a single instruction
+    ///
+    /// Breakpoints are reported only if the new program counter hits one
+    ///
+    pub fn step<C: Cpu>(&self, cpu: &mut C) -> Result<StopReason, CpuError> {
+        cpu.step()?;
+        let cnt = cpu.cnt_get();
+        if self.breakpoint_contains(&cnt) {
+            Ok(StopReason::Breakpoint(cnt))
+        } else {
+            Ok(StopReason::Step)
+        }
+    }
+
+    ///
+    /// Process instructions until a breakpoint is reached or `limit`
+    /// instructions were processed
+    ///
+    /// The first instruction is always processed, so resuming from a
+    /// breakpoint will not stop on the same address again
+    ///
+    pub fn resume<C: Cpu>(&self, cpu: &mut C, limit: usize) -> Result<StopReason, CpuError> {
+        for _ in 0..limit {
+            if let StopReason::Breakpoint(a) = self.step(cpu)? {
+                return Ok(StopReason::Breakpoint(a));
+            }
+        }
+        Ok(StopReason::Limit)
+    }
+}
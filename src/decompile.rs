@@ -0,0 +1,586 @@
+use crate::analysis::Cfg;
+use crate::emu::{PRG_INCR, PRG_START};
+use crate::oper::OperCode;
+use std::collections::BTreeSet;
+use std::io::{self, Write};
+
+/// Indentation of a nesting level
+const INDENT: &str = "    ";
+
+///
+/// Decompiler of a ROM to C-like pseudocode
+///
+/// Every routine of the control-flow graph becomes a function: `main`
+/// for the one on `PRG_START` and `sub_NNN` for the subroutines. The
+/// registers are the variables `v0` to `vf` and `i`, and the timers
+/// `delay` and `sound`. Operations that set the flag register show it as
+/// a `vf = …` statement after their own.
+///
+/// Conditions followed by a jump are recovered as `if`, `if`/`else`,
+/// `while` and `do`/`while` structures, and conditions followed by any
+/// other operation as a guarded statement. Control flow matching none of
+/// these idioms is kept as `goto` to `label_NNN`, or `loop_NNN` for the
+/// targets of backward jumps.
+///
+/// # Example
+///
+/// ```
+/// use rc201_8::decompile::Decompiler;
+///
+/// // Count V3 up to 0x20, then loop forever
+/// let decompiler = Decompiler::new(&[
+///     0x63, 0x00, 0x73, 0x01, 0x33, 0x20, 0x12, 0x02, 0x12, 0x08,
+/// ]);
+///
+/// assert_eq!(
+///     decompiler.function(0x200).unwrap(),
+///     "void main(void)
+/// {
+///     v3 = 0x00;
+///     do {
+///         v3 += 0x01;
+///     } while (v3 != 0x20);
+///     for (;;) {
+///     }
+/// }
+/// "
+/// );
+/// ```
+///
+#[derive(Debug, Clone)]
+pub struct Decompiler {
+    /// Control-flow graph of the ROM
+    cfg: Cfg,
+}
+
+impl Decompiler {
+    ///
+    /// Returns a new Decompiler of a ROM loaded on `PRG_START`
+    ///
+    pub fn new(rom: &[u8]) -> Decompiler {
+        Decompiler { cfg: Cfg::new(rom) }
+    }
+
+    ///
+    /// Return the control-flow graph of the ROM
+    ///
+    pub fn cfg(&self) -> &Cfg {
+        &self.cfg
+    }
+
+    ///
+    /// Return the pseudocode of the function on a given entry, or None if
+    /// no block starts on it
+    ///
+    pub fn function(&self, entry: u16) -> Option<String> {
+        self.cfg.block(entry)?;
+        let mut opers: Vec<(u16, OperCode)> = self
+            .cfg
+            .local_blocks(entry)
+            .iter()
+            .filter_map(|start| self.cfg.block(*start))
+            .flat_map(|block| block.opers.iter().cloned())
+            .collect();
+        opers.sort_by_key(|(address, _)| *address);
+
+        // Contiguous runs of operations
+        let mut runs: Vec<Run> = Vec::new();
+        for (address, oper) in opers {
+            match runs.last_mut() {
+                Some(run) if run.end() == address => run.opers.push(oper),
+                _ => runs.push(Run {
+                    start: address,
+                    opers: vec![oper],
+                }),
+            }
+        }
+
+        let body = match Builder::new(&runs, true).build() {
+            Some(body) => body,
+            None => Builder::new(&runs, false)
+                .build()
+                .expect("unstructured functions label every operation"),
+        };
+
+        let mut text = format!("void {}(void)\n{{\n", name(entry));
+        render(&body, 1, &mut text);
+        text.push_str("}\n");
+        Some(text)
+    }
+
+    ///
+    /// Write the pseudocode of `main` followed by every subroutine, in
+    /// entry order
+    ///
+    pub fn write_pseudocode<W: Write>(&self, sink: &mut W) -> io::Result<()> {
+        let entries = Some(PRG_START as u16).into_iter().chain(
+            self.cfg
+                .subroutines()
+                .iter()
+                .map(|s| s.entry)
+                .filter(|entry| *entry != PRG_START as u16),
+        );
+        let mut first = true;
+        for entry in entries {
+            if let Some(function) = self.function(entry) {
+                if !first {
+                    writeln!(sink)?;
+                }
+                write!(sink, "{}", function)?;
+                first = false;
+            }
+        }
+        Ok(())
+    }
+}
+
+/// Statement of the pseudocode
+enum Stmt {
+    /// Single line
+    Line(String),
+
+    /// Target of a goto, and its name
+    Label(u16, String),
+
+    /// Block between an opening and a closing line
+    Nested {
+        open: String,
+        body: Vec<Stmt>,
+        close: String,
+    },
+
+    /// Condition with both branches
+    IfElse {
+        cond: String,
+        then: Vec<Stmt>,
+        otherwise: Vec<Stmt>,
+    },
+}
+
+/// Operations on consecutive addresses
+struct Run {
+    /// Address of the first operation
+    start: u16,
+
+    /// Operations, in order
+    opers: Vec<OperCode>,
+}
+
+impl Run {
+    /// Address of an operation
+    fn address(&self, index: usize) -> u16 {
+        self.start.wrapping_add(index as u16 * PRG_INCR)
+    }
+
+    /// Address after the last operation
+    fn end(&self) -> u16 {
+        self.address(self.opers.len())
+    }
+
+    /// Index of an address, which may be the end of the run
+    fn index(&self, address: u16) -> Option<usize> {
+        let offset = address.checked_sub(self.start)?;
+        let index = usize::from(offset / PRG_INCR);
+        if offset % PRG_INCR == 0 && index <= self.opers.len() {
+            Some(index)
+        } else {
+            None
+        }
+    }
+
+    /// Jump target of an operation
+    fn jump(&self, index: usize) -> Option<u16> {
+        match self.opers.get(index) {
+            Some(OperCode::Flow1NNN(target)) => Some(*target),
+            _ => None,
+        }
+    }
+}
+
+/// Structuring of the runs of a function
+struct Builder<'a> {
+    /// Runs of the function
+    runs: &'a [Run],
+
+    /// Whether to recover structures, or only emit gotos
+    structured: bool,
+
+    /// Addresses of the operations of the function
+    inside: BTreeSet<u16>,
+
+    /// Addresses a goto may reach
+    targets: BTreeSet<u16>,
+
+    /// Addresses reached by jumps, or skipped to over a condition, which
+    /// a structure cannot absorb
+    entries: BTreeSet<u16>,
+
+    /// Targets of backward jumps
+    loops: BTreeSet<u16>,
+
+    /// Targets of the gotos emitted
+    gotos: BTreeSet<u16>,
+
+    /// Labels emitted
+    labels: BTreeSet<u16>,
+}
+
+impl<'a> Builder<'a> {
+    fn new(runs: &'a [Run], structured: bool) -> Builder<'a> {
+        let mut builder = Builder {
+            runs,
+            structured,
+            inside: BTreeSet::new(),
+            targets: BTreeSet::new(),
+            entries: BTreeSet::new(),
+            loops: BTreeSet::new(),
+            gotos: BTreeSet::new(),
+            labels: BTreeSet::new(),
+        };
+        for run in runs {
+            for (index, oper) in run.opers.iter().enumerate() {
+                let address = run.address(index);
+                builder.inside.insert(address);
+                if let OperCode::Flow1NNN(target) = oper {
+                    builder.targets.insert(*target);
+                    builder.entries.insert(*target);
+                    if *target <= address {
+                        builder.loops.insert(*target);
+                    }
+                }
+                if conditions(oper).is_some() {
+                    let skipped = run.address(index + 2);
+                    builder.targets.insert(skipped);
+                    if run.opers.get(index + 1).and_then(conditions).is_some() {
+                        builder.entries.insert(skipped);
+                    }
+                }
+            }
+        }
+        builder
+    }
+
+    /// Return the statements of every run, or None if a goto would miss
+    /// its label
+    fn build(mut self) -> Option<Vec<Stmt>> {
+        let mut body = Vec::new();
+        for run in self.runs {
+            if !body.is_empty() {
+                body.push(Stmt::Line(String::new()));
+            }
+            body.extend(self.structure(run, 0, run.opers.len()));
+        }
+        let missing = self
+            .gotos
+            .iter()
+            .any(|g| self.inside.contains(g) && !self.labels.contains(g));
+        if missing {
+            None
+        } else {
+            Some(prune(body, &self.gotos))
+        }
+    }
+
+    /// Name of the label of an address
+    fn label(&self, address: u16) -> String {
+        if self.loops.contains(&address) {
+            format!("loop_{:03x}", address)
+        } else {
+            format!("label_{:03x}", address)
+        }
+    }
+
+    /// Statement jumping to an address
+    fn goto(&mut self, address: u16) -> String {
+        self.gotos.insert(address);
+        format!("goto {};", self.label(address))
+    }
+
+    /// Whether an operation may be absorbed by a structure
+    fn absorbable(&self, run: &Run, index: usize) -> bool {
+        !self.entries.contains(&run.address(index))
+    }
+
+    /// Structure the operations of a run between two indexes
+    fn structure(&mut self, run: &Run, lo: usize, hi: usize) -> Vec<Stmt> {
+        let mut stmts = Vec::new();
+        let mut index = lo;
+        while index < hi {
+            let address = run.address(index);
+            if self.targets.contains(&address) && self.labels.insert(address) {
+                stmts.push(Stmt::Label(address, self.label(address)));
+            }
+            let pattern = if self.structured {
+                self.pattern(run, index, hi)
+            } else {
+                None
+            };
+            let (mut recovered, next) = match pattern {
+                Some(pattern) => pattern,
+                None => self.single(run, index),
+            };
+            stmts.append(&mut recovered);
+            index = next;
+        }
+        stmts
+    }
+
+    /// Statement of a single operation, and the index after it
+    fn single(&mut self, run: &Run, index: usize) -> (Vec<Stmt>, usize) {
+        let oper = run.opers[index];
+        let line = match (oper, conditions(&oper)) {
+            (_, Some((skip, _))) => {
+                let goto = self.goto(run.address(index + 2));
+                format!("if ({}) {}", skip, goto)
+            }
+            (OperCode::Flow1NNN(target), _) => self.goto(target),
+            _ => statement(&oper),
+        };
+        (vec![Stmt::Line(line)], index + 1)
+    }
+
+    /// Structure starting on an index and ending by another one, and the
+    /// index after it
+    fn pattern(&mut self, run: &Run, index: usize, hi: usize) -> Option<(Vec<Stmt>, usize)> {
+        let head = run.address(index);
+        let skip = conditions(&run.opers[index]);
+        let next = index + 1;
+
+        // Condition jumping past a body that jumps back to it
+        if let (Some((cond, _)), Some(exit)) = (&skip, run.jump(next)) {
+            if let Some(end) = run.index(exit).filter(|e| *e > next + 1 && *e <= hi) {
+                if run.jump(end - 1) == Some(head)
+                    && self.absorbable(run, next)
+                    && self.absorbable(run, end - 1)
+                {
+                    let body = self.structure(run, next + 1, end - 1);
+                    let open = format!("while ({}) {{", cond);
+                    return Some((nested(open, body, "}".to_string()), end));
+                }
+            }
+        }
+
+        // Outermost backward jump to the index
+        let back = (index..hi)
+            .rev()
+            .find(|k| run.jump(*k) == Some(head) && (*k == index || self.absorbable(run, *k)));
+        if let Some(back) = back {
+            let guard = back
+                .checked_sub(1)
+                .filter(|g| *g >= index && self.absorbable(run, *g))
+                .and_then(|g| conditions(&run.opers[g]));
+            let stmts = match guard {
+                Some((_, cond)) => {
+                    let body = self.structure(run, index, back - 1);
+                    nested("do {".to_string(), body, format!("}} while ({});", cond))
+                }
+                None => {
+                    let body = self.structure(run, index, back);
+                    nested("for (;;) {".to_string(), body, "}".to_string())
+                }
+            };
+            return Some((stmts, back + 1));
+        }
+
+        let (cond, run_cond) = skip?;
+        if next >= hi || !self.absorbable(run, next) {
+            return None;
+        }
+        let target = match run.jump(next) {
+            Some(target) => target,
+            None => {
+                if conditions(&run.opers[next]).is_some() {
+                    return None;
+                }
+                let line = match statement(&run.opers[next]) {
+                    body if body.matches(';').count() > 1 => {
+                        format!("if ({}) {{ {} }}", run_cond, body)
+                    }
+                    body => format!("if ({}) {}", run_cond, body),
+                };
+                return Some((vec![Stmt::Line(line)], next + 1));
+            }
+        };
+
+        // Condition jumping forward past the then branch, which may end
+        // jumping past an else branch
+        if let Some(end) = run.index(target).filter(|e| *e > next + 1 && *e <= hi) {
+            let exit = run
+                .jump(end - 1)
+                .and_then(|f| run.index(f))
+                .filter(|f| *f > end && *f <= hi && end - 1 > next);
+            if let Some(exit) = exit.filter(|_| self.absorbable(run, end - 1)) {
+                let then = self.structure(run, next + 1, end - 1);
+                let otherwise = self.structure(run, end, exit);
+                return Some((
+                    vec![Stmt::IfElse {
+                        cond,
+                        then,
+                        otherwise,
+                    }],
+                    exit,
+                ));
+            }
+            let body = self.structure(run, next + 1, end);
+            let open = format!("if ({}) {{", cond);
+            return Some((nested(open, body, "}".to_string()), end));
+        }
+
+        let line = format!("if ({}) {}", run_cond, self.goto(target));
+        Some((vec![Stmt::Line(line)], next + 1))
+    }
+}
+
+/// Remove the labels no goto reaches
+fn prune(stmts: Vec<Stmt>, gotos: &BTreeSet<u16>) -> Vec<Stmt> {
+    stmts
+        .into_iter()
+        .filter(|stmt| match stmt {
+            Stmt::Label(address, _) => gotos.contains(address),
+            _ => true,
+        })
+        .map(|stmt| match stmt {
+            Stmt::Nested { open, body, close } => Stmt::Nested {
+                open,
+                body: prune(body, gotos),
+                close,
+            },
+            Stmt::IfElse {
+                cond,
+                then,
+                otherwise,
+            } => Stmt::IfElse {
+                cond,
+                then: prune(then, gotos),
+                otherwise: prune(otherwise, gotos),
+            },
+            stmt => stmt,
+        })
+        .collect()
+}
+
+/// Single nested statement
+fn nested(open: String, body: Vec<Stmt>, close: String) -> Vec<Stmt> {
+    vec![Stmt::Nested { open, body, close }]
+}
+
+/// Append statements indented to a given depth, labels one level less
+fn render(stmts: &[Stmt], depth: usize, text: &mut String) {
+    let indent = INDENT.repeat(depth);
+    for stmt in stmts {
+        match stmt {
+            Stmt::Line(line) if line.is_empty() => text.push('\n'),
+            Stmt::Line(line) => text.push_str(&format!("{}{}\n", indent, line)),
+            Stmt::Label(_, label) => {
+                let outer = INDENT.repeat(depth.saturating_sub(1));
+                text.push_str(&format!("{}{}:\n", outer, label));
+            }
+            Stmt::Nested { open, body, close } => {
+                text.push_str(&format!("{}{}\n", indent, open));
+                render(body, depth + 1, text);
+                text.push_str(&format!("{}{}\n", indent, close));
+            }
+            Stmt::IfElse {
+                cond,
+                then,
+                otherwise,
+            } => {
+                text.push_str(&format!("{}if ({}) {{\n", indent, cond));
+                render(then, depth + 1, text);
+                text.push_str(&format!("{}}} else {{\n", indent));
+                render(otherwise, depth + 1, text);
+                text.push_str(&format!("{}}}\n", indent));
+            }
+        }
+    }
+}
+
+/// Name of the function on an entry
+fn name(entry: u16) -> String {
+    if entry == PRG_START as u16 {
+        "main".to_string()
+    } else {
+        format!("sub_{:03x}", entry)
+    }
+}
+
+/// Conditions on which a conditional operation skips the next one, and
+/// on which it runs it
+fn conditions(oper: &OperCode) -> Option<(String, String)> {
+    let (skip, run) = match oper {
+        OperCode::Cond3XNN(x, n) => (
+            format!("v{:x} == 0x{:02X}", x, n),
+            format!("v{:x} != 0x{:02X}", x, n),
+        ),
+        OperCode::Cond4XNN(x, n) => (
+            format!("v{:x} != 0x{:02X}", x, n),
+            format!("v{:x} == 0x{:02X}", x, n),
+        ),
+        OperCode::Cond5XY0(x, y) => (
+            format!("v{:x} == v{:x}", x, y),
+            format!("v{:x} != v{:x}", x, y),
+        ),
+        OperCode::Cond9XY0(x, y) => (
+            format!("v{:x} != v{:x}", x, y),
+            format!("v{:x} == v{:x}", x, y),
+        ),
+        OperCode::KeyOpEX9E(x) => (format!("pressed(v{:x})", x), format!("!pressed(v{:x})", x)),
+        OperCode::KeyOpEXA1(x) => (format!("!pressed(v{:x})", x), format!("pressed(v{:x})", x)),
+        _ => return None,
+    };
+    Some((skip, run))
+}
+
+/// Statement of an operation other than a condition or a jump
+///
+/// Shifts read VY as the original CHIP-8 does, and `store`/`load` move V0
+/// to VX from or to the memory at I. Arithmetic and shifts follow with
+/// their write to VF, where `carry`, `!borrow` and `shifted` are the flag
+/// of the operation on the operands before it, written last so that it
+/// wins when X is F.
+fn statement(oper: &OperCode) -> String {
+    match oper {
+        OperCode::Display00E0 => "cls();".to_string(),
+        OperCode::Flow00EE => "return;".to_string(),
+        OperCode::Flow1NNN(n) => format!("goto label_{:03x};", n),
+        OperCode::Flow2NNN(n) => format!("{}();", name(*n)),
+        OperCode::Const6XNN(x, n) => format!("v{:x} = 0x{:02X};", x, n),
+        OperCode::Const7XNN(x, n) => format!("v{:x} += 0x{:02X};", x, n),
+        OperCode::Assign8XY0(x, y) => format!("v{:x} = v{:x};", x, y),
+        OperCode::BitOp8XY1(x, y) => format!("v{:x} |= v{:x};", x, y),
+        OperCode::BitOp8XY2(x, y) => format!("v{:x} &= v{:x};", x, y),
+        OperCode::BitOp8XY3(x, y) => format!("v{:x} ^= v{:x};", x, y),
+        OperCode::Math8XY4(x, y) => format!("v{:x} += v{:x}; vf = carry;", x, y),
+        OperCode::Math8XY5(x, y) => format!("v{:x} -= v{:x}; vf = !borrow;", x, y),
+        OperCode::BitOp8XY6(x, y) if x == y => format!("v{:x} >>= 1; vf = shifted;", x),
+        OperCode::BitOp8XY6(x, y) => format!("v{:x} = v{:x} >> 1; vf = shifted;", x, y),
+        OperCode::Math8XY7(x, y) => format!("v{:x} = v{:x} - v{:x}; vf = !borrow;", x, y, x),
+        OperCode::BitOp8XYE(x, y) if x == y => format!("v{:x} <<= 1; vf = shifted;", x),
+        OperCode::BitOp8XYE(x, y) => format!("v{:x} = v{:x} << 1; vf = shifted;", x, y),
+        OperCode::MemANNN(n) => format!("i = 0x{:03X};", n),
+        OperCode::FlowBNNN(n) => format!("goto *(0x{:03X} + v0);", n),
+        OperCode::RandCXNN(x, n) => format!("v{:x} = rand() & 0x{:02X};", x, n),
+        OperCode::DisplayDXYN(x, y, n) => format!("vf = draw(v{:x}, v{:x}, {});", x, y, n),
+        OperCode::TimerFX07(x) => format!("v{:x} = delay;", x),
+        OperCode::KeyOpFX0A(x) => format!("v{:x} = key();", x),
+        OperCode::TimerFX15(x) => format!("delay = v{:x};", x),
+        OperCode::SoundFX18(x) => format!("sound = v{:x};", x),
+        OperCode::SoundF002 => "audio(i);".to_string(),
+        OperCode::SoundFX3A(x) => format!("pitch = v{:x};", x),
+        OperCode::MemFX1E(x) => format!("i += v{:x};", x),
+        OperCode::MemFX29(x) => format!("i = font(v{:x});", x),
+        OperCode::BcdFX33(x) => format!("bcd(i, v{:x});", x),
+        OperCode::MemFX55(x) => format!("store(i, v{:x});", x),
+        OperCode::MemFX65(x) => format!("load(i, v{:x});", x),
+        OperCode::Cond3XNN(..)
+        | OperCode::Cond4XNN(..)
+        | OperCode::Cond5XY0(..)
+        | OperCode::Cond9XY0(..)
+        | OperCode::KeyOpEX9E(_)
+        | OperCode::KeyOpEXA1(_) => match conditions(oper) {
+            Some((skip, _)) => format!("if ({}) skip;", skip),
+            None => String::new(),
+        },
+        OperCode::Unknown => "invalid();".to_string(),
+    }
+}
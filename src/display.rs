@@ -1,3 +1,9 @@
+/// Display width in pixels
+pub const DSP_WIDTH: usize = 64_usize;
+
+/// Display height in pixels
+pub const DSP_HEIGHT: usize = 32_usize;
+
 pub enum DisplayDrawResult {
     Collision,
     Free,
@@ -6,14 +12,16 @@ pub enum DisplayDrawResult {
 pub trait Display {
     fn new() -> Self;
     fn clear(&mut self);
-    fn draw(&mut self, x: &usize, y: &usize, height: &u8) -> DisplayDrawResult;
+    fn draw(&mut self, x: &usize, y: &usize, sprite: &[u8]) -> DisplayDrawResult;
     fn refresh(&mut self);
 }
 
 pub trait DisplayEmu<D: Display + Sized> {
     fn set_display(&mut self, display: D);
+    fn get_display(&self) -> &D;
 }
 
+#[derive(PartialEq)]
 pub struct DisplayDummy {}
 impl Display for DisplayDummy {
     fn new() -> DisplayDummy {
@@ -22,9 +30,121 @@ impl Display for DisplayDummy {
 
     fn clear(&mut self) {}
 
-    fn draw(&mut self, _: &usize, _: &usize, _: &u8) -> DisplayDrawResult {
+    fn draw(&mut self, _: &usize, _: &usize, _: &[u8]) -> DisplayDrawResult {
         DisplayDrawResult::Free
     }
 
     fn refresh(&mut self) {}
 }
+
+///
+/// Monochrome framebuffer of `DSP_WIDTH` x `DSP_HEIGHT` pixels
+///
+/// Sprite coordinates wrap around the screen, and the sprite rows are
+/// clipped at the edges.
+///
+/// # Example
+///
+/// ```
+/// use rc201_8::display::{Display, DisplayBuffer, DisplayDrawResult};
+///
+/// let mut dsp = DisplayBuffer::new();
+///
+/// // A 2x2 square, drawn twice
+/// let sprite = [0xC0, 0xC0];
+/// match dsp.draw(&66, &1, &sprite) {
+///     DisplayDrawResult::Free => (),
+///     DisplayDrawResult::Collision => panic!(),
+/// }
+/// assert!(dsp.pixel(&3, &2));
+/// assert!(!dsp.pixel(&4, &2));
+///
+/// match dsp.draw(&2, &1, &sprite) {
+///     DisplayDrawResult::Collision => (),
+///     DisplayDrawResult::Free => panic!(),
+/// }
+/// assert!(dsp.pixels().iter().all(|p| !p));
+/// ```
+///
+#[derive(Clone, PartialEq, Eq)]
+pub struct DisplayBuffer {
+    /// Pixels, row by row
+    pixels: Vec<bool>,
+}
+
+impl DisplayBuffer {
+    ///
+    /// Return the state of a given pixel, false if out of the screen
+    ///
+    pub fn pixel(&self, x: &usize, y: &usize) -> bool {
+        x < &DSP_WIDTH && y < &DSP_HEIGHT && self.pixels[y * DSP_WIDTH + x]
+    }
+
+    ///
+    /// Return all pixels, row by row
+    ///
+    pub fn pixels(&self) -> &[bool] {
+        &self.pixels
+    }
+
+    ///
+    /// Return the pixels as a plain PBM image, one text row per pixel row
+    ///
+    /// # Example
+    ///
+    /// ```
+    /// use rc201_8::display::{Display, DisplayBuffer};
+    ///
+    /// let mut dsp = DisplayBuffer::new();
+    /// dsp.draw(&0, &0, &[0x80]);
+    /// let pbm = dsp.to_pbm();
+    /// let lines: Vec<&str> = pbm.lines().collect();
+    ///
+    /// assert_eq!(lines[0], "P1");
+    /// assert_eq!(lines[1], "64 32");
+    /// assert!(lines[2].starts_with("1 0 0"));
+    /// ```
+    ///
+    pub fn to_pbm(&self) -> String {
+        let mut image = format!("P1\n{} {}\n", DSP_WIDTH, DSP_HEIGHT);
+        for row in self.pixels.chunks(DSP_WIDTH) {
+            let row: Vec<&str> = row.iter().map(|p| if *p { "1" } else { "0" }).collect();
+            image.push_str(&row.join(" "));
+            image.push('\n');
+        }
+        image
+    }
+}
+
+impl Display for DisplayBuffer {
+    fn new() -> DisplayBuffer {
+        DisplayBuffer {
+            pixels: vec![false; DSP_WIDTH * DSP_HEIGHT],
+        }
+    }
+
+    fn clear(&mut self) {
+        self.pixels.iter_mut().for_each(|p| *p = false);
+    }
+
+    fn draw(&mut self, x: &usize, y: &usize, sprite: &[u8]) -> DisplayDrawResult {
+        let (x, y) = (x % DSP_WIDTH, y % DSP_HEIGHT);
+        let mut collision = false;
+        for (row, bits) in sprite.iter().enumerate().take(DSP_HEIGHT - y) {
+            for col in 0..(8.min(DSP_WIDTH - x)) {
+                if bits & (0x80 >> col) != 0 {
+                    let p = &mut self.pixels[(y + row) * DSP_WIDTH + x + col];
+                    collision |= *p;
+                    *p = !*p;
+                }
+            }
+        }
+        if collision {
+            DisplayDrawResult::Collision
+        } else {
+            DisplayDrawResult::Free
+        }
+    }
+
+    fn refresh(&mut self) {}
+}
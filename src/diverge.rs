@@ -0,0 +1,215 @@
+use crate::cpu::Cpu;
+use crate::debugger::Register;
+use crate::display::{Display, DisplayEmu};
+use crate::emu::Emu;
+use crate::keypad::{Key, Keypad};
+use crate::trace::TraceRecord;
+use std::collections::VecDeque;
+use std::fmt;
+
+///
+/// Key state change applied before a given instruction
+///
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct InputEvent {
+    /// Index of the instruction, starting from 0
+    pub step: u64,
+
+    /// Changed key
+    pub key: Key,
+
+    /// Whether the key is pressed or released
+    pub pressed: bool,
+}
+
+///
+/// First state difference between two runs
+///
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum Divergence {
+    ///
+    /// Register value of each run
+    ///
+    Register(Register, u16, u16),
+
+    ///
+    /// Memory address and value of each run
+    ///
+    Memory(usize, u8, u8),
+
+    ///
+    /// Displays differ
+    ///
+    Display,
+
+    ///
+    /// XO-CHIP audio patterns differ
+    ///
+    Pattern,
+
+    ///
+    /// Only one of the runs failed, with the error message
+    ///
+    Fault(Option<String>, Option<String>),
+}
+
+impl fmt::Display for Divergence {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            Divergence::Register(r, a, b) => {
+                write!(f, "register {}: {:04X} != {:04X}", r.name(), a, b)
+            }
+            Divergence::Memory(i, a, b) => write!(f, "memory {:04X}: {:02X} != {:02X}", i, a, b),
+            Divergence::Display => write!(f, "display"),
+            Divergence::Pattern => write!(f, "audio pattern"),
+            Divergence::Fault(a, b) => write!(
+                f,
+                "fault: {} != {}",
+                a.as_ref().map_or("none", |e| e.as_str()),
+                b.as_ref().map_or("none", |e| e.as_str())
+            ),
+        }
+    }
+}
+
+///
+/// Description of the first divergent instruction
+///
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct DivergenceReport {
+    /// Index of the divergent instruction
+    pub step: u64,
+
+    /// First state difference
+    pub divergence: Divergence,
+
+    /// Trace records of both runs, ending with the divergent instruction
+    pub context: Vec<(Option<TraceRecord>, Option<TraceRecord>)>,
+}
+
+impl fmt::Display for DivergenceReport {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        writeln!(f, "Divergence at step {}: {}", self.step, self.divergence)?;
+        for (a, b) in &self.context {
+            let a = a.as_ref().map_or(String::from("-"), TraceRecord::to_text);
+            let b = b.as_ref().map_or(String::from("-"), TraceRecord::to_text);
+            writeln!(f, "A {}", a)?;
+            writeln!(f, "B {}", b)?;
+        }
+        Ok(())
+    }
+}
+
+///
+/// Run two emulators in lockstep and return the first instruction after
+/// which their registers, memory or display differ
+///
+/// Both emulators receive the same input events, and tick their timers
+/// every `ipf` instructions, never if 0. The run stops after `limit`
+/// instructions, or when both emulators fail the same way. The report
+/// keeps up to `context` instructions, including the divergent one.
+///
+/// # Example
+///
+/// ```
+/// use rc201_8::emu::Emu;
+/// use rc201_8::diverge::{self, Divergence};
+/// use rc201_8::debugger::Register;
+/// use rc201_8::display::{Display, DisplayDummy};
+///
+/// // Draw a random number, then loop
+/// let rom = [0x60, 0x00, 0xC1, 0xFF, 0x12, 0x04];
+///
+/// let mut a = Emu::new(DisplayDummy::new());
+/// let mut b = Emu::new(DisplayDummy::new());
+/// a.load_rom(&rom).unwrap();
+/// b.load_rom(&rom).unwrap();
+/// a.rng_seed(1);
+/// b.rng_seed(1);
+/// assert!(diverge::find(&mut a, &mut b, &[], 100, 10, 4).is_none());
+///
+/// a.load_rom(&rom).unwrap();
+/// b.load_rom(&rom).unwrap();
+/// a.rng_seed(1);
+/// b.rng_seed(2);
+/// let report = diverge::find(&mut a, &mut b, &[], 100, 10, 4).unwrap();
+///
+/// assert_eq!(report.step, 1);
+/// assert_eq!(report.context.len(), 2);
+/// match report.divergence {
+///     Divergence::Register(r, _, _) => assert_eq!(r, Register::V(1)),
+///     _ => panic!(),
+/// }
+/// ```
+///
+pub fn find<D: Display + PartialEq>(
+    a: &mut Emu<D>,
+    b: &mut Emu<D>,
+    input: &[InputEvent],
+    limit: u64,
+    ipf: usize,
+    context: usize,
+) -> Option<DivergenceReport> {
+    let mut window = VecDeque::with_capacity(context + 1);
+    for step in 0..limit {
+        for event in input.iter().filter(|e| e.step == step) {
+            a.key_put(&event.key, event.pressed);
+            b.key_put(&event.key, event.pressed);
+        }
+
+        let ra = TraceRecord::step(a);
+        let rb = TraceRecord::step(b);
+        let fault = match (&ra, &rb) {
+            (Err(ea), Err(eb)) if ea.to_string() == eb.to_string() => return None,
+            (Ok(_), Ok(_)) => None,
+            (ea, eb) => Some(Divergence::Fault(
+                ea.as_ref().err().map(|e| e.to_string()),
+                eb.as_ref().err().map(|e| e.to_string()),
+            )),
+        };
+
+        window.push_back((ra.ok(), rb.ok()));
+        if window.len() > context {
+            window.pop_front();
+        }
+
+        if let Some(divergence) = fault.or_else(|| state_compare(a, b)) {
+            return Some(DivergenceReport {
+                step,
+                divergence,
+                context: window.into_iter().collect(),
+            });
+        }
+        if ipf > 0 && step as usize % ipf == ipf - 1 {
+            a.timers_tick();
+            b.timers_tick();
+        }
+    }
+    None
+}
+
+/// Return the first difference between two emulator states
+fn state_compare<D: Display + PartialEq>(a: &Emu<D>, b: &Emu<D>) -> Option<Divergence> {
+    for register in Register::all() {
+        let va = register.read(a).unwrap_or(0);
+        let vb = register.read(b).unwrap_or(0);
+        if va != vb {
+            return Some(Divergence::Register(register, va, vb));
+        }
+    }
+
+    let ma = a.mem_snapshot();
+    let mb = b.mem_snapshot();
+    if let Some(i) = (0..ma.len().min(mb.len())).find(|i| ma[*i] != mb[*i]) {
+        return Some(Divergence::Memory(i, ma[i], mb[i]));
+    }
+
+    if a.get_display() != b.get_display() {
+        return Some(Divergence::Display);
+    }
+
+    if a.pat_get() != b.pat_get() {
+        return Some(Divergence::Pattern);
+    }
+    None
+}
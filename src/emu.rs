@@ -1,19 +1,32 @@
+use crate::alu::{self, AluOp};
+use crate::audio::{Audio, PATTERN_SIZE, PITCH_DEFAULT};
 use crate::cpu::{Cpu, CpuError, CpuErrorVariant};
 use crate::display::{Display, DisplayDrawResult, DisplayEmu};
 use crate::keypad::{Key, Keypad};
-use crate::mem::{Mem, MemError, MemErrorVariant};
+use crate::mem::{
+    Mem, MemAccess, MemAccessKind, MemAccessSource, MemError, MemErrorVariant, MemObserver,
+};
 use crate::oper::{Oper, OperCode};
-use rand::rngs::ThreadRng;
-use rand::Rng;
+use crate::quirk::Quirks;
+use rand::rngs::StdRng;
+use rand::{FromEntropy, Rng, SeedableRng};
+use std::cell::{Cell, RefCell};
 use std::ops::Bound::*;
 use std::ops::RangeBounds;
 use std::slice::SliceIndex;
 
-const MEM_SIZE: usize = 4096_usize;
-const REG_SIZE: usize = 16_usize;
-const STK_SIZE: usize = 16_usize;
+/// Bytes of memory
+pub const MEM_SIZE: usize = 4096_usize;
+/// Number of general purpose registers
+pub const REG_SIZE: usize = 16_usize;
+/// Default depth of the call stack
+pub const STK_SIZE: usize = 16_usize;
 const KEY_SIZE: usize = 16_usize;
-const PRG_INCR: u16 = 2_u16;
+/// Bytes of an operation, by which the program counter advances
+pub const PRG_INCR: u16 = 2_u16;
+
+/// Address where programs are loaded and started
+pub const PRG_START: usize = 0x200_usize;
 
 ///
 /// Main emulator structure
@@ -56,7 +69,7 @@ pub struct Emu<D: Display + Sized> {
     cnt: u16,
 
     /// Random generator
-    rng: ThreadRng,
+    rng: StdRng,
 
     /// Delay timer
     dtm: u8,
@@ -64,10 +77,10 @@ pub struct Emu<D: Display + Sized> {
     /// Sound timer
     stm: u8,
 
-    /// Call stack
-    stk: [u16; STK_SIZE],
+    /// Call stack, sized to its depth
+    stk: Vec<u16>,
 
-    /// Stack pointer
+    /// Stack pointer, the next free slot of the call stack
     spt: usize,
 
     /// Keypad
@@ -75,6 +88,30 @@ pub struct Emu<D: Display + Sized> {
 
     /// Display
     dsp: D,
+
+    /// Buzzer receiver
+    aud: Option<Box<dyn Audio>>,
+
+    /// XO-CHIP audio pattern, None until one is loaded
+    pat: Option<[u8; PATTERN_SIZE]>,
+
+    /// XO-CHIP pitch register
+    pit: u8,
+
+    /// Interpreter quirks
+    qrk: Quirks,
+
+    /// Predecoded operations per address
+    ops: Vec<Option<OperCode>>,
+
+    /// Memory observers and their identifiers
+    obs: RefCell<Vec<(usize, Box<dyn MemObserver>)>>,
+
+    /// Identifier of the next memory observer
+    oid: usize,
+
+    /// Origin of the memory accesses in progress
+    src: Cell<MemAccessSource>,
 }
 
 impl<D: Display + Sized> Emu<D> {
@@ -96,27 +133,252 @@ impl<D: Display + Sized> Emu<D> {
             reg: [0; REG_SIZE],
             ind: 0,
             cnt: 0,
-            rng: rand::thread_rng(),
+            rng: StdRng::from_entropy(),
             dtm: 0,
             stm: 0,
-            stk: [0; STK_SIZE],
+            stk: vec![0; STK_SIZE],
             spt: 0,
             key: [false; KEY_SIZE],
             dsp: display,
+            aud: None,
+            pat: None,
+            pit: PITCH_DEFAULT,
+            qrk: Quirks::default(),
+            ops: vec![None; MEM_SIZE],
+            obs: RefCell::new(Vec::new()),
+            oid: 0,
+            src: Cell::new(MemAccessSource::External),
         }
     }
-}
 
-impl<D: Display + Sized> Cpu for Emu<D> {
-    /// Executes an operation from a given code
-    fn recv_opcode(&mut self, code: &u16) -> Result<(), CpuError> {
-        match Oper::from_code(code, &REG_SIZE) {
+    ///
+    /// Register a memory observer, returning its identifier
+    ///
+    /// Observers are notified of every byte accessed through `Mem`, by
+    /// the instruction fetch of `step` and by the operations.
+    ///
+    pub fn mem_observer_add(&mut self, observer: Box<dyn MemObserver>) -> usize {
+        self.oid += 1;
+        self.obs.get_mut().push((self.oid, observer));
+        self.oid
+    }
+
+    ///
+    /// Unregister a memory observer, returning false if it was not found
+    ///
+    pub fn mem_observer_remove(&mut self, id: usize) -> bool {
+        let obs = self.obs.get_mut();
+        let len = obs.len();
+        obs.retain(|(i, _)| i != &id);
+        obs.len() != len
+    }
+
+    ///
+    /// Return the whole memory without notifying the observers
+    ///
+    pub fn mem_snapshot(&self) -> &[u8] {
+        &self.mem
+    }
+
+    ///
+    /// Process an operation decoded from a given code, as `recv_opcode`
+    /// does without decoding it again
+    ///
+    pub fn oper_exec(&mut self, oper: OperCode, code: u16) -> Result<(), CpuError> {
+        self.oper_process(oper, &code)
+    }
+
+    /// Write a byte, invalidating the predecoded operations it overlaps
+    fn mem_store(&mut self, index: usize, value: u8) {
+        let old = self.mem[index];
+        self.mem[index] = value;
+        self.mem_notify(index, value, MemAccessKind::Write);
+        if old == value {
+            return;
+        }
+        for address in index.saturating_sub(1)..=index {
+            if self.ops[address].take().is_some() {
+                let access = MemAccess {
+                    address: index,
+                    value,
+                    kind: MemAccessKind::Write,
+                    source: self.src.get(),
+                };
+                for (_, o) in self.obs.get_mut().iter_mut() {
+                    o.code_modified(address, &access);
+                }
+            }
+        }
+    }
+
+    /// Notify the observers of a single byte access
+    fn mem_notify(&self, address: usize, value: u8, kind: MemAccessKind) {
+        let mut obs = self.obs.borrow_mut();
+        if obs.is_empty() {
+            return;
+        }
+        let access = MemAccess {
+            address,
+            value,
+            kind,
+            source: self.src.get(),
+        };
+        for (_, o) in obs.iter_mut() {
+            o.mem_access(&access);
+        }
+    }
+
+    ///
+    /// Return the interpreter quirks
+    ///
+    pub fn quirks(&self) -> Quirks {
+        self.qrk
+    }
+
+    ///
+    /// Set the interpreter quirks
+    ///
+    pub fn quirks_put(&mut self, quirks: Quirks) {
+        self.qrk = quirks;
+    }
+
+    ///
+    /// Return the depth of the call stack
+    ///
+    pub fn stack_depth(&self) -> usize {
+        self.stk.len()
+    }
+
+    ///
+    /// Set the depth of the call stack, dropping the addresses that no
+    /// longer fit
+    ///
+    /// # Example
+    ///
+    /// ```
+    /// use rc201_8::emu::Emu;
+    /// use rc201_8::cpu::{Cpu, CpuErrorVariant};
+    /// use rc201_8::display::{Display, DisplayDummy};
+    ///
+    /// let mut emu = Emu::new(DisplayDummy::new());
+    /// emu.stack_depth_put(2);
+    ///
+    /// emu.stk_push(0x202).unwrap();
+    /// emu.stk_push(0x204).unwrap();
+    /// let err = emu.stk_push(0x206).unwrap_err();
+    /// assert!(matches!(err.variant(), CpuErrorVariant::StackOverflow(3)));
+    /// ```
+    ///
+    pub fn stack_depth_put(&mut self, depth: usize) {
+        self.stk.resize(depth, 0);
+        self.spt = self.spt.min(depth);
+    }
+
+    ///
+    /// Set the receiver of the buzzer state, passing it the audio pattern
+    /// and pitch already set
+    ///
+    pub fn audio_set(&mut self, mut audio: Box<dyn Audio>) {
+        if let Some(pattern) = self.pat.as_ref() {
+            audio.pattern(pattern);
+        }
+        if self.pit != PITCH_DEFAULT {
+            audio.pitch(self.pit);
+        }
+        self.aud = Some(audio);
+    }
+
+    ///
+    /// Remove the receiver of the buzzer state, returning it
+    ///
+    pub fn audio_take(&mut self) -> Option<Box<dyn Audio>> {
+        self.aud.take()
+    }
+
+    /// Set the sound timer, notifying the buzzer transitions
+    fn stm_set(&mut self, value: u8) {
+        let (was, is) = (self.stm > 0, value > 0);
+        self.stm = value;
+        if let Some(aud) = self.aud.as_mut() {
+            if !was && is {
+                aud.buzzer_start();
+            } else if was && !is {
+                aud.buzzer_stop();
+            }
+        }
+    }
+
+    ///
+    /// Seed the random generator, making `RandCXNN` reproducible
+    ///
+    /// # Example
+    ///
+    /// ```
+    /// use rc201_8::emu::Emu;
+    /// use rc201_8::cpu::Cpu;
+    /// use rc201_8::display::{Display, DisplayDummy};
+    ///
+    /// let mut a = Emu::new(DisplayDummy::new());
+    /// let mut b = Emu::new(DisplayDummy::new());
+    /// a.rng_seed(7);
+    /// b.rng_seed(7);
+    ///
+    /// a.recv_opcode(&0xC0FF).unwrap();
+    /// b.recv_opcode(&0xC0FF).unwrap();
+    /// assert_eq!(a.reg_get(&0).unwrap(), b.reg_get(&0).unwrap());
+    /// ```
+    ///
+    pub fn rng_seed(&mut self, seed: u64) {
+        self.rng = StdRng::seed_from_u64(seed);
+    }
+
+    ///
+    /// Load a program on `PRG_START` and point the program counter to it
+    ///
+    /// # Example
+    ///
+    /// ```
+    /// use rc201_8::emu::{Emu, PRG_START};
+    /// use rc201_8::cpu::Cpu;
+    /// use rc201_8::mem::Mem;
+    /// use rc201_8::display::{Display, DisplayDummy};
+    ///
+    /// let mut emu = Emu::new(DisplayDummy::new());
+    /// emu.load_rom(&[0x00, 0xE0]).unwrap();
+    ///
+    /// assert_eq!(emu.cnt_get() as usize, PRG_START);
+    /// assert_eq!(emu.mem_read(PRG_START..PRG_START + 2).unwrap(), &[0x00, 0xE0]);
+    /// assert!(emu.load_rom(&[0; 4000]).is_err());
+    /// ```
+    ///
+    pub fn load_rom(&mut self, rom: &[u8]) -> Result<(), MemError> {
+        if !rom.is_empty() {
+            self.mem_write(PRG_START..PRG_START + rom.len(), rom)?;
+        }
+        self.cnt = PRG_START as u16;
+        Ok(())
+    }
+
+    /// Apply an `8XYN` operation, writing VF after VX
+    fn alu_apply(&mut self, op: AluOp, x: usize, y: usize) -> Result<(), CpuError> {
+        let out = op(self.reg_get(&x)?, self.reg_get(&y)?, &self.qrk);
+        self.reg_put(&x, out.value)?;
+        if let Some(flag) = out.flag {
+            self.reg_put_vf(flag);
+        }
+        Ok(())
+    }
+
+    /// Executes a decoded operation
+    fn oper_process(&mut self, oper: OperCode, code: &u16) -> Result<(), CpuError> {
+        let src = self.src.replace(MemAccessSource::Oper);
+        let result = match oper {
             OperCode::Display00E0 => {
                 self.dsp.clear();
                 Ok(())
             }
             OperCode::Flow00EE => {
-                self.cnt = self.stk_pop().unwrap();
+                self.cnt = self.stk_pop()?;
                 Ok(())
             }
             OperCode::Flow1NNN(v) => {
@@ -124,106 +386,49 @@ impl<D: Display + Sized> Cpu for Emu<D> {
                 Ok(())
             }
             OperCode::Flow2NNN(n) => {
-                self.stk_push(n).unwrap();
+                self.stk_push(self.cnt)?;
                 self.cnt = n;
                 Ok(())
             }
             OperCode::Cond3XNN(x, n) => {
-                if self.reg_get(&x).unwrap() == n {
+                if self.reg_get(&x)? == n {
                     self.skip_next_instruction();
                 }
                 Ok(())
             }
             OperCode::Cond4XNN(x, n) => {
-                if self.reg_get(&x).unwrap() != n {
+                if self.reg_get(&x)? != n {
                     self.skip_next_instruction();
                 }
                 Ok(())
             }
             OperCode::Cond5XY0(x, y) => {
-                if self.reg_get(&x).unwrap() == self.reg_get(&y).unwrap() {
+                if self.reg_get(&x)? == self.reg_get(&y)? {
                     self.skip_next_instruction();
                 }
                 Ok(())
             }
             OperCode::Const6XNN(x, v) => {
-                self.reg_put(&x, v).unwrap();
+                self.reg_put(&x, v)?;
                 Ok(())
             }
             OperCode::Const7XNN(x, v) => {
-                let vx = self.reg_get(&x).unwrap();
-                self.reg_put(&x, vx + v).unwrap();
-                Ok(())
-            }
-            OperCode::Assign8XY0(x, y) => {
-                let vy = self.reg_get(&y).unwrap();
-                self.reg_put(&x, vy).unwrap();
-                Ok(())
-            }
-            OperCode::BitOp8XY1(x, y) => {
-                let vx = self.reg_get(&x).unwrap();
-                let vy = self.reg_get(&y).unwrap();
-                self.reg_put(&x, vx | vy).unwrap();
-                Ok(())
-            }
-            OperCode::BitOp8XY2(x, y) => {
-                let vx = self.reg_get(&x).unwrap();
-                let vy = self.reg_get(&y).unwrap();
-                self.reg_put(&x, vx & vy).unwrap();
-                Ok(())
-            }
-            OperCode::BitOp8XY3(x, y) => {
-                let vx = self.reg_get(&x).unwrap();
-                let vy = self.reg_get(&y).unwrap();
-                self.reg_put(&x, vx ^ vy).unwrap();
-                Ok(())
-            }
-            OperCode::Math8XY4(x, y) => {
-                let vx = self.reg_get(&x).unwrap();
-                let vy = self.reg_get(&y).unwrap();
-                let sum = vx + vy;
-                let vf = if (sum as u16) > 0xFF { 1 } else { 0 };
-                self.reg_put_vf(vf);
-                self.reg_put(&x, sum).unwrap();
-                Ok(())
-            }
-            OperCode::Math8XY5(x, y) => {
-                let vx = self.reg_get(&x).unwrap();
-                let vy = self.reg_get(&y).unwrap();
-                let dif: i8 = vx as i8 - vy as i8;
-                self.reg_put(&x, dif as u8).unwrap();
-                let vf = if dif < 0 { 1 } else { 0 };
-                self.reg_put_vf(vf);
-                Ok(())
-            }
-            OperCode::BitOp8XY6(x, _) => {
-                let mut vx = self.reg_get(&x).unwrap();
-                let vf = vx & 0x1;
-                self.reg_put_vf(vf);
-                vx >>= 1;
-                self.reg_put(&x, vx).unwrap();
-                Ok(())
-            }
-            OperCode::Math8XY7(x, y) => {
-                let vx = self.reg_get(&x).unwrap();
-                let vy = self.reg_get(&y).unwrap();
-                let dif: i8 = vy as i8 - vx as i8;
-                self.reg_put(&x, dif as u8).unwrap();
-                let vf = if dif < 0 { 1 } else { 0 };
-                self.reg_put_vf(vf);
-                Ok(())
-            }
-            OperCode::BitOp8XYE(x, _) => {
-                let mut vx = self.reg_get(&x).unwrap();
-                let vf = vx & 0x80;
-                self.reg_put_vf(vf);
-                vx <<= 1;
-                self.reg_put(&x, vx).unwrap();
-                Ok(())
-            }
+                let vx = self.reg_get(&x)?;
+                self.reg_put(&x, alu::add_nn(vx, v))?;
+                Ok(())
+            }
+            OperCode::Assign8XY0(x, y) => self.alu_apply(alu::assign, x, y),
+            OperCode::BitOp8XY1(x, y) => self.alu_apply(alu::or, x, y),
+            OperCode::BitOp8XY2(x, y) => self.alu_apply(alu::and, x, y),
+            OperCode::BitOp8XY3(x, y) => self.alu_apply(alu::xor, x, y),
+            OperCode::Math8XY4(x, y) => self.alu_apply(alu::add, x, y),
+            OperCode::Math8XY5(x, y) => self.alu_apply(alu::sub, x, y),
+            OperCode::BitOp8XY6(x, y) => self.alu_apply(alu::shr, x, y),
+            OperCode::Math8XY7(x, y) => self.alu_apply(alu::subn, x, y),
+            OperCode::BitOp8XYE(x, y) => self.alu_apply(alu::shl, x, y),
             OperCode::Cond9XY0(x, y) => {
-                let vx = self.reg_get(&x).unwrap();
-                let vy = self.reg_get(&y).unwrap();
+                let vx = self.reg_get(&x)?;
+                let vy = self.reg_get(&y)?;
                 if vx != vy {
                     self.skip_next_instruction();
                 }
@@ -234,17 +439,27 @@ impl<D: Display + Sized> Cpu for Emu<D> {
                 Ok(())
             }
             OperCode::FlowBNNN(v) => {
-                let v0 = self.reg_get(&0).unwrap() as u16;
-                self.cnt = v + v0;
+                let x = if self.qrk.jump_vx {
+                    (v >> 8) as usize
+                } else {
+                    0
+                };
+                let vx = self.reg_get(&x)? as u16;
+                self.cnt = v + vx;
                 Ok(())
             }
             OperCode::RandCXNN(x, v) => {
-                let r: u8 = self.rng.gen_range(0, 255);
-                self.reg_put(&x, r & v).unwrap();
+                let r = self.rng.gen::<u8>();
+                self.reg_put(&x, r & v)?;
                 Ok(())
             }
             OperCode::DisplayDXYN(x, y, height) => {
-                let vf = match self.dsp.draw(&x, &y, &height) {
+                let vx = self.reg_get(&x)? as usize;
+                let vy = self.reg_get(&y)? as usize;
+                let sprite: Vec<u8> = (self.ind..self.ind.saturating_add(height as usize))
+                    .filter_map(|i| self.mem_get(&i).ok().cloned())
+                    .collect();
+                let vf = match self.dsp.draw(&vx, &vy, &sprite) {
                     DisplayDrawResult::Collision => 1,
                     DisplayDrawResult::Free => 0,
                 };
@@ -252,7 +467,7 @@ impl<D: Display + Sized> Cpu for Emu<D> {
                 Ok(())
             }
             OperCode::KeyOpEX9E(x) => {
-                let vx = self.reg_get(&x).unwrap();
+                let vx = self.reg_get(&x)?;
                 let key = self.key_from_u8(&vx);
                 if self.key_pressed(&key) {
                     self.skip_next_instruction();
@@ -260,7 +475,7 @@ impl<D: Display + Sized> Cpu for Emu<D> {
                 Ok(())
             }
             OperCode::KeyOpEXA1(x) => {
-                let vx = self.reg_get(&x).unwrap();
+                let vx = self.reg_get(&x)?;
                 let key = self.key_from_u8(&vx);
                 if !self.key_pressed(&key) {
                     self.skip_next_instruction();
@@ -268,68 +483,139 @@ impl<D: Display + Sized> Cpu for Emu<D> {
                 Ok(())
             }
             OperCode::TimerFX07(x) => {
-                self.reg_put(&x, self.dtm).unwrap();
+                self.reg_put(&x, self.dtm)?;
                 Ok(())
             }
             OperCode::KeyOpFX0A(x) => {
                 match self.any_key_pressed() {
-                    Some(k) => self.reg_put(&x, self.key_to_u8(&k)).unwrap(),
-                    None => self.cnt = self.cnt - PRG_INCR,
+                    Some(k) => self.reg_put(&x, self.key_to_u8(&k))?,
+                    None => self.cnt = self.cnt.wrapping_sub(PRG_INCR),
                 }
                 Ok(())
             }
             OperCode::TimerFX15(x) => {
-                let vx = self.reg_get(&x).unwrap();
+                let vx = self.reg_get(&x)?;
                 self.dtm = vx;
                 Ok(())
             }
             OperCode::SoundFX18(x) => {
-                let vx = self.reg_get(&x).unwrap();
-                self.stm = vx;
+                let vx = self.reg_get(&x)?;
+                self.stm_set(vx);
+                Ok(())
+            }
+            OperCode::SoundF002 => {
+                let mut pattern = [0; PATTERN_SIZE];
+                for (i, p) in pattern.iter_mut().enumerate() {
+                    *p = self
+                        .mem_get(&self.ind.saturating_add(i))
+                        .cloned()
+                        .unwrap_or(0);
+                }
+                self.pat_put(pattern);
+                Ok(())
+            }
+            OperCode::SoundFX3A(x) => {
+                let vx = self.reg_get(&x)?;
+                self.pit_put(vx);
                 Ok(())
             }
             OperCode::MemFX1E(x) => {
-                let vx = self.reg_get(&x).unwrap() as usize;
-                self.ind = self.ind + vx;
+                let vx = self.reg_get(&x)? as usize;
+                self.ind = self.ind.saturating_add(vx);
                 Ok(())
             }
             OperCode::MemFX29(x) => {
-                let vx = self.reg_get(&x).unwrap() as usize;
+                let vx = self.reg_get(&x)? as usize;
                 self.ind = vx * 5;
                 Ok(())
             }
             OperCode::BcdFX33(x) => {
-                let vx = self.reg_get(&x).unwrap();
+                let vx = self.reg_get(&x)?;
                 let ind = self.ind;
-                self.mem_put(&ind, vx / 100).unwrap();
-                self.mem_put(&(ind + 1), (vx / 10) % 10).unwrap();
-                self.mem_put(&(ind + 2), (vx % 100) % 10).unwrap();
+                self.mem_put(&ind, vx / 100)?;
+                self.mem_put(&ind.saturating_add(1), (vx / 10) % 10)?;
+                self.mem_put(&ind.saturating_add(2), (vx % 100) % 10)?;
                 Ok(())
             }
             OperCode::MemFX55(x) => {
-                let vx = self.reg_get(&x).unwrap();
-                let mi = (vx + 1) as usize;
-                for i in 0..mi {
-                    let vi = self.reg_get(&i).unwrap();
-                    self.mem_put(&(self.ind + i), vi).unwrap()
+                for i in 0..=x {
+                    let vi = self.reg_get(&i)?;
+                    self.mem_put(&self.ind.saturating_add(i), vi)?
+                }
+                if self.qrk.load_store_inc {
+                    self.ind = self.ind.saturating_add(x + 1);
                 }
                 Ok(())
             }
             OperCode::MemFX65(x) => {
-                let vx = self.reg_get(&x).unwrap();
-                for i in 0..((vx + 1) as usize) {
-                    let v = self.mem_get(&(self.ind + i)).unwrap().clone();
-                    self.reg_put(&i, v).unwrap();
+                for i in 0..=x {
+                    let v = *self.mem_get(&self.ind.saturating_add(i))?;
+                    self.reg_put(&i, v)?;
+                }
+                if self.qrk.load_store_inc {
+                    self.ind = self.ind.saturating_add(x + 1);
                 }
                 Ok(())
             }
             OperCode::Unknown => Err(CpuError::new(CpuErrorVariant::InvalidOperationCode(*code))),
+        };
+        self.src.set(src);
+        result
+    }
+}
+
+impl<D: Display + Sized> Cpu for Emu<D> {
+    /// Executes an operation from a given code
+    fn recv_opcode(&mut self, code: &u16) -> Result<(), CpuError> {
+        self.oper_process(Oper::from_code(code, &REG_SIZE), code)
+    }
+
+    /// Return the operation code stored at the program counter
+    fn fetch_opcode(&self) -> Result<u16, CpuError> {
+        let cnt = self.cnt as usize;
+        if self.validate_index(&(cnt + 1)) {
+            Ok(u16::from(self.mem[cnt]) << 8 | u16::from(self.mem[cnt + 1]))
+        } else {
+            Err(CpuError::new(CpuErrorVariant::InvalidProgramCounter(
+                self.cnt,
+            )))
         }
     }
 
+    /// Fetch, advance and process the next operation, reusing the
+    /// operation decoded on a previous visit of the address
+    fn step(&mut self) -> Result<(), CpuError> {
+        let code = self.fetch_opcode()?;
+        let cnt = self.cnt as usize;
+        let src = self.src.replace(MemAccessSource::Fetch);
+        self.mem_notify(cnt, (code >> 8) as u8, MemAccessKind::Read);
+        self.mem_notify(cnt + 1, code as u8, MemAccessKind::Read);
+        self.src.set(src);
+        let oper = match self.ops[cnt] {
+            Some(oper) => oper,
+            None => {
+                let oper = Oper::from_code(&code, &REG_SIZE);
+                self.ops[cnt] = Some(oper);
+                oper
+            }
+        };
+        self.cnt += PRG_INCR;
+        self.oper_process(oper, &code)
+    }
+
+    /// Notify the end of the frame and decrement the delay and sound
+    /// timers
+    fn timers_tick(&mut self) {
+        if let Some(aud) = self.aud.as_mut() {
+            aud.frame();
+        }
+        self.dtm = self.dtm.saturating_sub(1);
+        self.stm_set(self.stm.saturating_sub(1));
+    }
+
     /// Skip next processing instruction
     fn skip_next_instruction(&mut self) {
-        self.cnt = self.cnt + PRG_INCR;
+        self.cnt = self.cnt.wrapping_add(PRG_INCR);
     }
 
     ///
@@ -363,14 +649,87 @@ impl<D: Display + Sized> Cpu for Emu<D> {
         self.reg[REG_SIZE - 1] = value;
     }
 
+    /// Return the program counter
+    fn cnt_get(&self) -> u16 {
+        self.cnt
+    }
+
+    /// Put the value on the program counter
+    fn cnt_put(&mut self, value: u16) {
+        self.cnt = value;
+    }
+
+    /// Return the memory pointer
+    fn ind_get(&self) -> usize {
+        self.ind
+    }
+
+    /// Put the value on the memory pointer
+    fn ind_put(&mut self, value: usize) {
+        self.ind = value;
+    }
+
+    /// Return the delay timer
+    fn dtm_get(&self) -> u8 {
+        self.dtm
+    }
+
+    /// Put the value on the delay timer
+    fn dtm_put(&mut self, value: u8) {
+        self.dtm = value;
+    }
+
+    /// Return the sound timer
+    fn stm_get(&self) -> u8 {
+        self.stm
+    }
+
+    /// Put the value on the sound timer
+    fn stm_put(&mut self, value: u8) {
+        self.stm_set(value);
+    }
+
+    /// Return the XO-CHIP pitch register
+    fn pit_get(&self) -> u8 {
+        self.pit
+    }
+
+    /// Put the value on the XO-CHIP pitch register, notifying the audio
+    fn pit_put(&mut self, value: u8) {
+        self.pit = value;
+        if let Some(aud) = self.aud.as_mut() {
+            aud.pitch(value);
+        }
+    }
+
+    /// Return the XO-CHIP audio pattern
+    fn pat_get(&self) -> Option<[u8; PATTERN_SIZE]> {
+        self.pat
+    }
+
+    /// Put the XO-CHIP audio pattern, notifying the audio
+    fn pat_put(&mut self, pattern: [u8; PATTERN_SIZE]) {
+        self.pat = Some(pattern);
+        if let Some(aud) = self.aud.as_mut() {
+            aud.pattern(&pattern);
+        }
+    }
+
     /// Return the stack pointer
     fn spt_get(&self) -> usize {
         self.spt
     }
 
-    /// Validate a given stack pointer
+    /// Put the value on the stack pointer
+    fn spt_put(&mut self, spt: usize) -> Result<(), CpuError> {
+        self.spt = self.spt_validate(&spt)?;
+        Ok(())
+    }
+
+    /// Validate a given stack pointer, which may point one past the
+    /// last slot of a full stack
     fn spt_validate(&self, spt: &usize) -> Result<usize, CpuError> {
-        if spt < &STK_SIZE {
+        if spt <= &self.stk.len() {
             Ok(*spt)
         } else {
             Err(CpuError::new(CpuErrorVariant::StackOverflow(*spt)))
@@ -379,36 +738,55 @@ impl<D: Display + Sized> Cpu for Emu<D> {
 
     /// Increment the stack pointer
     fn spt_inc(&mut self) -> Result<usize, CpuError> {
-        let spt = self.spt_validate(&(self.spt_get() + 1)).unwrap();
-        self.spt = spt;
+        self.spt = self.spt_validate(&(self.spt + 1))?;
         Ok(self.spt)
     }
 
     /// Decrement the stack pointer
     fn spt_dec(&mut self) -> Result<usize, CpuError> {
-        let spt = self.spt_validate(&(self.spt_get() - 1)).unwrap();
-        self.spt = spt;
-        Ok(self.spt)
+        match self.spt.checked_sub(1) {
+            Some(spt) => {
+                self.spt = spt;
+                Ok(spt)
+            }
+            None => Err(CpuError::new(CpuErrorVariant::StackUnderflow)),
+        }
     }
 
-    /// Return a stack pointer
+    /// Return the address on top of the stack
     fn stk_get(&self) -> Result<u16, CpuError> {
-        Ok(self.stk[self.spt_get()])
+        match self.spt.checked_sub(1) {
+            Some(spt) => Ok(self.stk[spt]),
+            None => Err(CpuError::new(CpuErrorVariant::StackUnderflow)),
+        }
+    }
+
+    /// Return the address stored on a given stack pointer
+    fn stk_peek(&self, spt: &usize) -> Result<u16, CpuError> {
+        match self.stk.get(*spt) {
+            Some(address) => Ok(*address),
+            None => Err(CpuError::new(CpuErrorVariant::StackOverflow(*spt))),
+        }
     }
 
     /// Pop the address from the stack
     fn stk_pop(&mut self) -> Result<u16, CpuError> {
-        let stk = self.stk_get().unwrap();
-        self.spt_dec().unwrap();
-        Ok(stk)
+        let spt = self.spt_dec()?;
+        Ok(self.stk[spt])
     }
 
     /// Push the address on the stack
     fn stk_push(&mut self, address: u16) -> Result<(), CpuError> {
-        let spt = self.spt_inc().unwrap();
+        let spt = self.spt;
+        self.spt_inc()?;
         self.stk[spt] = address;
         Ok(())
     }
+
+    /// Return the return addresses on the stack, outermost first
+    fn call_stack(&self) -> &[u16] {
+        &self.stk[..self.spt]
+    }
 }
 
 impl<D: Display + Sized> Mem for Emu<D> {
@@ -428,21 +806,21 @@ impl<D: Display + Sized> Mem for Emu<D> {
         range: T,
     ) -> Result<(usize, usize), MemError> {
         let start = match range.start_bound() {
-            Included(i) => i.clone(),
-            Excluded(i) => i + 1,
-            Unbounded => 0,
+            Included(i) => Some(*i),
+            Excluded(i) => i.checked_add(1),
+            Unbounded => Some(0),
         };
         let end = match range.end_bound() {
-            Included(i) => i.clone(),
-            Excluded(i) => i.clone(),
-            Unbounded => self.max_size(),
+            Included(i) => i.checked_add(1),
+            Excluded(i) => Some(*i),
+            Unbounded => Some(self.max_size()),
         };
-        if self.validate_index(&start) && self.validate_index(&(end - 1)) {
-            Ok((start, end))
-        } else {
-            Err(MemError::new(MemErrorVariant::AccessRangeViolation(
-                start, end,
-            )))
+        match (start, end) {
+            (Some(start), Some(end)) if start <= end && end <= self.max_size() => Ok((start, end)),
+            (start, end) => Err(MemError::new(MemErrorVariant::AccessRangeViolation(
+                start.unwrap_or(usize::MAX),
+                end.unwrap_or(usize::MAX),
+            ))),
         }
     }
 
@@ -457,23 +835,20 @@ impl<D: Display + Sized> Mem for Emu<D> {
     /// Get the memory content of a given index
     fn mem_get(&self, index: &usize) -> Result<&u8, MemError> {
         if self.validate_index(index) {
+            self.mem_notify(*index, self.mem[*index], MemAccessKind::Read);
             Ok(&self.mem[*index])
         } else {
-            Err(MemError::new(MemErrorVariant::AccessViolation(
-                index.clone(),
-            )))
+            Err(MemError::new(MemErrorVariant::AccessViolation(*index)))
         }
     }
 
     /// Put a given value in a given index of the memory range
     fn mem_put(&mut self, index: &usize, value: u8) -> Result<(), MemError> {
         if self.validate_index(index) {
-            self.mem[*index] = value;
+            self.mem_store(*index, value);
             Ok(())
         } else {
-            Err(MemError::new(MemErrorVariant::AccessViolation(
-                index.clone(),
-            )))
+            Err(MemError::new(MemErrorVariant::AccessViolation(*index)))
         }
     }
 
@@ -483,7 +858,14 @@ impl<D: Display + Sized> Mem for Emu<D> {
         range: T,
     ) -> Result<&<T as SliceIndex<[u8]>>::Output, MemError> {
         match self.range_get_start_end(range.clone()) {
-            Ok(_) => Ok(&self.mem[range]),
+            Ok((start, end)) => {
+                if !self.obs.borrow().is_empty() {
+                    for i in start..end {
+                        self.mem_notify(i, self.mem[i], MemAccessKind::Read);
+                    }
+                }
+                Ok(&self.mem[range])
+            }
             Err(e) => Err(e),
         }
     }
@@ -495,9 +877,9 @@ impl<D: Display + Sized> Mem for Emu<D> {
         slice: &[u8],
     ) -> Result<(), MemError> {
         match self.range_get_start_end(range.clone()) {
-            Ok((start, _)) => {
-                for (i, v) in slice.iter().enumerate() {
-                    self.mem[start + i] = v.clone();
+            Ok((start, end)) => {
+                for (i, v) in (start..end).zip(slice.iter()) {
+                    self.mem_store(i, *v);
                 }
                 Ok(())
             }
@@ -509,6 +891,7 @@ impl<D: Display + Sized> Mem for Emu<D> {
 impl<D: Display + Sized> Keypad for Emu<D> {
     fn key_from_u8(&self, k: &u8) -> Key {
         match k {
+            0x0 => Key::K0,
             0x1 => Key::K1,
             0x2 => Key::K2,
             0x3 => Key::K3,
@@ -530,6 +913,7 @@ impl<D: Display + Sized> Keypad for Emu<D> {
 
     fn key_to_u8(&self, k: &Key) -> u8 {
         match k {
+            Key::K0 => 0x0,
             Key::K1 => 0x1,
             Key::K2 => 0x2,
             Key::K3 => 0x3,
@@ -551,21 +935,22 @@ impl<D: Display + Sized> Keypad for Emu<D> {
 
     fn key_to_index(&self, k: &Key) -> Option<usize> {
         match k {
-            Key::K1 => Some(0),
-            Key::K2 => Some(1),
-            Key::K3 => Some(2),
-            Key::K4 => Some(3),
-            Key::K5 => Some(4),
-            Key::K6 => Some(5),
-            Key::K7 => Some(6),
-            Key::K8 => Some(7),
-            Key::K9 => Some(8),
-            Key::KA => Some(9),
-            Key::KB => Some(10),
-            Key::KC => Some(11),
-            Key::KD => Some(12),
-            Key::KE => Some(13),
-            Key::KF => Some(14),
+            Key::K0 => Some(0),
+            Key::K1 => Some(1),
+            Key::K2 => Some(2),
+            Key::K3 => Some(3),
+            Key::K4 => Some(4),
+            Key::K5 => Some(5),
+            Key::K6 => Some(6),
+            Key::K7 => Some(7),
+            Key::K8 => Some(8),
+            Key::K9 => Some(9),
+            Key::KA => Some(10),
+            Key::KB => Some(11),
+            Key::KC => Some(12),
+            Key::KD => Some(13),
+            Key::KE => Some(14),
+            Key::KF => Some(15),
             Key::Unknown => None,
         }
     }
@@ -578,7 +963,9 @@ impl<D: Display + Sized> Keypad for Emu<D> {
     }
 
     fn any_key_pressed(&self) -> Option<Key> {
-        if self.key_pressed(&Key::K1) {
+        if self.key_pressed(&Key::K0) {
+            Some(Key::K0)
+        } else if self.key_pressed(&Key::K1) {
             Some(Key::K1)
         } else if self.key_pressed(&Key::K2) {
             Some(Key::K2)
@@ -612,10 +999,20 @@ impl<D: Display + Sized> Keypad for Emu<D> {
             None
         }
     }
+
+    fn key_put(&mut self, key: &Key, pressed: bool) {
+        if let Some(i) = self.key_to_index(key) {
+            self.key[i] = pressed;
+        }
+    }
 }
 
 impl<D: Display + Sized> DisplayEmu<D> for Emu<D> {
     fn set_display(&mut self, display: D) {
         self.dsp = display;
     }
+
+    fn get_display(&self) -> &D {
+        &self.dsp
+    }
 }
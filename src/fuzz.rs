@@ -0,0 +1,189 @@
+use crate::cpu::{Cpu, CpuError, CpuErrorVariant};
+use crate::debugger::Register;
+use crate::display::{Display, DisplayBuffer};
+use crate::emu::{Emu, PRG_START, REG_SIZE};
+use crate::keypad::Keypad;
+use crate::mem::Mem;
+use crate::oper::{Oper, OperCode};
+use crate::quirk::QuirkPreset;
+
+/// Instructions stepped by the execution target
+const FUZZ_STEPS: usize = 512_usize;
+
+/// Instructions stepped between two timer ticks
+const FUZZ_IPF: usize = 16_usize;
+
+/// Header bytes of the execution target: preset, seed, keys and a word
+/// for each of the 22 entries of `Register::all`
+pub const EXECUTE_HEADER: usize = 4 + 2 * 22;
+
+/// Bytes of each range probed by the memory target
+const RANGE_SIZE: usize = 5_usize;
+
+/// Infinite source of bytes, padding the input with zeroes
+struct Input<'a>(std::slice::Iter<'a, u8>);
+
+impl<'a> Input<'a> {
+    fn u8(&mut self) -> u8 {
+        self.0.next().cloned().unwrap_or(0)
+    }
+
+    fn u16(&mut self) -> u16 {
+        u16::from_be_bytes([self.u8(), self.u8()])
+    }
+}
+
+///
+/// Decode every word of the input, the first byte selecting a register
+/// capacity up to `REG_SIZE`
+///
+/// Panics if an operation decoded with the reduced capacity is known but
+/// differs from the one decoded with `REG_SIZE`, or if the encoding of
+/// an operation decodes to another one.
+///
+/// # Example
+///
+/// ```
+/// use rc201_8::fuzz;
+///
+/// // Capacity of 2 registers, then 8XY4 on V1 and VF
+/// fuzz::decode(&[0x02, 0x81, 0xF4]);
+/// ```
+///
+pub fn decode(data: &[u8]) {
+    let (rsize, words) = match data.split_first() {
+        Some((r, w)) => (usize::from(*r) % (REG_SIZE + 1), w),
+        None => return,
+    };
+    for word in words.chunks_exact(2) {
+        let code = u16::from_be_bytes([word[0], word[1]]);
+        let oper = Oper::from_code(&code, &rsize);
+        let full = Oper::from_code(&code, &REG_SIZE);
+        assert!(
+            oper == OperCode::Unknown || oper == full,
+            "{:04X} decoded as {:?} with {} registers, and as {:?}",
+            code,
+            oper,
+            rsize,
+            full
+        );
+        if let Some(c) = full.code() {
+            assert_eq!(Oper::from_code(&c, &REG_SIZE), full, "{:04X}", code);
+        }
+        let _ = oper.to_string();
+    }
+}
+
+///
+/// Set the emulator state from the header of the input, load the rest
+/// as a ROM, process each of its words through `recv_opcode` and then
+/// step through it
+///
+/// Panics if an error does not describe the failure, such as an invalid
+/// operation code reported for a known operation.
+///
+/// # Example
+///
+/// ```
+/// use rc201_8::fuzz::{self, EXECUTE_HEADER};
+///
+/// // Return from a subroutine that was never called
+/// let mut data = vec![0; EXECUTE_HEADER];
+/// data.extend_from_slice(&[0x00, 0xEE]);
+/// fuzz::execute(&data);
+/// ```
+///
+pub fn execute(data: &[u8]) {
+    let mut input = Input(data.iter());
+    let mut emu = Emu::new(DisplayBuffer::new());
+
+    let presets = QuirkPreset::all();
+    let preset = presets[usize::from(input.u8()) % presets.len()];
+    emu.quirks_put(preset.quirks());
+    emu.stack_depth_put(preset.stack_depth());
+    emu.rng_seed(u64::from(input.u8()));
+    let keys = input.u16();
+    for k in 0..16 {
+        let key = emu.key_from_u8(&k);
+        emu.key_put(&key, keys & 1 << k != 0);
+    }
+
+    let rom = data.get(EXECUTE_HEADER..).unwrap_or(&[]);
+    let rom = &rom[..rom.len().min(emu.max_size() - PRG_START)];
+    emu.load_rom(rom).unwrap();
+    for register in Register::all() {
+        let value = input.u16();
+        if let Err(e) = register.write(&mut emu, value) {
+            error_check(&e, None);
+        }
+    }
+
+    for word in rom.chunks_exact(2) {
+        let code = u16::from_be_bytes([word[0], word[1]]);
+        if let Err(e) = emu.recv_opcode(&code) {
+            error_check(&e, Some(code));
+        }
+    }
+
+    for step in 0..FUZZ_STEPS {
+        let code = emu.fetch_opcode().ok();
+        if let Err(e) = emu.step() {
+            error_check(&e, code);
+            break;
+        }
+        if step % FUZZ_IPF == FUZZ_IPF - 1 {
+            emu.timers_tick();
+        }
+    }
+}
+
+///
+/// Probe the memory with the ranges of the input, five bytes each: the
+/// kind of range and its two bounds
+///
+/// Panics if a valid range is rejected or an invalid one accepted.
+///
+/// # Example
+///
+/// ```
+/// use rc201_8::fuzz;
+///
+/// // Empty range ending on 0, then a range starting past its end
+/// fuzz::memory(&[0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x05, 0x00, 0x03]);
+/// ```
+///
+pub fn memory(data: &[u8]) {
+    let mut emu = Emu::new(DisplayBuffer::new());
+    let size = emu.max_size();
+    for range in data.chunks_exact(RANGE_SIZE) {
+        let mut input = Input(range.iter());
+        let kind = input.u8();
+        let a = usize::from(input.u16());
+        let b = usize::from(input.u16());
+        let (read, bounds) = match kind % 6 {
+            0 => (emu.mem_read(a..b).map(<[u8]>::len), Some((a, b))),
+            1 => (emu.mem_read(a..=b).map(<[u8]>::len), Some((a, b + 1))),
+            2 => (emu.mem_read(a..).map(<[u8]>::len), Some((a, size))),
+            3 => (emu.mem_read(..b).map(<[u8]>::len), Some((0, b))),
+            4 => (emu.mem_get(&a).map(|_| 1), Some((a, a + 1))),
+            _ => (emu.mem_write(a..b, &range[1..]).map(|_| 0), None),
+        };
+        let valid = match bounds {
+            Some((start, end)) => start <= end && end <= size,
+            None => a <= b && b <= size,
+        };
+        assert_eq!(read.is_ok(), valid, "range {:?} of kind {}", (a, b), kind);
+        if let (Ok(len), Some((start, end))) = (read, bounds) {
+            assert_eq!(len, end - start, "range {:?} of kind {}", (a, b), kind);
+        }
+    }
+}
+
+/// Panic if an error does not match the operation code that caused it
+fn error_check(err: &CpuError, code: Option<u16>) {
+    if let CpuErrorVariant::InvalidOperationCode(c) = err.variant() {
+        assert_eq!(code, Some(*c), "{}", err);
+        assert_eq!(Oper::from_code(c, &REG_SIZE), OperCode::Unknown, "{}", err);
+    }
+    assert!(!err.to_string().is_empty());
+}
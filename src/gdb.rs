@@ -0,0 +1,478 @@
+use crate::cpu::{Cpu, CpuError};
+use crate::debugger::{Debugger, Register, StopReason};
+use crate::mem::Mem;
+use std::io::{self, ErrorKind, Read, Write};
+use std::net::{TcpListener, TcpStream, ToSocketAddrs};
+
+/// Instructions processed between two interrupt polls while continuing
+const RESUME_CHUNK: usize = 10_000_usize;
+
+/// Byte sent by the client to interrupt a running target
+const INTERRUPT: u8 = 0x03_u8;
+
+/// Target description advertised through qXfer:features:read
+const TARGET_XML: &str = "<?xml version=\"1.0\"?>\
+<!DOCTYPE target SYSTEM \"gdb-target.dtd\">\
+<target version=\"1.0\">\
+<feature name=\"org.rc201_8.chip8\">\
+<reg name=\"v0\" bitsize=\"8\" regnum=\"0\" type=\"uint8\"/>\
+<reg name=\"v1\" bitsize=\"8\" type=\"uint8\"/>\
+<reg name=\"v2\" bitsize=\"8\" type=\"uint8\"/>\
+<reg name=\"v3\" bitsize=\"8\" type=\"uint8\"/>\
+<reg name=\"v4\" bitsize=\"8\" type=\"uint8\"/>\
+<reg name=\"v5\" bitsize=\"8\" type=\"uint8\"/>\
+<reg name=\"v6\" bitsize=\"8\" type=\"uint8\"/>\
+<reg name=\"v7\" bitsize=\"8\" type=\"uint8\"/>\
+<reg name=\"v8\" bitsize=\"8\" type=\"uint8\"/>\
+<reg name=\"v9\" bitsize=\"8\" type=\"uint8\"/>\
+<reg name=\"va\" bitsize=\"8\" type=\"uint8\"/>\
+<reg name=\"vb\" bitsize=\"8\" type=\"uint8\"/>\
+<reg name=\"vc\" bitsize=\"8\" type=\"uint8\"/>\
+<reg name=\"vd\" bitsize=\"8\" type=\"uint8\"/>\
+<reg name=\"ve\" bitsize=\"8\" type=\"uint8\"/>\
+<reg name=\"vf\" bitsize=\"8\" type=\"uint8\"/>\
+<reg name=\"i\" bitsize=\"16\" type=\"data_ptr\"/>\
+<reg name=\"pc\" bitsize=\"16\" type=\"code_ptr\"/>\
+<reg name=\"sp\" bitsize=\"8\" type=\"uint8\"/>\
+<reg name=\"dt\" bitsize=\"8\" type=\"uint8\"/>\
+<reg name=\"st\" bitsize=\"8\" type=\"uint8\"/>\
+<reg name=\"pitch\" bitsize=\"8\" type=\"uint8\"/>\
+</feature>\
+</target>";
+
+///
+/// Transport for the remote serial protocol
+///
+pub trait GdbConnection: Read + Write {
+    ///
+    /// Consume a pending interrupt request without blocking
+    ///
+    /// Returns true if the client sent the interrupt byte
+    ///
+    fn poll_interrupt(&mut self) -> io::Result<bool>;
+}
+
+impl GdbConnection for TcpStream {
+    fn poll_interrupt(&mut self) -> io::Result<bool> {
+        let mut buf = [0_u8];
+        self.set_nonblocking(true)?;
+        let interrupt = loop {
+            match self.peek(&mut buf) {
+                // Stray acknowledgments are dropped, packets are left in place
+                Ok(1) if buf[0] != b'$' => {
+                    self.read_exact(&mut buf)?;
+                    if buf[0] == INTERRUPT {
+                        break Ok(true);
+                    }
+                }
+                Ok(_) => break Ok(false),
+                Err(ref e) if e.kind() == ErrorKind::WouldBlock => break Ok(false),
+                Err(e) => break Err(e),
+            }
+        };
+        self.set_nonblocking(false)?;
+        interrupt
+    }
+}
+
+///
+/// GDB remote serial protocol stub
+///
+/// Supports register and memory access, software breakpoints (`Z0`),
+/// single-step and continue. Registers are transferred in the order of
+/// `Register::all`, with I and PC encoded as big-endian words, so the
+/// client should run `set endian big`.
+///
+/// # Example
+///
+/// ```
+/// use rc201_8::emu::Emu;
+/// use rc201_8::cpu::Cpu;
+/// use rc201_8::gdb::GdbStub;
+/// use rc201_8::display::{Display, DisplayDummy};
+/// use std::io::{Read, Write};
+/// use std::net::{TcpListener, TcpStream};
+/// use std::thread;
+///
+/// let listener = TcpListener::bind("127.0.0.1:0").unwrap();
+/// let addr = listener.local_addr().unwrap();
+///
+/// let client = thread::spawn(move || {
+///     let mut stream = TcpStream::connect(addr).unwrap();
+///     // Write 0x0200 on the program counter (register 0x11)
+///     stream.write_all(b"$P11=0200#b1").unwrap();
+///     let mut reply = [0_u8; 7];
+///     stream.read_exact(&mut reply).unwrap();
+///     assert_eq!(&reply, b"+$OK#9a");
+///     stream.write_all(b"+$D#44").unwrap();
+///     stream.read_exact(&mut reply).unwrap();
+///     assert_eq!(&reply, b"+$OK#9a");
+/// });
+///
+/// let mut emu = Emu::new(DisplayDummy::new());
+/// let (stream, _) = listener.accept().unwrap();
+/// GdbStub::new(stream).serve(&mut emu).unwrap();
+/// client.join().unwrap();
+///
+/// assert_eq!(emu.cnt_get(), 0x200);
+/// ```
+///
+pub struct GdbStub<S: GdbConnection> {
+    /// Client connection
+    stream: S,
+
+    /// Breakpoint and step machinery
+    debugger: Debugger,
+
+    /// Acknowledge mode, disabled by QStartNoAckMode
+    ack: bool,
+
+    /// Last sent packet, kept for retransmission
+    last: Vec<u8>,
+}
+
+impl<S: GdbConnection> GdbStub<S> {
+    ///
+    /// Returns a new GdbStub instance for an established connection
+    ///
+    pub fn new(stream: S) -> GdbStub<S> {
+        GdbStub {
+            stream,
+            debugger: Debugger::new(),
+            ack: true,
+            last: Vec::new(),
+        }
+    }
+
+    ///
+    /// Return the breakpoint machinery
+    ///
+    pub fn debugger(&mut self) -> &mut Debugger {
+        &mut self.debugger
+    }
+
+    ///
+    /// Serve requests until the client detaches, kills the target or
+    /// closes the connection
+    ///
+    pub fn serve<E: Cpu + Mem>(&mut self, emu: &mut E) -> io::Result<()> {
+        while let Some(packet) = self.packet_recv()? {
+            match self.packet_handle(emu, &packet)? {
+                Some(reply) => self.packet_send(reply.as_bytes())?,
+                None => return Ok(()),
+            }
+        }
+        Ok(())
+    }
+
+    /// Read the next packet, handling acknowledgments and interrupts;
+    /// packets with a bad checksum are rejected and the next one is read
+    fn packet_recv(&mut self) -> io::Result<Option<String>> {
+        let mut byte = [0_u8];
+        loop {
+            loop {
+                if self.stream.read(&mut byte)? == 0 {
+                    return Ok(None);
+                }
+                match byte[0] {
+                    b'$' => break,
+                    b'-' => {
+                        let last = self.last.clone();
+                        self.stream.write_all(&last)?;
+                    }
+                    INTERRUPT => self.packet_send(b"S02")?,
+                    _ => (),
+                }
+            }
+
+            let mut data = Vec::new();
+            loop {
+                if self.stream.read(&mut byte)? == 0 {
+                    return Ok(None);
+                }
+                if byte[0] == b'#' {
+                    break;
+                }
+                data.push(byte[0]);
+            }
+
+            let mut sum = [0_u8; 2];
+            self.stream.read_exact(&mut sum)?;
+            let valid = std::str::from_utf8(&sum)
+                .ok()
+                .and_then(|s| u8::from_str_radix(s, 16).ok())
+                .is_some_and(|s| s == checksum(&data));
+            if self.ack {
+                self.stream.write_all(if valid { b"+" } else { b"-" })?;
+            }
+            if valid {
+                return Ok(Some(String::from_utf8_lossy(&data).into_owned()));
+            }
+        }
+    }
+
+    /// Frame and send a packet
+    fn packet_send(&mut self, data: &[u8]) -> io::Result<()> {
+        let mut packet = Vec::with_capacity(data.len() + 4);
+        packet.push(b'$');
+        packet.extend_from_slice(data);
+        packet.extend_from_slice(format!("#{:02x}", checksum(data)).as_bytes());
+        self.stream.write_all(&packet)?;
+        self.stream.flush()?;
+        self.last = packet;
+        Ok(())
+    }
+
+    /// Process a packet, returning None if the session is over
+    fn packet_handle<E: Cpu + Mem>(
+        &mut self,
+        emu: &mut E,
+        packet: &str,
+    ) -> io::Result<Option<String>> {
+        let (cmd, args) = packet.split_at(packet.chars().next().map_or(0, |c| c.len_utf8()));
+        let reply = match cmd {
+            "?" => String::from("S05"),
+            "g" => registers_read(emu),
+            "G" => ok_or_error(registers_write(emu, args)),
+            "p" => match usize::from_str_radix(args, 16) {
+                Ok(n) if n < Register::all().len() => register_hex(emu, &Register::all()[n]),
+                _ => String::from("E01"),
+            },
+            "P" => ok_or_error(register_write(emu, args)),
+            "m" => memory_read(emu, args).unwrap_or_else(|| String::from("E01")),
+            "M" => ok_or_error(memory_write(emu, args)),
+            "Z" | "z" => self.breakpoint(cmd == "Z", args),
+            "s" => {
+                resume_at(emu, args);
+                stop_reply(self.debugger.step(emu))
+            }
+            "c" => {
+                resume_at(emu, args);
+                self.resume(emu)?
+            }
+            "H" => String::from("OK"),
+            "k" => return Ok(None),
+            "D" => {
+                self.packet_send(b"OK")?;
+                return Ok(None);
+            }
+            _ => self.query(packet),
+        };
+        Ok(Some(reply))
+    }
+
+    /// Continue until a breakpoint, an error or an interrupt
+    fn resume<E: Cpu + Mem>(&mut self, emu: &mut E) -> io::Result<String> {
+        loop {
+            match self.debugger.resume(emu, RESUME_CHUNK) {
+                Ok(StopReason::Limit) => {
+                    if self.stream.poll_interrupt()? {
+                        return Ok(String::from("S02"));
+                    }
+                }
+                reply => return Ok(stop_reply(reply)),
+            }
+        }
+    }
+
+    /// Insert or remove a software breakpoint
+    fn breakpoint(&mut self, insert: bool, args: &str) -> String {
+        let mut fields = args.split(',');
+        match (
+            fields.next(),
+            fields.next().map(|a| u16::from_str_radix(a, 16)),
+        ) {
+            (Some("0"), Some(Ok(address))) => {
+                if insert {
+                    self.debugger.breakpoint_insert(address);
+                } else {
+                    self.debugger.breakpoint_remove(address);
+                }
+                String::from("OK")
+            }
+            (Some("0"), _) => String::from("E01"),
+            _ => String::new(),
+        }
+    }
+
+    /// Answer general queries and settings
+    fn query(&mut self, packet: &str) -> String {
+        if packet.starts_with("qSupported") {
+            String::from("PacketSize=1000;qXfer:features:read+;QStartNoAckMode+")
+        } else if let Some(range) = packet.strip_prefix("qXfer:features:read:target.xml:") {
+            xfer(TARGET_XML, range)
+        } else if packet == "QStartNoAckMode" {
+            self.ack = false;
+            String::from("OK")
+        } else if packet == "qAttached" {
+            String::from("1")
+        } else if packet == "qfThreadInfo" {
+            String::from("m1")
+        } else if packet == "qsThreadInfo" {
+            String::from("l")
+        } else if packet == "qC" {
+            String::from("QC1")
+        } else {
+            String::new()
+        }
+    }
+}
+
+///
+/// Wait for a single client on a given address and serve it
+///
+/// # Example
+///
+/// ```no_run
+/// use rc201_8::emu::Emu;
+/// use rc201_8::gdb;
+/// use rc201_8::display::{Display, DisplayDummy};
+///
+/// let mut emu = Emu::new(DisplayDummy::new());
+///
+/// // (gdb) target remote localhost:2159
+/// gdb::listen(&mut emu, "127.0.0.1:2159").unwrap();
+/// ```
+///
+pub fn listen<E: Cpu + Mem, A: ToSocketAddrs>(emu: &mut E, addr: A) -> io::Result<()> {
+    let listener = TcpListener::bind(addr)?;
+    let (stream, _) = listener.accept()?;
+    stream.set_nodelay(true)?;
+    GdbStub::new(stream).serve(emu)
+}
+
+/// Modulo 256 sum of the packet data
+fn checksum(data: &[u8]) -> u8 {
+    data.iter().fold(0_u8, |s, b| s.wrapping_add(*b))
+}
+
+/// Parse a hexadecimal byte string
+fn hex_decode(hex: &str) -> Option<Vec<u8>> {
+    if !hex.len().is_multiple_of(2) {
+        return None;
+    }
+    (0..hex.len())
+        .step_by(2)
+        .map(|i| {
+            hex.get(i..i + 2)
+                .and_then(|b| u8::from_str_radix(b, 16).ok())
+        })
+        .collect()
+}
+
+/// Encode bytes as a hexadecimal string
+fn hex_encode(bytes: &[u8]) -> String {
+    bytes.iter().map(|b| format!("{:02x}", b)).collect()
+}
+
+/// Encode a register value in target byte order
+fn register_hex<C: Cpu>(cpu: &C, register: &Register) -> String {
+    match register.read(cpu) {
+        Ok(v) if register.size() == 2 => format!("{:04x}", v),
+        Ok(v) => format!("{:02x}", v),
+        Err(_) => String::from("E01"),
+    }
+}
+
+/// Encode the full register file
+fn registers_read<C: Cpu>(cpu: &C) -> String {
+    Register::all()
+        .iter()
+        .map(|r| register_hex(cpu, r))
+        .collect()
+}
+
+/// Decode and write the full register file
+fn registers_write<C: Cpu>(cpu: &mut C, args: &str) -> Option<()> {
+    let bytes = hex_decode(args)?;
+    let mut offset = 0;
+    for register in Register::all() {
+        let value = bytes
+            .get(offset..offset + register.size())?
+            .iter()
+            .fold(0_u16, |v, b| v << 8 | u16::from(*b));
+        register.write(cpu, value).ok()?;
+        offset += register.size();
+    }
+    Some(())
+}
+
+/// Decode and write a single register from `n=value`
+fn register_write<C: Cpu>(cpu: &mut C, args: &str) -> Option<()> {
+    let mut fields = args.splitn(2, '=');
+    let n = usize::from_str_radix(fields.next()?, 16).ok()?;
+    let register = *Register::all().get(n)?;
+    let value = hex_decode(fields.next()?)?
+        .iter()
+        .fold(0_u16, |v, b| v << 8 | u16::from(*b));
+    register.write(cpu, value).ok()
+}
+
+/// Parse an `address,length` pair
+fn address_length(args: &str) -> Option<(usize, usize)> {
+    let mut fields = args.splitn(2, ',');
+    let address = usize::from_str_radix(fields.next()?, 16).ok()?;
+    let length = usize::from_str_radix(fields.next()?, 16).ok()?;
+    Some((address, length))
+}
+
+/// Read memory from `address,length`
+fn memory_read<M: Mem>(mem: &M, args: &str) -> Option<String> {
+    let (address, length) = address_length(args)?;
+    if length == 0 {
+        return Some(String::new());
+    }
+    let end = address.checked_add(length)?;
+    mem.mem_read(address..end).ok().map(hex_encode)
+}
+
+/// Write memory from `address,length:bytes`
+fn memory_write<M: Mem>(mem: &mut M, args: &str) -> Option<()> {
+    let mut fields = args.splitn(2, ':');
+    let (address, length) = address_length(fields.next()?)?;
+    let bytes = hex_decode(fields.next()?)?;
+    if bytes.len() != length {
+        return None;
+    }
+    if length == 0 {
+        return Some(());
+    }
+    let end = address.checked_add(length)?;
+    mem.mem_write(address..end, &bytes).ok()
+}
+
+/// Move the program counter for `s` and `c` packets with an address
+fn resume_at<C: Cpu>(cpu: &mut C, args: &str) {
+    if let Ok(address) = u16::from_str_radix(args, 16) {
+        cpu.cnt_put(address);
+    }
+}
+
+/// Translate a debugger result into a stop reply packet
+fn stop_reply(reply: Result<StopReason, CpuError>) -> String {
+    match reply {
+        Ok(StopReason::Breakpoint(_)) => String::from("T05swbreak:;"),
+        Ok(_) => String::from("S05"),
+        Err(_) => String::from("S04"),
+    }
+}
+
+/// Reply OK or a generic error
+fn ok_or_error(result: Option<()>) -> String {
+    match result {
+        Some(_) => String::from("OK"),
+        None => String::from("E01"),
+    }
+}
+
+/// Serve a chunk of an annex for `offset,length`
+fn xfer(annex: &str, range: &str) -> String {
+    match address_length(range) {
+        Some((offset, _)) if offset >= annex.len() => String::from("l"),
+        Some((offset, length)) if offset.saturating_add(length) >= annex.len() => {
+            format!("l{}", &annex[offset..])
+        }
+        Some((offset, length)) => format!("m{}", &annex[offset..offset + length]),
+        None => String::from("E01"),
+    }
+}
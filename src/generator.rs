@@ -0,0 +1,307 @@
+use crate::emu::{MEM_SIZE, PRG_START, REG_SIZE, STK_SIZE};
+use crate::oper::OperCode;
+use rand::rngs::StdRng;
+use rand::{Rng, SeedableRng};
+
+/// Bytes of the data area following the code
+const DATA_SIZE: u16 = 0x40_u16;
+
+/// Bytes an operation may access from I
+const DATA_SPAN: u16 = 0x10_u16;
+
+/// Most operations expanded from a single unit
+const UNIT_OPERS: usize = 3_usize;
+
+/// Bytes of each operation
+const OPER_SIZE: u16 = 2_u16;
+
+///
+/// Shape of the generated programs
+///
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct GenConfig {
+    /// Units of the main routine and of each subroutine, a unit being
+    /// one operation or a short idiom such as `ANNN` followed by `FX55`
+    pub routine_size: usize,
+
+    /// Number of subroutines, which is also the deepest call chain
+    pub subroutines: usize,
+
+    /// Depth of the call stack the programs run on, which must hold
+    /// the deepest call chain
+    pub stack_depth: usize,
+
+    /// Whether `KeyOpFX0A` may be generated, blocking the program until
+    /// a key is pressed
+    pub key_wait: bool,
+}
+
+impl Default for GenConfig {
+    fn default() -> GenConfig {
+        GenConfig {
+            routine_size: 32,
+            subroutines: 4,
+            stack_depth: STK_SIZE,
+            key_wait: false,
+        }
+    }
+}
+
+/// Operation whose address is resolved once the program is laid out
+#[derive(Debug, Clone, Copy)]
+enum Slot {
+    /// Operation without addresses
+    Oper(OperCode),
+
+    /// `Flow1NNN` to a unit of the same routine
+    Jump(usize),
+
+    /// `Flow2NNN` to a subroutine, 1 being the first one
+    Call(usize),
+
+    /// `MemANNN` to an offset of the data area
+    Index(u16),
+
+    /// `FlowBNNN` to a unit of the same routine, clearing V0 and VX
+    Indirect(usize),
+}
+
+impl Slot {
+    /// Number of operations the slot expands to
+    fn len(&self) -> usize {
+        match self {
+            Slot::Indirect(_) => 3,
+            _ => 1,
+        }
+    }
+}
+
+///
+/// Generator of random, well formed programs
+///
+/// Programs start with the main routine on `PRG_START`, followed by the
+/// subroutines and a data area holding random bytes. Jumps only go
+/// forward inside their routine, subroutines only call the ones after
+/// them, and every operation accessing memory through I sets I into the
+/// data area first. The main routine ends on a jump to itself, so every
+/// program halts unless `key_wait` is set.
+///
+/// # Example
+///
+/// ```
+/// use rc201_8::emu::Emu;
+/// use rc201_8::cpu::Cpu;
+/// use rc201_8::generator::{GenConfig, Generator};
+/// use rc201_8::display::{Display, DisplayDummy};
+///
+/// let mut gen = Generator::new(7, GenConfig::default()).unwrap();
+/// let rom = gen.program();
+///
+/// let mut emu = Emu::new(DisplayDummy::new());
+/// emu.load_rom(&rom).unwrap();
+/// loop {
+///     let cnt = emu.cnt_get();
+///     emu.step().unwrap();
+///     if emu.cnt_get() == cnt {
+///         break;
+///     }
+/// }
+/// assert_eq!(emu.spt_get(), 0);
+/// ```
+///
+pub struct Generator {
+    /// Random generator
+    rng: StdRng,
+
+    /// Shape of the programs
+    config: GenConfig,
+}
+
+impl Generator {
+    ///
+    /// Returns a new Generator instance, or None if the programs of a
+    /// given configuration may not fit the memory or the stack
+    ///
+    /// # Example
+    ///
+    /// ```
+    /// use rc201_8::generator::{GenConfig, Generator};
+    ///
+    /// let config = GenConfig {
+    ///     routine_size: 1000,
+    ///     ..GenConfig::default()
+    /// };
+    ///
+    /// assert!(Generator::new(0, config).is_none());
+    /// ```
+    ///
+    pub fn new(seed: u64, config: GenConfig) -> Option<Generator> {
+        let units = (config.routine_size + 1) * (config.subroutines + 1);
+        let size = units * UNIT_OPERS * OPER_SIZE as usize + DATA_SIZE as usize;
+        if PRG_START + size > MEM_SIZE || config.subroutines > config.stack_depth {
+            return None;
+        }
+        Some(Generator {
+            rng: StdRng::seed_from_u64(seed),
+            config,
+        })
+    }
+
+    ///
+    /// Generate a program, returning its ROM
+    ///
+    pub fn program(&mut self) -> Vec<u8> {
+        let routines: Vec<Vec<Vec<Slot>>> = (0..=self.config.subroutines)
+            .map(|r| self.routine(r))
+            .collect();
+
+        // Addresses of the units of each routine, the terminator included
+        let mut address = PRG_START as u16;
+        let mut starts = Vec::with_capacity(routines.len());
+        for routine in &routines {
+            let mut units = Vec::with_capacity(routine.len());
+            for unit in routine {
+                units.push(address);
+                address += unit.iter().map(Slot::len).sum::<usize>() as u16 * OPER_SIZE;
+            }
+            starts.push(units);
+        }
+        let data = address;
+
+        let mut rom = Vec::new();
+        for (r, routine) in routines.iter().enumerate() {
+            for slot in routine.iter().flatten() {
+                let opers = match *slot {
+                    Slot::Oper(oper) => vec![oper],
+                    Slot::Jump(u) => vec![OperCode::Flow1NNN(starts[r][u])],
+                    Slot::Call(s) => vec![OperCode::Flow2NNN(starts[s][0])],
+                    Slot::Index(offset) => vec![OperCode::MemANNN(data + offset)],
+                    Slot::Indirect(u) => {
+                        let target = starts[r][u];
+                        vec![
+                            OperCode::Const6XNN(0, 0),
+                            OperCode::Const6XNN(usize::from(target >> 8), 0),
+                            OperCode::FlowBNNN(target),
+                        ]
+                    }
+                };
+                for oper in opers {
+                    rom.extend_from_slice(&oper.code().unwrap_or(0).to_be_bytes());
+                }
+            }
+        }
+        rom.extend((0..DATA_SIZE).map(|_| self.rng.gen::<u8>()));
+        rom
+    }
+
+    /// Generate the units of a routine, 0 being the main routine,
+    /// followed by its terminator
+    fn routine(&mut self, index: usize) -> Vec<Vec<Slot>> {
+        let size = self.config.routine_size;
+        let mut units = Vec::with_capacity(size + 1);
+        let mut skip = false;
+        for unit in 0..size {
+            // A skip is only followed by a single operation, and never by
+            // the terminator
+            let (slots, is_skip) = if skip {
+                (vec![self.single(index, unit)], false)
+            } else {
+                self.unit(index, unit, unit + 1 == size)
+            };
+            skip = is_skip;
+            units.push(slots);
+        }
+
+        let terminator = if index == 0 {
+            Slot::Jump(size)
+        } else {
+            Slot::Oper(OperCode::Flow00EE)
+        };
+        units.push(vec![terminator]);
+        units
+    }
+
+    /// Generate a unit, returning its slots and whether it is a skip
+    fn unit(&mut self, routine: usize, unit: usize, last: bool) -> (Vec<Slot>, bool) {
+        let x = self.reg();
+        match self.rng.gen_range(0, 10) {
+            0 if !last => (vec![Slot::Oper(self.skip())], true),
+            1 => {
+                let offset = self.rng.gen_range(0, DATA_SIZE - DATA_SPAN + 1);
+                let oper = match self.rng.gen_range(0, 6) {
+                    0 => OperCode::MemFX55(x),
+                    1 => OperCode::MemFX65(x),
+                    2 => OperCode::BcdFX33(x),
+                    3 => OperCode::SoundF002,
+                    4 => OperCode::MemFX1E(x),
+                    _ => OperCode::DisplayDXYN(x, self.reg(), self.rng.gen_range(0, 16)),
+                };
+                (vec![Slot::Index(offset), Slot::Oper(oper)], false)
+            }
+            2 if self.rng.gen_range(0, 4) == 0 => {
+                let target = self.target(unit);
+                (vec![Slot::Indirect(target)], false)
+            }
+            _ => (vec![self.single(routine, unit)], false),
+        }
+    }
+
+    /// Generate a unit of a single operation, never a skip
+    fn single(&mut self, routine: usize, unit: usize) -> Slot {
+        let (x, y) = (self.reg(), self.reg());
+        let nn = self.rng.gen::<u8>();
+        let subroutines = self.config.subroutines;
+        match self.rng.gen_range(0, 24) {
+            0 => Slot::Jump(self.target(unit)),
+            1 if routine < subroutines => Slot::Call(
+                self.rng
+                    .gen_range(routine as u32 + 1, subroutines as u32 + 1) as usize,
+            ),
+            2 => Slot::Oper(OperCode::Display00E0),
+            3 | 4 => Slot::Oper(OperCode::Const6XNN(x, nn)),
+            5 | 6 => Slot::Oper(OperCode::Const7XNN(x, nn)),
+            7 => Slot::Oper(OperCode::Assign8XY0(x, y)),
+            8 => Slot::Oper(OperCode::BitOp8XY1(x, y)),
+            9 => Slot::Oper(OperCode::BitOp8XY2(x, y)),
+            10 => Slot::Oper(OperCode::BitOp8XY3(x, y)),
+            11 => Slot::Oper(OperCode::Math8XY4(x, y)),
+            12 => Slot::Oper(OperCode::Math8XY5(x, y)),
+            13 => Slot::Oper(OperCode::BitOp8XY6(x, y)),
+            14 => Slot::Oper(OperCode::Math8XY7(x, y)),
+            15 => Slot::Oper(OperCode::BitOp8XYE(x, y)),
+            16 => Slot::Oper(OperCode::RandCXNN(x, nn)),
+            17 => Slot::Oper(OperCode::TimerFX07(x)),
+            18 => Slot::Oper(OperCode::TimerFX15(x)),
+            19 => Slot::Oper(OperCode::SoundFX18(x)),
+            20 => Slot::Oper(OperCode::SoundFX3A(x)),
+            21 => Slot::Oper(OperCode::MemFX29(x)),
+            22 if self.config.key_wait => Slot::Oper(OperCode::KeyOpFX0A(x)),
+            _ => Slot::Oper(OperCode::Const6XNN(x, nn)),
+        }
+    }
+
+    /// Generate a condition skipping the next operation
+    fn skip(&mut self) -> OperCode {
+        let (x, y) = (self.reg(), self.reg());
+        let nn = self.rng.gen::<u8>();
+        match self.rng.gen_range(0, 6) {
+            0 => OperCode::Cond3XNN(x, nn),
+            1 => OperCode::Cond4XNN(x, nn),
+            2 => OperCode::Cond5XY0(x, y),
+            3 => OperCode::Cond9XY0(x, y),
+            4 => OperCode::KeyOpEX9E(x),
+            _ => OperCode::KeyOpEXA1(x),
+        }
+    }
+
+    /// Random unit after a given one, the terminator included
+    fn target(&mut self, unit: usize) -> usize {
+        let size = self.config.routine_size as u32;
+        self.rng.gen_range(unit as u32 + 1, size + 1) as usize
+    }
+
+    /// Random register index
+    fn reg(&mut self) -> usize {
+        self.rng.gen_range(0, REG_SIZE as u32) as usize
+    }
+}
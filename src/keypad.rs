@@ -1,4 +1,6 @@
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
 pub enum Key {
+    K0,
     K1,
     K2,
     K3,
@@ -23,4 +25,5 @@ pub trait Keypad {
     fn key_from_u8(&self, k: &u8) -> Key;
     fn key_pressed(&self, key: &Key) -> bool;
     fn any_key_pressed(&self) -> Option<Key>;
+    fn key_put(&mut self, key: &Key, pressed: bool);
 }
@@ -1,6 +1,27 @@
+pub mod alu;
+pub mod analysis;
+pub mod audio;
+pub mod block;
+pub mod callgraph;
+pub mod coverage;
 pub mod cpu;
+pub mod dap;
+pub mod debugger;
+pub mod decompile;
 pub mod display;
+pub mod diverge;
 pub mod emu;
+pub mod fuzz;
+pub mod gdb;
+pub mod generator;
 pub mod keypad;
+pub mod lint;
 pub mod mem;
 pub mod oper;
+pub mod platform;
+pub mod profile;
+pub mod quirk;
+pub mod reference;
+pub mod timing;
+pub mod trace;
+pub mod wav;
@@ -0,0 +1,418 @@
+use crate::analysis::Cfg;
+use crate::display::{DSP_HEIGHT, DSP_WIDTH};
+use crate::emu::{PRG_INCR, REG_SIZE};
+use crate::oper::OperCode;
+use crate::quirk::{QuirkPreset, Quirks};
+use std::collections::{BTreeMap, BTreeSet};
+use std::io::{self, Write};
+
+/// Index of the flag register
+const VF: usize = 0xF;
+
+/// Columns of a sprite row
+const SPRITE_WIDTH: usize = 8;
+
+///
+/// Code pattern whose behaviour depends on the interpreter
+///
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum LintKind {
+    ///
+    /// `BitOp8XY6` or `BitOp8XYE` with X different from Y, which shifts
+    /// VY or VX depending on `Quirks::shift_vy`
+    ///
+    ShiftVy,
+
+    ///
+    /// `MemFX55` or `MemFX65` followed by an operation using I before it
+    /// is set again, which depends on `Quirks::load_store_inc`
+    ///
+    LoadStoreInc,
+
+    ///
+    /// `FlowBNNN` with a nonzero high nibble, which adds V0 or VX
+    /// depending on `Quirks::jump_vx`
+    ///
+    JumpVx,
+
+    ///
+    /// `BitOp8XY1`, `BitOp8XY2` or `BitOp8XY3` followed by a read of VF
+    /// before it is written again, which depends on `Quirks::vf_reset`
+    ///
+    VfRead,
+
+    ///
+    /// `DisplayDXYN` on constant coordinates drawing a sprite across the
+    /// edge of the screen, which some interpreters clip and others wrap
+    ///
+    SpriteEdge,
+}
+
+impl LintKind {
+    ///
+    /// Return the short name of the lint
+    ///
+    pub fn name(&self) -> &'static str {
+        match self {
+            LintKind::ShiftVy => "shift-vy",
+            LintKind::LoadStoreInc => "load-store-inc",
+            LintKind::JumpVx => "jump-vx",
+            LintKind::VfRead => "vf-read",
+            LintKind::SpriteEdge => "sprite-edge",
+        }
+    }
+
+    ///
+    /// Return the setting of the quirk the lint depends on, or None if
+    /// `Quirks` does not cover it
+    ///
+    pub fn quirk(&self, quirks: &Quirks) -> Option<bool> {
+        match self {
+            LintKind::ShiftVy => Some(quirks.shift_vy),
+            LintKind::LoadStoreInc => Some(quirks.load_store_inc),
+            LintKind::JumpVx => Some(quirks.jump_vx),
+            LintKind::VfRead => Some(quirks.vf_reset),
+            LintKind::SpriteEdge => None,
+        }
+    }
+}
+
+///
+/// Quirk-dependent operation found in a ROM
+///
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct Lint {
+    /// Address of the operation
+    pub address: u16,
+
+    /// Operation
+    pub oper: OperCode,
+
+    /// Pattern found
+    pub kind: LintKind,
+
+    /// Quirk setting the code most likely expects, or None if the
+    /// pattern does not tell
+    pub expects: Option<bool>,
+}
+
+///
+/// Quirk-dependent operations of a ROM, and the preset they suggest
+///
+/// Operations are reached through the control-flow graph of the ROM,
+/// and the ones depending on I or VF are followed along every path
+/// until the register is used or set again. The presets are scored by
+/// the lints whose expected quirk they match, minus the ones they do not:
+///
+/// - Shifting VY into a different VX expects `shift_vy`.
+/// - Loading or storing again after `MemFX55` or `MemFX65` without setting
+///   I walks on from where it left, and expects `load_store_inc`. Any
+///   other use of I reuses the same address, and expects it unset.
+/// - `FlowBNNN` with a nonzero high nibble in a ROM that never writes V0
+///   expects `jump_vx`.
+/// - A logic operation into VF itself expects its result to stay, so it
+///   expects `vf_reset` unset.
+///
+/// The other lints do not vote: reading VF after a logic operation tells
+/// nothing of which value the code wants, `Quirks` does not cover
+/// sprites across the edges, and I used both ways along different paths
+/// contradicts itself.
+///
+/// # Example
+///
+/// ```
+/// use rc201_8::lint::{LintKind, LintReport};
+/// use rc201_8::quirk::QuirkPreset;
+///
+/// // Shift V2 into V1, load V0 to V1 from two rows of a table at I,
+/// // then loop forever
+/// let report = LintReport::new(&[
+///     0x81, 0x26, 0xA3, 0x00, 0xF1, 0x65, 0xF1, 0x65, 0x12, 0x08,
+/// ]);
+/// let kinds: Vec<LintKind> = report.lints().iter().map(|l| l.kind).collect();
+///
+/// assert_eq!(kinds, vec![LintKind::ShiftVy, LintKind::LoadStoreInc]);
+/// assert_eq!(report.lints()[1].expects, Some(true));
+/// assert_eq!(report.recommend(), QuirkPreset::Chip8);
+/// ```
+///
+#[derive(Debug, Clone)]
+pub struct LintReport {
+    /// Lints, in address order
+    lints: Vec<Lint>,
+}
+
+impl LintReport {
+    ///
+    /// Returns the report of a ROM loaded on `PRG_START`
+    ///
+    pub fn new(rom: &[u8]) -> LintReport {
+        let cfg = Cfg::new(rom);
+        let graph = graph(&cfg);
+        let writes_v0 = graph.values().any(|(oper, _)| access(oper).writes & 1 != 0);
+
+        let mut lints = Vec::new();
+        let mut lint = |address, oper, kind, expects| {
+            lints.push(Lint {
+                address,
+                oper,
+                kind,
+                expects,
+            })
+        };
+        for (address, (oper, _)) in &graph {
+            match *oper {
+                OperCode::BitOp8XY6(x, y) | OperCode::BitOp8XYE(x, y) if x != y => {
+                    lint(*address, *oper, LintKind::ShiftVy, Some(true));
+                }
+                OperCode::MemFX55(_) | OperCode::MemFX65(_) => {
+                    // Whether the next use of I loads or stores again,
+                    // walking on from the incremented I, or uses it as
+                    // the same address
+                    let next_use = |walks: bool| {
+                        move |o: &OperCode| {
+                            let a = access(o);
+                            match o {
+                                OperCode::MemFX55(_) | OperCode::MemFX65(_) => Some(walks),
+                                _ if a.reads_i => Some(!walks),
+                                _ if a.writes_i => Some(false),
+                                _ => None,
+                            }
+                        }
+                    };
+                    let walks = reaches(&graph, *address, next_use(true));
+                    let reuses = reaches(&graph, *address, next_use(false));
+                    if walks || reuses {
+                        let expects = if walks == reuses { None } else { Some(walks) };
+                        lint(*address, *oper, LintKind::LoadStoreInc, expects);
+                    }
+                }
+                OperCode::FlowBNNN(n) if n >> 8 != 0 => {
+                    let expects = if writes_v0 { None } else { Some(true) };
+                    lint(*address, *oper, LintKind::JumpVx, expects);
+                }
+                OperCode::BitOp8XY1(x, y)
+                | OperCode::BitOp8XY2(x, y)
+                | OperCode::BitOp8XY3(x, y) => {
+                    let uses = |o: &OperCode| {
+                        let a = access(o);
+                        if (a.reads | a.writes) & 1 << VF != 0 {
+                            Some(a.reads & 1 << VF != 0)
+                        } else {
+                            None
+                        }
+                    };
+                    let clears = x == y && matches!(oper, OperCode::BitOp8XY3(..));
+                    if x == VF && !clears {
+                        lint(*address, *oper, LintKind::VfRead, Some(false));
+                    } else if x == VF || reaches(&graph, *address, uses) {
+                        lint(*address, *oper, LintKind::VfRead, None);
+                    }
+                }
+                _ => {}
+            }
+        }
+
+        // Coordinates set on the same block as the sprite
+        for block in cfg.blocks() {
+            let mut consts: [Option<u8>; REG_SIZE] = [None; REG_SIZE];
+            for (address, oper) in &block.opers {
+                if let OperCode::DisplayDXYN(x, y, n) = *oper {
+                    if let (Some(vx), Some(vy)) = (consts[x], consts[y]) {
+                        let column = usize::from(vx) % DSP_WIDTH;
+                        let row = usize::from(vy) % DSP_HEIGHT;
+                        if column + SPRITE_WIDTH > DSP_WIDTH || row + usize::from(n) > DSP_HEIGHT {
+                            lint(*address, *oper, LintKind::SpriteEdge, None);
+                        }
+                    }
+                }
+                match *oper {
+                    OperCode::Const6XNN(x, n) => consts[x] = Some(n),
+                    OperCode::Const7XNN(x, n) => consts[x] = consts[x].map(|v| v.wrapping_add(n)),
+                    OperCode::Assign8XY0(x, y) => consts[x] = consts[y],
+                    _ => {
+                        let writes = access(oper).writes;
+                        for (r, c) in consts.iter_mut().enumerate() {
+                            if writes & 1 << r != 0 {
+                                *c = None;
+                            }
+                        }
+                    }
+                }
+            }
+        }
+
+        lints.sort_by_key(|l| l.address);
+        LintReport { lints }
+    }
+
+    ///
+    /// Return the lints, in address order
+    ///
+    pub fn lints(&self) -> &[Lint] {
+        &self.lints
+    }
+
+    ///
+    /// Return the score of every preset: one point for each lint whose
+    /// expected quirk it matches, minus one for each it does not
+    ///
+    pub fn scores(&self) -> Vec<(QuirkPreset, i32)> {
+        QuirkPreset::all()
+            .into_iter()
+            .map(|preset| {
+                let quirks = preset.quirks();
+                let score = self
+                    .lints
+                    .iter()
+                    .filter_map(|l| match (l.expects, l.kind.quirk(&quirks)) {
+                        (Some(e), Some(q)) if e == q => Some(1),
+                        (Some(_), Some(_)) => Some(-1),
+                        _ => None,
+                    })
+                    .sum();
+                (preset, score)
+            })
+            .collect()
+    }
+
+    ///
+    /// Return the preset with the highest score, the earliest of
+    /// `QuirkPreset::all` on a tie
+    ///
+    pub fn recommend(&self) -> QuirkPreset {
+        let mut best = (QuirkPreset::Chip8, i32::MIN);
+        for (preset, score) in self.scores() {
+            if score > best.1 {
+                best = (preset, score);
+            }
+        }
+        best.0
+    }
+
+    ///
+    /// Write one `ADDR  NAME  OPERATION` line per lint, followed by the
+    /// score of every preset and the recommended one
+    ///
+    pub fn write_report<W: Write>(&self, sink: &mut W) -> io::Result<()> {
+        for lint in &self.lints {
+            writeln!(
+                sink,
+                "{:03X}  {:<14}  {}",
+                lint.address,
+                lint.kind.name(),
+                lint.oper
+            )?;
+        }
+        for (preset, score) in self.scores() {
+            writeln!(sink, "score {} {}", preset.name(), score)?;
+        }
+        writeln!(sink, "recommend {}", self.recommend().name())
+    }
+}
+
+/// Registers an operation reads and writes
+#[derive(Debug, Default)]
+struct Access {
+    /// V registers read, as a bit mask
+    reads: u16,
+
+    /// V registers written, as a bit mask
+    writes: u16,
+
+    /// Whether I is read
+    reads_i: bool,
+
+    /// Whether I is written
+    writes_i: bool,
+}
+
+/// Return the registers an operation reads and writes, under any quirk
+fn access(oper: &OperCode) -> Access {
+    let v = |r: usize| 1_u16 << r;
+    let upto = |x: usize| (0..=x).fold(0, |m, r| m | v(r));
+    let (reads, writes) = match *oper {
+        OperCode::Cond3XNN(x, _)
+        | OperCode::Cond4XNN(x, _)
+        | OperCode::KeyOpEX9E(x)
+        | OperCode::KeyOpEXA1(x)
+        | OperCode::TimerFX15(x)
+        | OperCode::SoundFX18(x)
+        | OperCode::SoundFX3A(x)
+        | OperCode::MemFX1E(x)
+        | OperCode::MemFX29(x)
+        | OperCode::BcdFX33(x) => (v(x), 0),
+        OperCode::Cond5XY0(x, y) | OperCode::Cond9XY0(x, y) => (v(x) | v(y), 0),
+        OperCode::Const6XNN(x, _)
+        | OperCode::RandCXNN(x, _)
+        | OperCode::TimerFX07(x)
+        | OperCode::KeyOpFX0A(x) => (0, v(x)),
+        OperCode::Const7XNN(x, _) => (v(x), v(x)),
+        OperCode::Assign8XY0(x, y) => (v(y), v(x)),
+        OperCode::BitOp8XY1(x, y)
+        | OperCode::BitOp8XY2(x, y)
+        | OperCode::BitOp8XY3(x, y)
+        | OperCode::Math8XY4(x, y)
+        | OperCode::Math8XY5(x, y)
+        | OperCode::BitOp8XY6(x, y)
+        | OperCode::Math8XY7(x, y)
+        | OperCode::BitOp8XYE(x, y) => (v(x) | v(y), v(x) | v(VF)),
+        OperCode::FlowBNNN(n) => (v(0) | v(usize::from(n >> 8)), 0),
+        OperCode::DisplayDXYN(x, y, _) => (v(x) | v(y), v(VF)),
+        OperCode::MemFX55(x) => (upto(x), 0),
+        OperCode::MemFX65(x) => (0, upto(x)),
+        _ => (0, 0),
+    };
+    let (reads_i, writes_i) = match oper {
+        OperCode::MemANNN(_) | OperCode::MemFX29(_) => (false, true),
+        OperCode::MemFX1E(_) | OperCode::MemFX55(_) | OperCode::MemFX65(_) => (true, true),
+        OperCode::DisplayDXYN(..) | OperCode::BcdFX33(_) | OperCode::SoundF002 => (true, false),
+        _ => (false, false),
+    };
+    Access {
+        reads,
+        writes,
+        reads_i,
+        writes_i,
+    }
+}
+
+/// Return every reached operation and the addresses that may follow it
+fn graph(cfg: &Cfg) -> BTreeMap<u16, (OperCode, Vec<u16>)> {
+    let mut graph = BTreeMap::new();
+    for block in cfg.blocks() {
+        let last = block.opers.len() - 1;
+        for (n, (address, oper)) in block.opers.iter().enumerate() {
+            let next = if n == last {
+                block.successors.iter().map(|e| e.target).collect()
+            } else {
+                vec![address.wrapping_add(PRG_INCR)]
+            };
+            graph.insert(*address, (*oper, next));
+        }
+    }
+    graph
+}
+
+/// Return whether a path after an address reaches an operation `verdict`
+/// accepts, before one it rejects; operations it ignores are followed
+fn reaches<F>(graph: &BTreeMap<u16, (OperCode, Vec<u16>)>, from: u16, verdict: F) -> bool
+where
+    F: Fn(&OperCode) -> Option<bool>,
+{
+    let mut seen = BTreeSet::new();
+    let mut pending: Vec<u16> = graph.get(&from).map(|n| n.1.clone()).unwrap_or_default();
+    while let Some(address) = pending.pop() {
+        if !seen.insert(address) {
+            continue;
+        }
+        if let Some((oper, next)) = graph.get(&address) {
+            match verdict(oper) {
+                Some(true) => return true,
+                Some(false) => {}
+                None => pending.extend(next),
+            }
+        }
+    }
+    false
+}
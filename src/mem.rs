@@ -1,5 +1,7 @@
+use std::cell::RefCell;
 use std::fmt;
 use std::ops::RangeBounds;
+use std::rc::Rc;
 use std::slice::SliceIndex;
 
 ///
@@ -151,7 +153,8 @@ pub trait Mem {
     /// Replace the given range with a given slice.
     ///
     /// If the given slice contains less members than the given range,
-    /// only the given slice members will be inserted into the memory
+    /// only the given slice members will be inserted into the memory.
+    /// Members beyond the range are ignored
     ///
     /// # Example
     ///
@@ -174,6 +177,161 @@ pub trait Mem {
     ) -> Result<(), MemError>;
 }
 
+///
+/// Direction of a memory access
+///
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum MemAccessKind {
+    Read,
+    Write,
+}
+
+///
+/// Origin of a memory access
+///
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum MemAccessSource {
+    ///
+    /// Instruction fetch of the cpu
+    ///
+    Fetch,
+
+    ///
+    /// Processing of an operation code
+    ///
+    Oper,
+
+    ///
+    /// Direct call of the `Mem` methods, such as a frontend or debugger
+    ///
+    External,
+}
+
+///
+/// Single byte memory access
+///
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct MemAccess {
+    /// Accessed address
+    pub address: usize,
+
+    /// Value read, or written
+    pub value: u8,
+
+    /// Direction of the access
+    pub kind: MemAccessKind,
+
+    /// Origin of the access
+    pub source: MemAccessSource,
+}
+
+///
+/// Receiver of memory access notifications
+///
+/// Observers are owned by the emulator. To keep access to the collected
+/// data, register an `Rc<RefCell<T>>` clone.
+///
+/// # Example
+///
+/// ```
+/// use rc201_8::emu::Emu;
+/// use rc201_8::cpu::Cpu;
+/// use rc201_8::mem::{Mem, MemAccess, MemAccessKind, MemAccessSource};
+/// use rc201_8::display::{Display, DisplayDummy};
+/// use std::cell::RefCell;
+/// use std::rc::Rc;
+///
+/// let mut emu = Emu::new(DisplayDummy::new());
+/// let accesses: Rc<RefCell<Vec<MemAccess>>> = Rc::new(RefCell::new(Vec::new()));
+/// emu.mem_observer_add(Box::new(accesses.clone()));
+///
+/// // Store V0 on I
+/// emu.mem_write(0..2, &[0xF0, 0x55]).unwrap();
+/// emu.ind_put(0x300);
+/// emu.step().unwrap();
+///
+/// let accesses = accesses.borrow();
+/// assert_eq!(accesses.len(), 5);
+/// assert_eq!(accesses[0].source, MemAccessSource::External);
+/// assert_eq!(accesses[2].source, MemAccessSource::Fetch);
+/// assert_eq!(
+///     accesses[4],
+///     MemAccess {
+///         address: 0x300,
+///         value: 0,
+///         kind: MemAccessKind::Write,
+///         source: MemAccessSource::Oper,
+///     }
+/// );
+/// ```
+///
+pub trait MemObserver {
+    ///
+    /// Receive a memory access
+    ///
+    fn mem_access(&mut self, access: &MemAccess);
+
+    ///
+    /// Receive a write that changed an already executed operation
+    ///
+    /// `address` is the start of the modified operation and `access` the
+    /// write that modified it, which is also passed to `mem_access`.
+    ///
+    /// # Example
+    ///
+    /// ```
+    /// use rc201_8::emu::Emu;
+    /// use rc201_8::cpu::Cpu;
+    /// use rc201_8::mem::{MemAccess, MemAccessSource, MemObserver};
+    /// use rc201_8::display::{Display, DisplayDummy};
+    /// use std::cell::RefCell;
+    /// use std::rc::Rc;
+    ///
+    /// #[derive(Default)]
+    /// struct Patches(Vec<(usize, MemAccessSource)>);
+    ///
+    /// impl MemObserver for Patches {
+    ///     fn mem_access(&mut self, _: &MemAccess) {}
+    ///
+    ///     fn code_modified(&mut self, address: usize, access: &MemAccess) {
+    ///         self.0.push((address, access.source));
+    ///     }
+    /// }
+    ///
+    /// let mut emu = Emu::new(DisplayDummy::new());
+    /// let patches = Rc::new(RefCell::new(Patches::default()));
+    /// emu.mem_observer_add(Box::new(patches.clone()));
+    ///
+    /// // Assign V1, then overwrite that operation with an assignment of V2
+    /// emu.load_rom(&[0x61, 0x01, 0x60, 0x62, 0xA2, 0x00, 0xF0, 0x55, 0x12, 0x00])
+    ///     .unwrap();
+    /// for _ in 0..6 {
+    ///     emu.step().unwrap();
+    /// }
+    ///
+    /// assert_eq!(emu.reg_get(&2).unwrap(), 1);
+    /// assert_eq!(patches.borrow().0, vec![(0x200, MemAccessSource::Oper)]);
+    /// ```
+    ///
+    fn code_modified(&mut self, _address: usize, _access: &MemAccess) {}
+}
+
+impl MemObserver for Vec<MemAccess> {
+    fn mem_access(&mut self, access: &MemAccess) {
+        self.push(*access);
+    }
+}
+
+impl<T: MemObserver> MemObserver for Rc<RefCell<T>> {
+    fn mem_access(&mut self, access: &MemAccess) {
+        self.borrow_mut().mem_access(access);
+    }
+
+    fn code_modified(&mut self, address: usize, access: &MemAccess) {
+        self.borrow_mut().code_modified(address, access);
+    }
+}
+
 ///
 /// Possible variants for memory access error
 ///
@@ -222,12 +380,19 @@ impl MemError {
         let (variant, message) = match param {
             MemErrorVariant::AccessViolation(a) => (param, format!("Illegal address '{}'!", a)),
             MemErrorVariant::AccessRangeViolation(a, b) => (
-                MemErrorVariant::AccessRangeViolation(a, b - 1),
-                format!("Illegal range '{}..{}'!", a, b - 1),
+                MemErrorVariant::AccessRangeViolation(a, b.saturating_sub(1)),
+                format!("Illegal range '{}..{}'!", a, b.saturating_sub(1)),
             ),
         };
         MemError { variant, message }
     }
+
+    ///
+    /// Return the variant of the error
+    ///
+    pub fn variant(&self) -> &MemErrorVariant {
+        &self.variant
+    }
 }
 
 impl fmt::Display for MemError {
@@ -1,3 +1,6 @@
+use std::fmt;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
 pub enum OperCode {
     /// 00E0-Display
     ///
@@ -159,6 +162,17 @@ pub enum OperCode {
     /// Sets the delay timer to VX.
     SoundFX18(usize),
 
+    /// F002-Sound (XO-CHIP)
+    ///
+    /// Loads the 16 bytes at I as the 1-bit audio pattern played
+    /// while the sound timer is not zero.
+    SoundF002,
+
+    /// FX3A-Sound (XO-CHIP)
+    ///
+    /// Sets the audio pitch register to VX.
+    SoundFX3A(usize),
+
     /// FX1E-MEM
     ///
     /// Adds VX to I.
@@ -201,73 +215,251 @@ pub enum OperCode {
     Unknown,
 }
 
+impl OperCode {
+    /// Returns the name of the variant, without operands
+    ///
+    /// # Example
+    ///
+    /// ```
+    /// use rc201_8::oper::Oper;
+    ///
+    /// assert_eq!(Oper::from_code(&0x6A02, &16).name(), "Const6XNN");
+    /// ```
+    pub fn name(&self) -> &'static str {
+        match self {
+            OperCode::Display00E0 => "Display00E0",
+            OperCode::Flow00EE => "Flow00EE",
+            OperCode::Flow1NNN(_) => "Flow1NNN",
+            OperCode::Flow2NNN(_) => "Flow2NNN",
+            OperCode::Cond3XNN(_, _) => "Cond3XNN",
+            OperCode::Cond4XNN(_, _) => "Cond4XNN",
+            OperCode::Cond5XY0(_, _) => "Cond5XY0",
+            OperCode::Const6XNN(_, _) => "Const6XNN",
+            OperCode::Const7XNN(_, _) => "Const7XNN",
+            OperCode::Assign8XY0(_, _) => "Assign8XY0",
+            OperCode::BitOp8XY1(_, _) => "BitOp8XY1",
+            OperCode::BitOp8XY2(_, _) => "BitOp8XY2",
+            OperCode::BitOp8XY3(_, _) => "BitOp8XY3",
+            OperCode::Math8XY4(_, _) => "Math8XY4",
+            OperCode::Math8XY5(_, _) => "Math8XY5",
+            OperCode::BitOp8XY6(_, _) => "BitOp8XY6",
+            OperCode::Math8XY7(_, _) => "Math8XY7",
+            OperCode::BitOp8XYE(_, _) => "BitOp8XYE",
+            OperCode::Cond9XY0(_, _) => "Cond9XY0",
+            OperCode::MemANNN(_) => "MemANNN",
+            OperCode::FlowBNNN(_) => "FlowBNNN",
+            OperCode::RandCXNN(_, _) => "RandCXNN",
+            OperCode::DisplayDXYN(_, _, _) => "DisplayDXYN",
+            OperCode::KeyOpEX9E(_) => "KeyOpEX9E",
+            OperCode::KeyOpEXA1(_) => "KeyOpEXA1",
+            OperCode::TimerFX07(_) => "TimerFX07",
+            OperCode::KeyOpFX0A(_) => "KeyOpFX0A",
+            OperCode::TimerFX15(_) => "TimerFX15",
+            OperCode::SoundFX18(_) => "SoundFX18",
+            OperCode::SoundF002 => "SoundF002",
+            OperCode::SoundFX3A(_) => "SoundFX3A",
+            OperCode::MemFX1E(_) => "MemFX1E",
+            OperCode::MemFX29(_) => "MemFX29",
+            OperCode::BcdFX33(_) => "BcdFX33",
+            OperCode::MemFX55(_) => "MemFX55",
+            OperCode::MemFX65(_) => "MemFX65",
+            OperCode::Unknown => "Unknown",
+        }
+    }
+
+    /// Returns the operation code encoding the operation, or None for
+    /// unknown operations
+    ///
+    /// Register indexes and values wider than their fields are truncated.
+    ///
+    /// ```
+    /// use rc201_8::oper::{Oper, OperCode};
+    ///
+    /// assert_eq!(OperCode::Math8XY4(3, 0xA).code(), Some(0x83A4));
+    /// assert_eq!(Oper::from_code(&0xF265, &16).code(), Some(0xF265));
+    /// assert_eq!(OperCode::Unknown.code(), None);
+    /// ```
+    pub fn code(&self) -> Option<u16> {
+        let x = |x: &usize| (*x as u16 & 0xF) << 8;
+        let xy = |x: &usize, y: &usize| (*x as u16 & 0xF) << 8 | (*y as u16 & 0xF) << 4;
+        let code = match self {
+            OperCode::Display00E0 => 0x00E0,
+            OperCode::Flow00EE => 0x00EE,
+            OperCode::Flow1NNN(n) => 0x1000 | n & 0xFFF,
+            OperCode::Flow2NNN(n) => 0x2000 | n & 0xFFF,
+            OperCode::Cond3XNN(vx, n) => 0x3000 | x(vx) | u16::from(*n),
+            OperCode::Cond4XNN(vx, n) => 0x4000 | x(vx) | u16::from(*n),
+            OperCode::Cond5XY0(vx, vy) => 0x5000 | xy(vx, vy),
+            OperCode::Const6XNN(vx, n) => 0x6000 | x(vx) | u16::from(*n),
+            OperCode::Const7XNN(vx, n) => 0x7000 | x(vx) | u16::from(*n),
+            OperCode::Assign8XY0(vx, vy) => 0x8000 | xy(vx, vy),
+            OperCode::BitOp8XY1(vx, vy) => 0x8001 | xy(vx, vy),
+            OperCode::BitOp8XY2(vx, vy) => 0x8002 | xy(vx, vy),
+            OperCode::BitOp8XY3(vx, vy) => 0x8003 | xy(vx, vy),
+            OperCode::Math8XY4(vx, vy) => 0x8004 | xy(vx, vy),
+            OperCode::Math8XY5(vx, vy) => 0x8005 | xy(vx, vy),
+            OperCode::BitOp8XY6(vx, vy) => 0x8006 | xy(vx, vy),
+            OperCode::Math8XY7(vx, vy) => 0x8007 | xy(vx, vy),
+            OperCode::BitOp8XYE(vx, vy) => 0x800E | xy(vx, vy),
+            OperCode::Cond9XY0(vx, vy) => 0x9000 | xy(vx, vy),
+            OperCode::MemANNN(n) => 0xA000 | n & 0xFFF,
+            OperCode::FlowBNNN(n) => 0xB000 | n & 0xFFF,
+            OperCode::RandCXNN(vx, n) => 0xC000 | x(vx) | u16::from(*n),
+            OperCode::DisplayDXYN(vx, vy, n) => 0xD000 | xy(vx, vy) | u16::from(*n & 0xF),
+            OperCode::KeyOpEX9E(vx) => 0xE09E | x(vx),
+            OperCode::KeyOpEXA1(vx) => 0xE0A1 | x(vx),
+            OperCode::TimerFX07(vx) => 0xF007 | x(vx),
+            OperCode::KeyOpFX0A(vx) => 0xF00A | x(vx),
+            OperCode::TimerFX15(vx) => 0xF015 | x(vx),
+            OperCode::SoundFX18(vx) => 0xF018 | x(vx),
+            OperCode::SoundF002 => 0xF002,
+            OperCode::SoundFX3A(vx) => 0xF03A | x(vx),
+            OperCode::MemFX1E(vx) => 0xF01E | x(vx),
+            OperCode::MemFX29(vx) => 0xF029 | x(vx),
+            OperCode::BcdFX33(vx) => 0xF033 | x(vx),
+            OperCode::MemFX55(vx) => 0xF055 | x(vx),
+            OperCode::MemFX65(vx) => 0xF065 | x(vx),
+            OperCode::Unknown => return None,
+        };
+        Some(code)
+    }
+}
+
+impl fmt::Display for OperCode {
+    /// Formats the operation as an assembler mnemonic
+    ///
+    /// # Example
+    ///
+    /// ```
+    /// use rc201_8::oper::{Oper, OperCode};
+    ///
+    /// assert_eq!(Oper::from_code(&0x6A02, &16).to_string(), "LD VA, 0x02");
+    /// assert_eq!(Oper::from_code(&0xD125, &16).to_string(), "DRW V1, V2, 0x5");
+    /// assert_eq!(OperCode::Unknown.to_string(), "???");
+    /// ```
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            OperCode::Display00E0 => write!(f, "CLS"),
+            OperCode::Flow00EE => write!(f, "RET"),
+            OperCode::Flow1NNN(n) => write!(f, "JP 0x{:03X}", n),
+            OperCode::Flow2NNN(n) => write!(f, "CALL 0x{:03X}", n),
+            OperCode::Cond3XNN(x, n) => write!(f, "SE V{:X}, 0x{:02X}", x, n),
+            OperCode::Cond4XNN(x, n) => write!(f, "SNE V{:X}, 0x{:02X}", x, n),
+            OperCode::Cond5XY0(x, y) => write!(f, "SE V{:X}, V{:X}", x, y),
+            OperCode::Const6XNN(x, n) => write!(f, "LD V{:X}, 0x{:02X}", x, n),
+            OperCode::Const7XNN(x, n) => write!(f, "ADD V{:X}, 0x{:02X}", x, n),
+            OperCode::Assign8XY0(x, y) => write!(f, "LD V{:X}, V{:X}", x, y),
+            OperCode::BitOp8XY1(x, y) => write!(f, "OR V{:X}, V{:X}", x, y),
+            OperCode::BitOp8XY2(x, y) => write!(f, "AND V{:X}, V{:X}", x, y),
+            OperCode::BitOp8XY3(x, y) => write!(f, "XOR V{:X}, V{:X}", x, y),
+            OperCode::Math8XY4(x, y) => write!(f, "ADD V{:X}, V{:X}", x, y),
+            OperCode::Math8XY5(x, y) => write!(f, "SUB V{:X}, V{:X}", x, y),
+            OperCode::BitOp8XY6(x, y) => write!(f, "SHR V{:X}, V{:X}", x, y),
+            OperCode::Math8XY7(x, y) => write!(f, "SUBN V{:X}, V{:X}", x, y),
+            OperCode::BitOp8XYE(x, y) => write!(f, "SHL V{:X}, V{:X}", x, y),
+            OperCode::Cond9XY0(x, y) => write!(f, "SNE V{:X}, V{:X}", x, y),
+            OperCode::MemANNN(n) => write!(f, "LD I, 0x{:03X}", n),
+            OperCode::FlowBNNN(n) => write!(f, "JP V0, 0x{:03X}", n),
+            OperCode::RandCXNN(x, n) => write!(f, "RND V{:X}, 0x{:02X}", x, n),
+            OperCode::DisplayDXYN(x, y, n) => write!(f, "DRW V{:X}, V{:X}, 0x{:X}", x, y, n),
+            OperCode::KeyOpEX9E(x) => write!(f, "SKP V{:X}", x),
+            OperCode::KeyOpEXA1(x) => write!(f, "SKNP V{:X}", x),
+            OperCode::TimerFX07(x) => write!(f, "LD V{:X}, DT", x),
+            OperCode::KeyOpFX0A(x) => write!(f, "LD V{:X}, K", x),
+            OperCode::TimerFX15(x) => write!(f, "LD DT, V{:X}", x),
+            OperCode::SoundFX18(x) => write!(f, "LD ST, V{:X}", x),
+            OperCode::SoundF002 => write!(f, "AUDIO"),
+            OperCode::SoundFX3A(x) => write!(f, "LD PITCH, V{:X}", x),
+            OperCode::MemFX1E(x) => write!(f, "ADD I, V{:X}", x),
+            OperCode::MemFX29(x) => write!(f, "LD F, V{:X}", x),
+            OperCode::BcdFX33(x) => write!(f, "LD B, V{:X}", x),
+            OperCode::MemFX55(x) => write!(f, "LD [I], V{:X}", x),
+            OperCode::MemFX65(x) => write!(f, "LD V{:X}, [I]", x),
+            OperCode::Unknown => write!(f, "???"),
+        }
+    }
+}
+
 pub struct Oper {}
 
 impl Oper {
     /// Returns enum OperCode from a given code and
     /// the registers capacity (typically 16)
+    ///
+    /// Codes addressing registers beyond the capacity are unknown.
     pub fn from_code(code: &u16, rsize: &usize) -> OperCode {
-        let a = (code & 0xF000) >> 12;
-        let b = (code & 0x0F00) >> 08;
-        let c = (code & 0x00F0) >> 04;
-        let d = code & 0x000F;
-
-        match (a, b, c, d) {
-            (0, 0, 0xE, 0) => OperCode::Display00E0,
-            (0, 0, 0xE, 0xE) => OperCode::Flow00EE,
-            (0x1, _, _, _) => OperCode::Flow1NNN(get_nnn(code)),
-            (0x2, _, _, _) => OperCode::Flow2NNN(get_nnn(code)),
-            (0x3, _, _, _) => OperCode::Cond3XNN(get_x(code, rsize), get_nn(code)),
-            (0x4, _, _, _) => OperCode::Cond4XNN(get_x(code, rsize), get_nn(code)),
-            (0x5, _, _, _) => OperCode::Cond5XY0(get_x(code, rsize), get_y(code, rsize)),
-            (0x6, _, _, _) => OperCode::Const6XNN(get_x(code, rsize), get_nn(code)),
-            (0x7, _, _, _) => OperCode::Const7XNN(get_x(code, rsize), get_nn(code)),
-            (0x8, _, _, 0x0) => OperCode::Assign8XY0(get_x(code, rsize), get_y(code, rsize)),
-            (0x8, _, _, 0x1) => OperCode::BitOp8XY1(get_x(code, rsize), get_y(code, rsize)),
-            (0x8, _, _, 0x2) => OperCode::BitOp8XY2(get_x(code, rsize), get_y(code, rsize)),
-            (0x8, _, _, 0x3) => OperCode::BitOp8XY3(get_x(code, rsize), get_y(code, rsize)),
-            (0x8, _, _, 0x4) => OperCode::Math8XY4(get_x(code, rsize), get_y(code, rsize)),
-            (0x8, _, _, 0x5) => OperCode::Math8XY5(get_x(code, rsize), get_y(code, rsize)),
-            (0x8, _, _, 0x6) => OperCode::BitOp8XY6(get_x(code, rsize), get_y(code, rsize)),
-            (0x8, _, _, 0x7) => OperCode::Math8XY7(get_x(code, rsize), get_y(code, rsize)),
-            (0x8, _, _, 0xE) => OperCode::BitOp8XYE(get_x(code, rsize), get_y(code, rsize)),
-            (0x9, _, _, _) => OperCode::Cond9XY0(get_x(code, rsize), get_y(code, rsize)),
-            (0xA, _, _, _) => OperCode::MemANNN(get_nnn(code)),
-            (0xB, _, _, _) => OperCode::FlowBNNN(get_nnn(code)),
-            (0xC, _, _, _) => OperCode::RandCXNN(get_x(code, rsize), get_nn(code)),
-            (0xD, _, _, _) => {
-                OperCode::DisplayDXYN(get_x(code, rsize), get_y(code, rsize), get_n(code))
-            }
-            (0xE, _, 0x9, 0xE) => OperCode::KeyOpEX9E(get_x(code, rsize)),
-            (0xE, _, 0xA, 0x1) => OperCode::KeyOpEXA1(get_x(code, rsize)),
-            (0xF, _, 0x0, 0x7) => OperCode::TimerFX07(get_x(code, rsize)),
-            (0xF, _, 0x0, 0xA) => OperCode::KeyOpFX0A(get_x(code, rsize)),
-            (0xF, _, 0x1, 0x5) => OperCode::TimerFX15(get_x(code, rsize)),
-            (0xF, _, 0x1, 0x8) => OperCode::SoundFX18(get_x(code, rsize)),
-            (0xF, _, 0x1, 0xE) => OperCode::MemFX1E(get_x(code, rsize)),
-            (0xF, _, 0x2, 0x9) => OperCode::MemFX29(get_x(code, rsize)),
-            (0xF, _, 0x3, 0x3) => OperCode::BcdFX33(get_x(code, rsize)),
-            (0xF, _, 0x5, 0x5) => OperCode::MemFX55(get_x(code, rsize)),
-            (0xF, _, 0x6, 0x5) => OperCode::MemFX65(get_x(code, rsize)),
-            (_, _, _, _) => OperCode::Unknown,
-        }
+        decode(code, rsize).unwrap_or(OperCode::Unknown)
     }
 }
 
-fn get_x(code: &u16, rsize: &usize) -> usize {
+/// Decode a given code, returning None if it addresses a register beyond
+/// the capacity
+fn decode(code: &u16, rsize: &usize) -> Option<OperCode> {
+    let a = (code & 0xF000) >> 12;
+    let b = (code & 0x0F00) >> 8;
+    let c = (code & 0x00F0) >> 4;
+    let d = code & 0x000F;
+
+    let oper = match (a, b, c, d) {
+        (0, 0, 0xE, 0) => OperCode::Display00E0,
+        (0, 0, 0xE, 0xE) => OperCode::Flow00EE,
+        (0x1, _, _, _) => OperCode::Flow1NNN(get_nnn(code)),
+        (0x2, _, _, _) => OperCode::Flow2NNN(get_nnn(code)),
+        (0x3, _, _, _) => OperCode::Cond3XNN(get_x(code, rsize)?, get_nn(code)),
+        (0x4, _, _, _) => OperCode::Cond4XNN(get_x(code, rsize)?, get_nn(code)),
+        (0x5, _, _, _) => OperCode::Cond5XY0(get_x(code, rsize)?, get_y(code, rsize)?),
+        (0x6, _, _, _) => OperCode::Const6XNN(get_x(code, rsize)?, get_nn(code)),
+        (0x7, _, _, _) => OperCode::Const7XNN(get_x(code, rsize)?, get_nn(code)),
+        (0x8, _, _, 0x0) => OperCode::Assign8XY0(get_x(code, rsize)?, get_y(code, rsize)?),
+        (0x8, _, _, 0x1) => OperCode::BitOp8XY1(get_x(code, rsize)?, get_y(code, rsize)?),
+        (0x8, _, _, 0x2) => OperCode::BitOp8XY2(get_x(code, rsize)?, get_y(code, rsize)?),
+        (0x8, _, _, 0x3) => OperCode::BitOp8XY3(get_x(code, rsize)?, get_y(code, rsize)?),
+        (0x8, _, _, 0x4) => OperCode::Math8XY4(get_x(code, rsize)?, get_y(code, rsize)?),
+        (0x8, _, _, 0x5) => OperCode::Math8XY5(get_x(code, rsize)?, get_y(code, rsize)?),
+        (0x8, _, _, 0x6) => OperCode::BitOp8XY6(get_x(code, rsize)?, get_y(code, rsize)?),
+        (0x8, _, _, 0x7) => OperCode::Math8XY7(get_x(code, rsize)?, get_y(code, rsize)?),
+        (0x8, _, _, 0xE) => OperCode::BitOp8XYE(get_x(code, rsize)?, get_y(code, rsize)?),
+        (0x9, _, _, _) => OperCode::Cond9XY0(get_x(code, rsize)?, get_y(code, rsize)?),
+        (0xA, _, _, _) => OperCode::MemANNN(get_nnn(code)),
+        (0xB, _, _, _) => OperCode::FlowBNNN(get_nnn(code)),
+        (0xC, _, _, _) => OperCode::RandCXNN(get_x(code, rsize)?, get_nn(code)),
+        (0xD, _, _, _) => {
+            OperCode::DisplayDXYN(get_x(code, rsize)?, get_y(code, rsize)?, get_n(code))
+        }
+        (0xE, _, 0x9, 0xE) => OperCode::KeyOpEX9E(get_x(code, rsize)?),
+        (0xE, _, 0xA, 0x1) => OperCode::KeyOpEXA1(get_x(code, rsize)?),
+        (0xF, _, 0x0, 0x7) => OperCode::TimerFX07(get_x(code, rsize)?),
+        (0xF, _, 0x0, 0xA) => OperCode::KeyOpFX0A(get_x(code, rsize)?),
+        (0xF, _, 0x1, 0x5) => OperCode::TimerFX15(get_x(code, rsize)?),
+        (0xF, _, 0x1, 0x8) => OperCode::SoundFX18(get_x(code, rsize)?),
+        (0xF, 0, 0x0, 0x2) => OperCode::SoundF002,
+        (0xF, _, 0x3, 0xA) => OperCode::SoundFX3A(get_x(code, rsize)?),
+        (0xF, _, 0x1, 0xE) => OperCode::MemFX1E(get_x(code, rsize)?),
+        (0xF, _, 0x2, 0x9) => OperCode::MemFX29(get_x(code, rsize)?),
+        (0xF, _, 0x3, 0x3) => OperCode::BcdFX33(get_x(code, rsize)?),
+        (0xF, _, 0x5, 0x5) => OperCode::MemFX55(get_x(code, rsize)?),
+        (0xF, _, 0x6, 0x5) => OperCode::MemFX65(get_x(code, rsize)?),
+        (_, _, _, _) => OperCode::Unknown,
+    };
+    Some(oper)
+}
+
+fn get_x(code: &u16, rsize: &usize) -> Option<usize> {
     let x = ((code & 0x0F00) >> 8) as usize;
-    if &x >= rsize {
-        panic!("Register overflow!");
+    if &x < rsize {
+        Some(x)
+    } else {
+        None
     }
-    x
 }
 
-fn get_y(code: &u16, rsize: &usize) -> usize {
+fn get_y(code: &u16, rsize: &usize) -> Option<usize> {
     let y = ((code & 0x00F0) >> 4) as usize;
-    if &y >= rsize {
-        panic!("Register overflow!");
+    if &y < rsize {
+        Some(y)
+    } else {
+        None
     }
-    y
 }
 
 fn get_n(code: &u16) -> u8 {
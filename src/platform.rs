@@ -0,0 +1,349 @@
+use crate::analysis::{self, EdgeKind};
+use crate::emu::{MEM_SIZE, PRG_INCR, PRG_START, REG_SIZE};
+use crate::oper::{Oper, OperCode};
+use crate::quirk::QuirkPreset;
+use std::collections::BTreeSet;
+
+/// Weight of an extension operation reached from the entry
+const WEIGHT_REACHED: f64 = 4.0;
+
+/// Weight of an extension operation found only by sweeping the ROM,
+/// which may be data
+const WEIGHT_SWEPT: f64 = 0.25;
+
+/// Weight of a ROM larger than a platform memory
+const WEIGHT_SIZE: f64 = 8.0;
+
+/// Reached standard operations worth one point of CHIP-8 evidence
+const STANDARD_PER_POINT: f64 = 8.0;
+
+/// Score by which a platform must exceed the ones it extends, that of
+/// a single reached operation
+const MARGIN: f64 = WEIGHT_REACHED;
+
+/// Total weight of the operations found only by sweeping, short of the
+/// margin so that data never chooses a platform on its own
+const SWEPT_MAX: f64 = MARGIN / 2.0;
+
+/// Bytes of XO-CHIP memory
+const XO_MEM_SIZE: usize = 0x10000_usize;
+
+///
+/// Interpreter platform a ROM targets
+///
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Platform {
+    ///
+    /// Original CHIP-8
+    ///
+    Chip8,
+
+    ///
+    /// SUPER-CHIP 1.1, adding the high resolution mode, scrolling and
+    /// large sprites
+    ///
+    SuperChip,
+
+    ///
+    /// XO-CHIP, extending SUPER-CHIP with 64K of memory, bit planes and
+    /// audio patterns
+    ///
+    XoChip,
+
+    ///
+    /// MegaChip, extending SUPER-CHIP with 16M of memory and color
+    /// sprites
+    ///
+    MegaChip,
+}
+
+impl Platform {
+    ///
+    /// Return all platforms, every one after the ones it extends
+    ///
+    pub fn all() -> Vec<Platform> {
+        vec![
+            Platform::Chip8,
+            Platform::SuperChip,
+            Platform::XoChip,
+            Platform::MegaChip,
+        ]
+    }
+
+    ///
+    /// Return the short name of the platform
+    ///
+    pub fn name(&self) -> &'static str {
+        match self {
+            Platform::Chip8 => "chip8",
+            Platform::SuperChip => "schip",
+            Platform::XoChip => "xochip",
+            Platform::MegaChip => "megachip",
+        }
+    }
+
+    ///
+    /// Return whether the platform runs the operations of another one
+    ///
+    pub fn extends(&self, other: &Platform) -> bool {
+        match (self, other) {
+            (a, b) if a == b => true,
+            (_, Platform::Chip8) => true,
+            (Platform::XoChip, Platform::SuperChip) => true,
+            (Platform::MegaChip, Platform::SuperChip) => true,
+            _ => false,
+        }
+    }
+
+    ///
+    /// Return the quirk preset of the platform; MegaChip builds on
+    /// SUPER-CHIP 1.1
+    ///
+    pub fn preset(&self) -> QuirkPreset {
+        match self {
+            Platform::Chip8 => QuirkPreset::Chip8,
+            Platform::SuperChip | Platform::MegaChip => QuirkPreset::SuperChip,
+            Platform::XoChip => QuirkPreset::XoChip,
+        }
+    }
+}
+
+///
+/// Hint of the platform a ROM targets
+///
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct Evidence {
+    /// Address of the operation, or None for the size of the ROM and the
+    /// standard operations
+    pub address: Option<u16>,
+
+    /// Platform the hint points to
+    pub platform: Platform,
+
+    /// Weight of the hint
+    pub weight: f64,
+}
+
+///
+/// Platform inferred from the operations and the size of a ROM
+///
+/// Operations only defined by an extension are searched on the addresses
+/// reached from `PRG_START`, walking past them and the operands of the
+/// long ones, and on every even offset of the ROM, where they may be
+/// data. Those only found on offsets weigh less, all together less than
+/// a single reached one. Standard operations reached count for CHIP-8,
+/// and a ROM beyond `MEM_SIZE` for the platforms with more memory.
+///
+/// Every platform scores the evidence of the platforms it extends, and
+/// is chosen over them only by exceeding their score by a margin. The
+/// confidence is the share of the evidence the chosen platform scores,
+/// so it grows with the evidence and drops on conflicting hints.
+///
+/// # Example
+///
+/// ```
+/// use rc201_8::platform::{Detection, Platform};
+///
+/// // Switch to high resolution, then loop forever
+/// let detection = Detection::new(&[0x00, 0xFF, 0x12, 0x02]);
+///
+/// assert_eq!(detection.platform, Platform::SuperChip);
+/// assert!(detection.confidence > 0.75);
+/// ```
+///
+#[derive(Debug, Clone, PartialEq)]
+pub struct Detection {
+    /// Most likely platform
+    pub platform: Platform,
+
+    /// Confidence in the platform, from 0 to 1
+    pub confidence: f64,
+
+    /// Hints found
+    pub evidence: Vec<Evidence>,
+}
+
+impl Detection {
+    ///
+    /// Returns the detection of a ROM loaded on `PRG_START`
+    ///
+    pub fn new(rom: &[u8]) -> Detection {
+        let base = PRG_START as u16;
+        let word = |offset: usize| -> Option<u16> {
+            Some(u16::from(*rom.get(offset)?) << 8 | u16::from(*rom.get(offset + 1)?))
+        };
+        let reached = reach(rom);
+        let standard = reached
+            .iter()
+            .filter_map(|a| word(usize::from(a - base)))
+            .filter(|c| extension(*c).is_none())
+            .filter(|c| Oper::from_code(c, &REG_SIZE) != OperCode::Unknown)
+            .count();
+
+        let mut evidence = Vec::new();
+        if standard > 0 {
+            evidence.push(Evidence {
+                address: None,
+                platform: Platform::Chip8,
+                weight: standard as f64 / STANDARD_PER_POINT,
+            });
+        }
+
+        // Extension operations, skipping the operands of long ones
+        let offsets: BTreeSet<usize> = (0..rom.len())
+            .step_by(2)
+            .chain(reached.iter().map(|a| usize::from(a - base)))
+            .collect();
+        let mut operand = None;
+        for offset in offsets {
+            let address = base.wrapping_add(offset as u16);
+            let code = match word(offset) {
+                Some(code) if operand != Some(offset) => code,
+                _ => continue,
+            };
+            let platform = match extension(code) {
+                Some(platform) => platform,
+                None => continue,
+            };
+            if size(code) > PRG_INCR {
+                operand = Some(offset + 2);
+            }
+            let weight = if reached.contains(&address) {
+                WEIGHT_REACHED
+            } else {
+                WEIGHT_SWEPT
+            };
+            evidence.push(Evidence {
+                address: Some(address),
+                platform,
+                weight,
+            });
+        }
+
+        // Scale the swept operations down to their bound
+        let unreached = |e: &Evidence| e.address.is_some_and(|a| !reached.contains(&a));
+        let swept: f64 = evidence
+            .iter()
+            .filter(|e| unreached(e))
+            .map(|e| e.weight)
+            .sum();
+        if swept > SWEPT_MAX {
+            for e in evidence.iter_mut() {
+                if unreached(e) {
+                    e.weight *= SWEPT_MAX / swept;
+                }
+            }
+        }
+
+        let size = PRG_START + rom.len();
+        if size > XO_MEM_SIZE {
+            evidence.push(Evidence {
+                address: None,
+                platform: Platform::MegaChip,
+                weight: WEIGHT_SIZE,
+            });
+        } else if size > MEM_SIZE {
+            for platform in [Platform::XoChip, Platform::MegaChip].iter() {
+                evidence.push(Evidence {
+                    address: None,
+                    platform: *platform,
+                    weight: WEIGHT_SIZE,
+                });
+            }
+        }
+
+        let score = |platform: &Platform| -> f64 {
+            evidence
+                .iter()
+                .filter(|e| platform.extends(&e.platform))
+                .map(|e| e.weight)
+                .sum()
+        };
+        let mut platform = Platform::Chip8;
+        for candidate in Platform::all() {
+            if score(&candidate) >= score(&platform) + MARGIN {
+                platform = candidate;
+            }
+        }
+
+        let total: f64 = evidence.iter().map(|e| e.weight).sum();
+        let confidence = score(&platform) / (total + 1.0);
+        Detection {
+            platform,
+            confidence,
+            evidence,
+        }
+    }
+}
+
+/// Return the addresses of the operations reached from `PRG_START`,
+/// walking past the extension operations and their operands
+fn reach(rom: &[u8]) -> BTreeSet<u16> {
+    let base = PRG_START as u16;
+    let word = |address: u16| -> Option<u16> {
+        let offset = usize::from(address.checked_sub(base)?);
+        Some(u16::from(*rom.get(offset)?) << 8 | u16::from(*rom.get(offset + 1)?))
+    };
+    let mut reached = BTreeSet::new();
+    let mut pending = vec![base];
+    while let Some(address) = pending.pop() {
+        let code = match word(address) {
+            Some(code) if reached.insert(address) => code,
+            _ => continue,
+        };
+        if extension(code).is_some() {
+            // Exit ends the program
+            if code != 0x00FD {
+                pending.push(address.wrapping_add(size(code)));
+            }
+            continue;
+        }
+        for edge in analysis::successors(address, &Oper::from_code(&code, &REG_SIZE)) {
+            // A skip steps over the whole of a long operation
+            let skipped = word(edge.target.wrapping_sub(PRG_INCR)).map_or(PRG_INCR, size);
+            match edge.kind {
+                EdgeKind::Skip => pending.push(edge.target.wrapping_add(skipped - PRG_INCR)),
+                _ => pending.push(edge.target),
+            }
+        }
+    }
+    reached
+}
+
+/// Return the bytes of an operation, 4 for the long ones followed by an
+/// operand
+fn size(code: u16) -> u16 {
+    if code == 0xF000 || code & 0xFF00 == 0x0100 {
+        2 * PRG_INCR
+    } else {
+        PRG_INCR
+    }
+}
+
+/// Return the platform defining an operation beyond CHIP-8
+fn extension(code: u16) -> Option<Platform> {
+    let platform = match code {
+        // Scroll down, right and left, exit, low and high resolution
+        0x00C0..=0x00CF | 0x00FB..=0x00FF => Platform::SuperChip,
+        // Large font, save and load flags
+        _ if matches!(code & 0xF0FF, 0xF030 | 0xF075 | 0xF085) => Platform::SuperChip,
+        // Large sprite
+        _ if code & 0xF00F == 0xD000 => Platform::SuperChip,
+        // Scroll up
+        0x00D0..=0x00DF => Platform::XoChip,
+        // Long I, audio pattern
+        0xF000 | 0xF002 => Platform::XoChip,
+        // Plane, pitch
+        _ if matches!(code & 0xF0FF, 0xF001 | 0xF03A) => Platform::XoChip,
+        // Save and load ranges
+        _ if matches!(code & 0xF00F, 0x5002 | 0x5003) => Platform::XoChip,
+        // Mega off and on, scroll up, stop sound
+        0x0010 | 0x0011 | 0x00B0..=0x00BF | 0x0700 => Platform::MegaChip,
+        // Long I, palette, sprite width and height, alpha
+        0x0100..=0x05FF => Platform::MegaChip,
+        // Digitized sound, blend mode
+        0x0600..=0x060F | 0x0800..=0x080F => Platform::MegaChip,
+        _ => return None,
+    };
+    Some(platform)
+}
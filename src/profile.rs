@@ -0,0 +1,202 @@
+use crate::cpu::{Cpu, CpuError};
+use crate::emu::REG_SIZE;
+use crate::oper::{Oper, OperCode};
+use std::collections::HashMap;
+use std::fmt;
+
+/// Hot addresses listed by the textual report
+const REPORT_ADDRESSES: usize = 16_usize;
+
+///
+/// Execution counters per operation and per address
+///
+/// # Example
+///
+/// ```
+/// use rc201_8::emu::Emu;
+/// use rc201_8::profile::Profiler;
+/// use rc201_8::display::{Display, DisplayDummy};
+///
+/// let mut emu = Emu::new(DisplayDummy::new());
+/// let mut profiler = Profiler::new();
+///
+/// // Increment V0 in a loop, then wait for a key
+/// emu.load_rom(&[0x70, 0x01, 0x30, 0x10, 0x12, 0x00, 0xF1, 0x0A]).unwrap();
+/// for _ in 0..10 {
+///     profiler.frame(&mut emu, 10).unwrap();
+/// }
+///
+/// let report = profiler.report();
+///
+/// assert_eq!(report.instructions, 100);
+/// assert_eq!(report.opers[0], ("KeyOpFX0A", 53));
+/// assert_eq!(report.opers[1], ("Cond3XNN", 16));
+/// assert_eq!(report.addresses[0], (0x206, 53));
+/// assert_eq!(report.blocked_instructions, 53);
+/// assert_eq!(report.blocked_frames, 6);
+/// assert_eq!(report.ipf_min, 0);
+/// assert_eq!(report.ipf_max, 10);
+/// ```
+///
+#[derive(Debug, Default)]
+pub struct Profiler {
+    /// Executions per operation name
+    opers: HashMap<&'static str, u64>,
+
+    /// Executions per program counter
+    addresses: HashMap<u16, u64>,
+
+    /// Instructions spent waiting for a key in `KeyOpFX0A`
+    blocked_instructions: u64,
+
+    /// Frames that ended waiting for a key
+    blocked_frames: u64,
+
+    /// Instructions, excluding key waits, of each frame
+    frames: Vec<usize>,
+}
+
+///
+/// Sorted profiling results
+///
+#[derive(Debug, Clone, PartialEq)]
+pub struct ProfileReport {
+    /// Processed instructions
+    pub instructions: u64,
+
+    /// Executions per operation, most frequent first
+    pub opers: Vec<(&'static str, u64)>,
+
+    /// Executions per address, most frequent first
+    pub addresses: Vec<(u16, u64)>,
+
+    /// Profiled frames
+    pub frames: usize,
+
+    /// Fewest instructions in a frame, excluding key waits
+    pub ipf_min: usize,
+
+    /// Most instructions in a frame, excluding key waits
+    pub ipf_max: usize,
+
+    /// Average instructions per frame, excluding key waits
+    pub ipf_mean: f64,
+
+    /// Instructions spent waiting for a key in `KeyOpFX0A`
+    pub blocked_instructions: u64,
+
+    /// Frames that ended waiting for a key
+    pub blocked_frames: u64,
+}
+
+impl Profiler {
+    ///
+    /// Returns a new Profiler instance with empty counters
+    ///
+    pub fn new() -> Profiler {
+        Profiler::default()
+    }
+
+    ///
+    /// Process a single instruction, returning true if it is waiting
+    /// for a key
+    ///
+    pub fn step<C: Cpu>(&mut self, cpu: &mut C) -> Result<bool, CpuError> {
+        let cnt = cpu.cnt_get();
+        let oper = Oper::from_code(&cpu.fetch_opcode()?, &REG_SIZE);
+        cpu.step()?;
+
+        *self.opers.entry(oper.name()).or_insert(0) += 1;
+        *self.addresses.entry(cnt).or_insert(0) += 1;
+        let blocked = match oper {
+            OperCode::KeyOpFX0A(_) => cpu.cnt_get() == cnt,
+            _ => false,
+        };
+        if blocked {
+            self.blocked_instructions += 1;
+        }
+        Ok(blocked)
+    }
+
+    ///
+    /// Process a frame of `ipf` instructions followed by a timer tick
+    ///
+    pub fn frame<C: Cpu>(&mut self, cpu: &mut C, ipf: usize) -> Result<(), CpuError> {
+        let mut useful = 0;
+        let mut blocked = false;
+        for _ in 0..ipf {
+            blocked = self.step(cpu)?;
+            if !blocked {
+                useful += 1;
+            }
+        }
+        cpu.timers_tick();
+
+        if blocked {
+            self.blocked_frames += 1;
+        }
+        self.frames.push(useful);
+        Ok(())
+    }
+
+    ///
+    /// Return the sorted results
+    ///
+    pub fn report(&self) -> ProfileReport {
+        let mut opers: Vec<(&'static str, u64)> =
+            self.opers.iter().map(|(o, n)| (*o, *n)).collect();
+        opers.sort_by(|a, b| b.1.cmp(&a.1).then(a.0.cmp(b.0)));
+
+        let mut addresses: Vec<(u16, u64)> = self.addresses.iter().map(|(a, n)| (*a, *n)).collect();
+        addresses.sort_by(|a, b| b.1.cmp(&a.1).then(a.0.cmp(&b.0)));
+
+        let total: usize = self.frames.iter().sum();
+        ProfileReport {
+            instructions: self.addresses.values().sum(),
+            opers,
+            addresses,
+            frames: self.frames.len(),
+            ipf_min: self.frames.iter().cloned().min().unwrap_or(0),
+            ipf_max: self.frames.iter().cloned().max().unwrap_or(0),
+            ipf_mean: if self.frames.is_empty() {
+                0.0
+            } else {
+                total as f64 / self.frames.len() as f64
+            },
+            blocked_instructions: self.blocked_instructions,
+            blocked_frames: self.blocked_frames,
+        }
+    }
+}
+
+impl fmt::Display for ProfileReport {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        let percent = |n: u64| 100.0 * n as f64 / self.instructions.max(1) as f64;
+        writeln!(f, "Instructions: {}", self.instructions)?;
+        writeln!(
+            f,
+            "Frames: {} (instructions per frame min {} mean {:.1} max {})",
+            self.frames, self.ipf_min, self.ipf_mean, self.ipf_max
+        )?;
+        writeln!(
+            f,
+            "Blocked in KeyOpFX0A: {} instructions, {} frames",
+            self.blocked_instructions, self.blocked_frames
+        )?;
+        writeln!(f, "Operations:")?;
+        for (oper, n) in &self.opers {
+            writeln!(f, "  {:<12} {:>10} {:>6.2}%", oper, n, percent(*n))?;
+        }
+        writeln!(f, "Hot addresses:")?;
+        for (address, n) in self.addresses.iter().take(REPORT_ADDRESSES) {
+            writeln!(
+                f,
+                "  0x{:04X}       {:>10} {:>6.2}%",
+                address,
+                n,
+                percent(*n)
+            )?;
+        }
+        Ok(())
+    }
+}
@@ -0,0 +1,156 @@
+///
+/// Behaviours that differ between CHIP-8 interpreters
+///
+/// The default disables every quirk.
+///
+/// # Example
+///
+/// ```
+/// use rc201_8::emu::Emu;
+/// use rc201_8::cpu::Cpu;
+/// use rc201_8::quirk::QuirkPreset;
+/// use rc201_8::display::{Display, DisplayDummy};
+///
+/// let mut emu = Emu::new(DisplayDummy::new());
+/// emu.quirks_put(QuirkPreset::Chip8.quirks());
+///
+/// // Shift V1 into V0
+/// emu.reg_put(&1, 0x6).unwrap();
+/// emu.recv_opcode(&0x8016).unwrap();
+/// assert_eq!(emu.reg_get(&0).unwrap(), 0x3);
+///
+/// // Store V0 to V2, moving I past them
+/// emu.ind_put(0x300);
+/// emu.recv_opcode(&0xF255).unwrap();
+/// assert_eq!(emu.ind_get(), 0x303);
+/// ```
+///
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub struct Quirks {
+    /// `BitOp8XY6` and `BitOp8XYE` shift VY into VX, instead of VX in
+    /// place
+    pub shift_vy: bool,
+
+    /// `MemFX55` and `MemFX65` leave I after the last accessed address
+    pub load_store_inc: bool,
+
+    /// `FlowBNNN` jumps to NNN plus VX, X being the high nibble of NNN,
+    /// instead of NNN plus V0
+    pub jump_vx: bool,
+
+    /// `BitOp8XY1`, `BitOp8XY2` and `BitOp8XY3` reset VF to zero
+    pub vf_reset: bool,
+}
+
+///
+/// Quirks of well known interpreters
+///
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum QuirkPreset {
+    ///
+    /// Original COSMAC VIP interpreter
+    ///
+    Chip8,
+
+    ///
+    /// SUPER-CHIP 1.1 on the HP 48
+    ///
+    SuperChip,
+
+    ///
+    /// XO-CHIP, as implemented by Octo
+    ///
+    XoChip,
+}
+
+impl QuirkPreset {
+    ///
+    /// Return all presets
+    ///
+    pub fn all() -> Vec<QuirkPreset> {
+        vec![
+            QuirkPreset::Chip8,
+            QuirkPreset::SuperChip,
+            QuirkPreset::XoChip,
+        ]
+    }
+
+    ///
+    /// Return the short name of the preset
+    ///
+    pub fn name(&self) -> &'static str {
+        match self {
+            QuirkPreset::Chip8 => "chip8",
+            QuirkPreset::SuperChip => "schip",
+            QuirkPreset::XoChip => "xochip",
+        }
+    }
+
+    ///
+    /// Return the preset with a given short name
+    ///
+    /// # Example
+    ///
+    /// ```
+    /// use rc201_8::quirk::QuirkPreset;
+    ///
+    /// assert_eq!(QuirkPreset::from_name("schip"), Some(QuirkPreset::SuperChip));
+    /// assert_eq!(QuirkPreset::from_name("vip"), None);
+    /// ```
+    ///
+    pub fn from_name(name: &str) -> Option<QuirkPreset> {
+        QuirkPreset::all().into_iter().find(|p| p.name() == name)
+    }
+
+    ///
+    /// Return the call stack depth of the preset
+    ///
+    /// The COSMAC VIP interpreter reserves room for 12 return addresses,
+    /// while later interpreters hold 16.
+    ///
+    /// # Example
+    ///
+    /// ```
+    /// use rc201_8::emu::Emu;
+    /// use rc201_8::quirk::QuirkPreset;
+    /// use rc201_8::display::{Display, DisplayDummy};
+    ///
+    /// let mut emu = Emu::new(DisplayDummy::new());
+    /// emu.stack_depth_put(QuirkPreset::Chip8.stack_depth());
+    ///
+    /// assert_eq!(emu.stack_depth(), 12);
+    /// ```
+    ///
+    pub fn stack_depth(&self) -> usize {
+        match self {
+            QuirkPreset::Chip8 => 12,
+            QuirkPreset::SuperChip | QuirkPreset::XoChip => 16,
+        }
+    }
+
+    ///
+    /// Return the quirks of the preset
+    ///
+    pub fn quirks(&self) -> Quirks {
+        match self {
+            QuirkPreset::Chip8 => Quirks {
+                shift_vy: true,
+                load_store_inc: true,
+                jump_vx: false,
+                vf_reset: true,
+            },
+            QuirkPreset::SuperChip => Quirks {
+                shift_vy: false,
+                load_store_inc: false,
+                jump_vx: true,
+                vf_reset: false,
+            },
+            QuirkPreset::XoChip => Quirks {
+                shift_vy: true,
+                load_store_inc: true,
+                jump_vx: false,
+                vf_reset: false,
+            },
+        }
+    }
+}
@@ -0,0 +1,645 @@
+use crate::audio::{PATTERN_SIZE, PITCH_DEFAULT};
+use crate::cpu::{Cpu, CpuError, CpuErrorVariant};
+use crate::debugger::Register;
+use crate::display::{DisplayBuffer, DisplayEmu, DSP_HEIGHT, DSP_WIDTH};
+use crate::diverge::Divergence;
+use crate::emu::{Emu, MEM_SIZE, PRG_START, REG_SIZE, STK_SIZE};
+use crate::mem::{MemError, MemErrorVariant};
+use crate::quirk::Quirks;
+
+/// Bytes of each font character
+const FONT_SIZE: usize = 5_usize;
+
+/// Semantics of an operation, receiving the whole operation code
+type Exec = fn(&mut Reference, u16) -> Result<(), CpuError>;
+
+/// Row of the operation table
+struct Entry {
+    /// Bits of the operation code identifying the operation
+    mask: u16,
+
+    /// Value of the identifying bits
+    pattern: u16,
+
+    /// Name of the operation, as in `OperCode::name`
+    name: &'static str,
+
+    /// Semantics of the operation
+    exec: Exec,
+}
+
+/// Every operation, looked up in order by `code & mask == pattern`
+#[rustfmt::skip]
+const TABLE: &[Entry] = &[
+    Entry { mask: 0xFFFF, pattern: 0x00E0, name: "Display00E0", exec: cls },
+    Entry { mask: 0xFFFF, pattern: 0x00EE, name: "Flow00EE", exec: ret },
+    Entry { mask: 0xF000, pattern: 0x1000, name: "Flow1NNN", exec: jp },
+    Entry { mask: 0xF000, pattern: 0x2000, name: "Flow2NNN", exec: call },
+    Entry { mask: 0xF000, pattern: 0x3000, name: "Cond3XNN", exec: se_nn },
+    Entry { mask: 0xF000, pattern: 0x4000, name: "Cond4XNN", exec: sne_nn },
+    Entry { mask: 0xF000, pattern: 0x5000, name: "Cond5XY0", exec: se_vy },
+    Entry { mask: 0xF000, pattern: 0x6000, name: "Const6XNN", exec: ld_nn },
+    Entry { mask: 0xF000, pattern: 0x7000, name: "Const7XNN", exec: add_nn },
+    Entry { mask: 0xF00F, pattern: 0x8000, name: "Assign8XY0", exec: ld_vy },
+    Entry { mask: 0xF00F, pattern: 0x8001, name: "BitOp8XY1", exec: or },
+    Entry { mask: 0xF00F, pattern: 0x8002, name: "BitOp8XY2", exec: and },
+    Entry { mask: 0xF00F, pattern: 0x8003, name: "BitOp8XY3", exec: xor },
+    Entry { mask: 0xF00F, pattern: 0x8004, name: "Math8XY4", exec: add_vy },
+    Entry { mask: 0xF00F, pattern: 0x8005, name: "Math8XY5", exec: sub },
+    Entry { mask: 0xF00F, pattern: 0x8006, name: "BitOp8XY6", exec: shr },
+    Entry { mask: 0xF00F, pattern: 0x8007, name: "Math8XY7", exec: subn },
+    Entry { mask: 0xF00F, pattern: 0x800E, name: "BitOp8XYE", exec: shl },
+    Entry { mask: 0xF000, pattern: 0x9000, name: "Cond9XY0", exec: sne_vy },
+    Entry { mask: 0xF000, pattern: 0xA000, name: "MemANNN", exec: ld_i },
+    Entry { mask: 0xF000, pattern: 0xB000, name: "FlowBNNN", exec: jp_v },
+    Entry { mask: 0xF000, pattern: 0xC000, name: "RandCXNN", exec: rnd },
+    Entry { mask: 0xF000, pattern: 0xD000, name: "DisplayDXYN", exec: drw },
+    Entry { mask: 0xF0FF, pattern: 0xE09E, name: "KeyOpEX9E", exec: skp },
+    Entry { mask: 0xF0FF, pattern: 0xE0A1, name: "KeyOpEXA1", exec: sknp },
+    Entry { mask: 0xF0FF, pattern: 0xF007, name: "TimerFX07", exec: ld_dt_get },
+    Entry { mask: 0xF0FF, pattern: 0xF00A, name: "KeyOpFX0A", exec: ld_k },
+    Entry { mask: 0xF0FF, pattern: 0xF015, name: "TimerFX15", exec: ld_dt_put },
+    Entry { mask: 0xF0FF, pattern: 0xF018, name: "SoundFX18", exec: ld_st },
+    Entry { mask: 0xFFFF, pattern: 0xF002, name: "SoundF002", exec: audio },
+    Entry { mask: 0xF0FF, pattern: 0xF03A, name: "SoundFX3A", exec: pitch },
+    Entry { mask: 0xF0FF, pattern: 0xF01E, name: "MemFX1E", exec: add_i },
+    Entry { mask: 0xF0FF, pattern: 0xF029, name: "MemFX29", exec: ld_f },
+    Entry { mask: 0xF0FF, pattern: 0xF033, name: "BcdFX33", exec: bcd },
+    Entry { mask: 0xF0FF, pattern: 0xF055, name: "MemFX55", exec: store },
+    Entry { mask: 0xF0FF, pattern: 0xF065, name: "MemFX65", exec: load },
+];
+
+///
+/// Reference interpreter, written for clarity rather than speed
+///
+/// Each operation is a row of a table matching the operation code and a
+/// small function implementing it, straight from the specification.
+/// The state is held in public fields, so tests can set and inspect it
+/// directly. Random numbers come from a given source, to reproduce the
+/// ones of another interpreter.
+///
+/// # Example
+///
+/// ```
+/// use rc201_8::reference::Reference;
+///
+/// let mut reference = Reference::new(Box::new(|| 0xFF));
+///
+/// // Add V1 to V0 with a carry, then draw a random number in V2
+/// reference.load_rom(&[0x60, 0xF0, 0x61, 0x20, 0x80, 0x14, 0xC2, 0x0F]).unwrap();
+/// for _ in 0..4 {
+///     reference.step().unwrap();
+/// }
+///
+/// assert_eq!(reference.v[0], 0x10);
+/// assert_eq!(reference.v[0xF], 1);
+/// assert_eq!(reference.v[2], 0x0F);
+/// assert_eq!(reference.pc, 0x208);
+/// ```
+///
+pub struct Reference {
+    /// Memory
+    pub mem: Vec<u8>,
+
+    /// Registers V0 to VF
+    pub v: [u8; REG_SIZE],
+
+    /// Memory pointer
+    pub i: usize,
+
+    /// Program counter
+    pub pc: u16,
+
+    /// Return addresses, outermost first
+    pub stack: Vec<u16>,
+
+    /// Most return addresses held by the stack
+    pub stack_depth: usize,
+
+    /// Delay timer
+    pub dt: u8,
+
+    /// Sound timer
+    pub st: u8,
+
+    /// Pressed keys
+    pub keys: [bool; 16],
+
+    /// Pixels, row by row
+    pub pixels: Vec<bool>,
+
+    /// Last audio pattern loaded by `SoundF002`, None until one is
+    pub pattern: Option<[u8; PATTERN_SIZE]>,
+
+    /// Pitch set by `SoundFX3A`
+    pub pitch: u8,
+
+    /// Interpreter quirks
+    pub quirks: Quirks,
+
+    /// Source of random numbers
+    random: Box<dyn FnMut() -> u8>,
+}
+
+impl Reference {
+    ///
+    /// Returns a new Reference instance, drawing random numbers from a
+    /// given source
+    ///
+    pub fn new(random: Box<dyn FnMut() -> u8>) -> Reference {
+        Reference {
+            mem: vec![0; MEM_SIZE],
+            v: [0; REG_SIZE],
+            i: 0,
+            pc: 0,
+            stack: Vec::new(),
+            stack_depth: STK_SIZE,
+            dt: 0,
+            st: 0,
+            keys: [false; 16],
+            pixels: vec![false; DSP_WIDTH * DSP_HEIGHT],
+            pattern: None,
+            pitch: PITCH_DEFAULT,
+            quirks: Quirks::default(),
+            random,
+        }
+    }
+
+    ///
+    /// Load a program on `PRG_START` and point the program counter to it
+    ///
+    pub fn load_rom(&mut self, rom: &[u8]) -> Result<(), MemError> {
+        if PRG_START + rom.len() > MEM_SIZE {
+            return Err(MemError::new(MemErrorVariant::AccessRangeViolation(
+                PRG_START,
+                PRG_START + rom.len(),
+            )));
+        }
+        self.mem[PRG_START..PRG_START + rom.len()].copy_from_slice(rom);
+        self.pc = PRG_START as u16;
+        Ok(())
+    }
+
+    ///
+    /// Return the name of the operation of a given code, or None if the
+    /// code is unknown
+    ///
+    /// # Example
+    ///
+    /// ```
+    /// use rc201_8::reference::Reference;
+    ///
+    /// assert_eq!(Reference::name(0x8AB6), Some("BitOp8XY6"));
+    /// assert_eq!(Reference::name(0x8AB8), None);
+    /// ```
+    ///
+    pub fn name(code: u16) -> Option<&'static str> {
+        Reference::entry(code).map(|e| e.name)
+    }
+
+    /// Return the table row of a given code
+    fn entry(code: u16) -> Option<&'static Entry> {
+        TABLE.iter().find(|e| code & e.mask == e.pattern)
+    }
+
+    ///
+    /// Fetch the operation at the program counter, advance the program
+    /// counter and process the operation
+    ///
+    pub fn step(&mut self) -> Result<(), CpuError> {
+        let pc = self.pc as usize;
+        if pc + 1 >= MEM_SIZE {
+            return Err(CpuError::new(CpuErrorVariant::InvalidProgramCounter(
+                self.pc,
+            )));
+        }
+        let code = u16::from(self.mem[pc]) << 8 | u16::from(self.mem[pc + 1]);
+        self.pc += 2;
+        self.exec(code)
+    }
+
+    ///
+    /// Process an operation code, leaving the program counter untouched
+    /// unless the operation changes it
+    ///
+    pub fn exec(&mut self, code: u16) -> Result<(), CpuError> {
+        match Reference::entry(code) {
+            Some(entry) => (entry.exec)(self, code),
+            None => Err(CpuError::new(CpuErrorVariant::InvalidOperationCode(code))),
+        }
+    }
+
+    ///
+    /// Decrement the delay and sound timers, once per 60 Hz frame
+    ///
+    pub fn timers_tick(&mut self) {
+        self.dt = self.dt.saturating_sub(1);
+        self.st = self.st.saturating_sub(1);
+    }
+
+    ///
+    /// Return the value of a register, as exposed to debuggers
+    ///
+    pub fn register(&self, register: &Register) -> u16 {
+        match register {
+            Register::V(x) => u16::from(self.v[*x]),
+            Register::I => self.i as u16,
+            Register::PC => self.pc,
+            Register::SP => self.stack.len() as u16,
+            Register::DT => u16::from(self.dt),
+            Register::ST => u16::from(self.st),
+            Register::PITCH => u16::from(self.pitch),
+        }
+    }
+
+    /// Read a byte, failing outside of the memory
+    fn read(&self, address: usize) -> Result<u8, CpuError> {
+        match self.mem.get(address) {
+            Some(value) => Ok(*value),
+            None => Err(CpuError::new(CpuErrorVariant::InvalidMemoryAccess(address))),
+        }
+    }
+
+    /// Write a byte, failing outside of the memory
+    fn write(&mut self, address: usize, value: u8) -> Result<(), CpuError> {
+        match self.mem.get_mut(address) {
+            Some(byte) => {
+                *byte = value;
+                Ok(())
+            }
+            None => Err(CpuError::new(CpuErrorVariant::InvalidMemoryAccess(address))),
+        }
+    }
+
+    /// Skip the next operation if a condition holds
+    fn skip_if(&mut self, condition: bool) -> Result<(), CpuError> {
+        if condition {
+            self.pc = self.pc.wrapping_add(2);
+        }
+        Ok(())
+    }
+
+    /// Set VX and then VF, if the operation has a flag
+    fn set_with_flag(&mut self, code: u16, value: u8, flag: Option<u8>) -> Result<(), CpuError> {
+        self.v[x(code)] = value;
+        if let Some(flag) = flag {
+            self.v[0xF] = flag;
+        }
+        Ok(())
+    }
+
+    /// Flag of the logic operations
+    fn logic_flag(&self) -> Option<u8> {
+        if self.quirks.vf_reset {
+            Some(0)
+        } else {
+            None
+        }
+    }
+
+    /// Operand of the shift operations
+    fn shift_operand(&self, code: u16) -> u8 {
+        if self.quirks.shift_vy {
+            self.v[y(code)]
+        } else {
+            self.v[x(code)]
+        }
+    }
+}
+
+/// Register X of an operation code
+fn x(code: u16) -> usize {
+    usize::from(code >> 8 & 0xF)
+}
+
+/// Register Y of an operation code
+fn y(code: u16) -> usize {
+    usize::from(code >> 4 & 0xF)
+}
+
+/// Lowest nibble of an operation code
+fn n(code: u16) -> u8 {
+    (code & 0xF) as u8
+}
+
+/// Lowest byte of an operation code
+fn nn(code: u16) -> u8 {
+    (code & 0xFF) as u8
+}
+
+/// Address of an operation code
+fn nnn(code: u16) -> u16 {
+    code & 0xFFF
+}
+
+fn cls(r: &mut Reference, _: u16) -> Result<(), CpuError> {
+    r.pixels.iter_mut().for_each(|p| *p = false);
+    Ok(())
+}
+
+fn ret(r: &mut Reference, _: u16) -> Result<(), CpuError> {
+    match r.stack.pop() {
+        Some(address) => {
+            r.pc = address;
+            Ok(())
+        }
+        None => Err(CpuError::new(CpuErrorVariant::StackUnderflow)),
+    }
+}
+
+fn jp(r: &mut Reference, code: u16) -> Result<(), CpuError> {
+    r.pc = nnn(code);
+    Ok(())
+}
+
+fn call(r: &mut Reference, code: u16) -> Result<(), CpuError> {
+    if r.stack.len() >= r.stack_depth {
+        return Err(CpuError::new(CpuErrorVariant::StackOverflow(
+            r.stack.len() + 1,
+        )));
+    }
+    r.stack.push(r.pc);
+    r.pc = nnn(code);
+    Ok(())
+}
+
+fn se_nn(r: &mut Reference, code: u16) -> Result<(), CpuError> {
+    r.skip_if(r.v[x(code)] == nn(code))
+}
+
+fn sne_nn(r: &mut Reference, code: u16) -> Result<(), CpuError> {
+    r.skip_if(r.v[x(code)] != nn(code))
+}
+
+fn se_vy(r: &mut Reference, code: u16) -> Result<(), CpuError> {
+    r.skip_if(r.v[x(code)] == r.v[y(code)])
+}
+
+fn sne_vy(r: &mut Reference, code: u16) -> Result<(), CpuError> {
+    r.skip_if(r.v[x(code)] != r.v[y(code)])
+}
+
+fn ld_nn(r: &mut Reference, code: u16) -> Result<(), CpuError> {
+    r.v[x(code)] = nn(code);
+    Ok(())
+}
+
+fn add_nn(r: &mut Reference, code: u16) -> Result<(), CpuError> {
+    r.v[x(code)] = r.v[x(code)].wrapping_add(nn(code));
+    Ok(())
+}
+
+fn ld_vy(r: &mut Reference, code: u16) -> Result<(), CpuError> {
+    r.set_with_flag(code, r.v[y(code)], None)
+}
+
+fn or(r: &mut Reference, code: u16) -> Result<(), CpuError> {
+    r.set_with_flag(code, r.v[x(code)] | r.v[y(code)], r.logic_flag())
+}
+
+fn and(r: &mut Reference, code: u16) -> Result<(), CpuError> {
+    r.set_with_flag(code, r.v[x(code)] & r.v[y(code)], r.logic_flag())
+}
+
+fn xor(r: &mut Reference, code: u16) -> Result<(), CpuError> {
+    r.set_with_flag(code, r.v[x(code)] ^ r.v[y(code)], r.logic_flag())
+}
+
+fn add_vy(r: &mut Reference, code: u16) -> Result<(), CpuError> {
+    let sum = u16::from(r.v[x(code)]) + u16::from(r.v[y(code)]);
+    r.set_with_flag(code, sum as u8, Some((sum > 0xFF) as u8))
+}
+
+fn sub(r: &mut Reference, code: u16) -> Result<(), CpuError> {
+    let (vx, vy) = (r.v[x(code)], r.v[y(code)]);
+    r.set_with_flag(code, vx.wrapping_sub(vy), Some((vx >= vy) as u8))
+}
+
+fn subn(r: &mut Reference, code: u16) -> Result<(), CpuError> {
+    let (vx, vy) = (r.v[x(code)], r.v[y(code)]);
+    r.set_with_flag(code, vy.wrapping_sub(vx), Some((vy >= vx) as u8))
+}
+
+fn shr(r: &mut Reference, code: u16) -> Result<(), CpuError> {
+    let operand = r.shift_operand(code);
+    r.set_with_flag(code, operand >> 1, Some(operand & 1))
+}
+
+fn shl(r: &mut Reference, code: u16) -> Result<(), CpuError> {
+    let operand = r.shift_operand(code);
+    r.set_with_flag(code, operand << 1, Some(operand >> 7))
+}
+
+fn ld_i(r: &mut Reference, code: u16) -> Result<(), CpuError> {
+    r.i = usize::from(nnn(code));
+    Ok(())
+}
+
+fn jp_v(r: &mut Reference, code: u16) -> Result<(), CpuError> {
+    let offset = if r.quirks.jump_vx {
+        r.v[x(code)]
+    } else {
+        r.v[0]
+    };
+    r.pc = nnn(code) + u16::from(offset);
+    Ok(())
+}
+
+fn rnd(r: &mut Reference, code: u16) -> Result<(), CpuError> {
+    r.v[x(code)] = (r.random)() & nn(code);
+    Ok(())
+}
+
+fn drw(r: &mut Reference, code: u16) -> Result<(), CpuError> {
+    // The sprite starts wrapped around the screen, and is clipped at its
+    // edges
+    let left = usize::from(r.v[x(code)]) % DSP_WIDTH;
+    let top = usize::from(r.v[y(code)]) % DSP_HEIGHT;
+    let mut collision = false;
+    for row in 0..usize::from(n(code)) {
+        let bits = match r.mem.get(r.i.saturating_add(row)) {
+            Some(bits) => *bits,
+            None => continue,
+        };
+        for col in 0..8 {
+            let (px, py) = (left + col, top + row);
+            if bits & (0x80 >> col) != 0 && px < DSP_WIDTH && py < DSP_HEIGHT {
+                let pixel = &mut r.pixels[py * DSP_WIDTH + px];
+                collision |= *pixel;
+                *pixel = !*pixel;
+            }
+        }
+    }
+    r.v[0xF] = collision as u8;
+    Ok(())
+}
+
+fn skp(r: &mut Reference, code: u16) -> Result<(), CpuError> {
+    let key = usize::from(r.v[x(code)]);
+    r.skip_if(key < 16 && r.keys[key])
+}
+
+fn sknp(r: &mut Reference, code: u16) -> Result<(), CpuError> {
+    let key = usize::from(r.v[x(code)]);
+    r.skip_if(!(key < 16 && r.keys[key]))
+}
+
+fn ld_dt_get(r: &mut Reference, code: u16) -> Result<(), CpuError> {
+    r.v[x(code)] = r.dt;
+    Ok(())
+}
+
+fn ld_k(r: &mut Reference, code: u16) -> Result<(), CpuError> {
+    // Wait by processing the same operation again
+    match r.keys.iter().position(|k| *k) {
+        Some(key) => r.v[x(code)] = key as u8,
+        None => r.pc = r.pc.wrapping_sub(2),
+    }
+    Ok(())
+}
+
+fn ld_dt_put(r: &mut Reference, code: u16) -> Result<(), CpuError> {
+    r.dt = r.v[x(code)];
+    Ok(())
+}
+
+fn ld_st(r: &mut Reference, code: u16) -> Result<(), CpuError> {
+    r.st = r.v[x(code)];
+    Ok(())
+}
+
+fn audio(r: &mut Reference, _: u16) -> Result<(), CpuError> {
+    let mut pattern = [0; PATTERN_SIZE];
+    for (k, p) in pattern.iter_mut().enumerate() {
+        *p = r.mem.get(r.i.saturating_add(k)).cloned().unwrap_or(0);
+    }
+    r.pattern = Some(pattern);
+    Ok(())
+}
+
+fn pitch(r: &mut Reference, code: u16) -> Result<(), CpuError> {
+    r.pitch = r.v[x(code)];
+    Ok(())
+}
+
+fn add_i(r: &mut Reference, code: u16) -> Result<(), CpuError> {
+    r.i = r.i.saturating_add(usize::from(r.v[x(code)]));
+    Ok(())
+}
+
+fn ld_f(r: &mut Reference, code: u16) -> Result<(), CpuError> {
+    r.i = usize::from(r.v[x(code)]) * FONT_SIZE;
+    Ok(())
+}
+
+fn bcd(r: &mut Reference, code: u16) -> Result<(), CpuError> {
+    let vx = r.v[x(code)];
+    r.write(r.i, vx / 100)?;
+    r.write(r.i.saturating_add(1), vx / 10 % 10)?;
+    r.write(r.i.saturating_add(2), vx % 10)
+}
+
+fn store(r: &mut Reference, code: u16) -> Result<(), CpuError> {
+    for k in 0..=x(code) {
+        r.write(r.i.saturating_add(k), r.v[k])?;
+    }
+    if r.quirks.load_store_inc {
+        r.i = r.i.saturating_add(x(code) + 1);
+    }
+    Ok(())
+}
+
+fn load(r: &mut Reference, code: u16) -> Result<(), CpuError> {
+    for k in 0..=x(code) {
+        r.v[k] = r.read(r.i.saturating_add(k))?;
+    }
+    if r.quirks.load_store_inc {
+        r.i = r.i.saturating_add(x(code) + 1);
+    }
+    Ok(())
+}
+
+///
+/// Return the first difference between the state of an emulator and the
+/// one of the reference interpreter
+///
+pub fn divergence(emu: &Emu<DisplayBuffer>, reference: &Reference) -> Option<Divergence> {
+    for register in Register::all() {
+        let va = register.read(emu).unwrap_or(0);
+        let vb = reference.register(&register);
+        if va != vb {
+            return Some(Divergence::Register(register, va, vb));
+        }
+    }
+
+    let mem = emu.mem_snapshot();
+    if mem != &reference.mem[..] {
+        let i = (0..mem.len()).find(|i| mem[*i] != reference.mem[*i])?;
+        return Some(Divergence::Memory(i, mem[i], reference.mem[i]));
+    }
+
+    if emu.get_display().pixels() != &reference.pixels[..] {
+        return Some(Divergence::Display);
+    }
+
+    if emu.pat_get() != reference.pattern {
+        return Some(Divergence::Pattern);
+    }
+    None
+}
+
+///
+/// Run an emulator and the reference interpreter in lockstep, returning
+/// the first instruction after which they differ
+///
+/// The timers of both tick after every `ipf` instructions. The run stops
+/// after `limit` instructions, or when both fail the same way.
+///
+/// # Example
+///
+/// ```
+/// use rc201_8::emu::Emu;
+/// use rc201_8::reference::{self, Reference};
+/// use rc201_8::display::{Display, DisplayBuffer};
+///
+/// // Count V0 down from 3, then return without a call
+/// let rom = [0x60, 0x03, 0x70, 0xFF, 0x30, 0x00, 0x12, 0x02, 0x00, 0xEE];
+///
+/// let mut emu = Emu::new(DisplayBuffer::new());
+/// let mut reference = Reference::new(Box::new(|| 0));
+/// emu.load_rom(&rom).unwrap();
+/// reference.load_rom(&rom).unwrap();
+///
+/// assert!(reference::lockstep(&mut emu, &mut reference, 100, 16).is_none());
+/// ```
+///
+pub fn lockstep(
+    emu: &mut Emu<DisplayBuffer>,
+    reference: &mut Reference,
+    limit: u64,
+    ipf: usize,
+) -> Option<(u64, Divergence)> {
+    for step in 0..limit {
+        let ra = emu.step();
+        let rb = reference.step();
+        match (&ra, &rb) {
+            (Err(ea), Err(eb)) if ea.to_string() == eb.to_string() => return None,
+            (Ok(_), Ok(_)) => (),
+            (ea, eb) => {
+                let fault = Divergence::Fault(
+                    ea.as_ref().err().map(|e| e.to_string()),
+                    eb.as_ref().err().map(|e| e.to_string()),
+                );
+                return Some((step, fault));
+            }
+        }
+        if let Some(divergence) = divergence(emu, reference) {
+            return Some((step, divergence));
+        }
+        if ipf > 0 && step as usize % ipf == ipf - 1 {
+            emu.timers_tick();
+            reference.timers_tick();
+        }
+    }
+    None
+}
@@ -0,0 +1,202 @@
+use crate::cpu::{Cpu, CpuError};
+use crate::emu::REG_SIZE;
+use crate::oper::{Oper, OperCode};
+
+/// Machine cycles of a 60 Hz frame of the COSMAC VIP, clocked at
+/// 1.76064 MHz with 8 clock cycles per machine cycle
+const VIP_FRAME_CYCLES: i64 = 3668_i64;
+
+/// Machine cycles taken each frame by the display interrupt routine and
+/// the 128 DMA transfers of the CDP1861
+const VIP_INTERRUPT_CYCLES: i64 = 1070_i64;
+
+/// Machine cycles of the fetch and decode loop of the VIP interpreter
+const VIP_FETCH_CYCLES: u64 = 40_u64;
+
+/// Machine cycles added to a condition when the next operation is skipped
+const VIP_SKIP_CYCLES: u64 = 4_u64;
+
+///
+/// Timing models of the emulation
+///
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Timing {
+    ///
+    /// Fixed number of instructions per frame
+    ///
+    InstructionsPerFrame(usize),
+
+    ///
+    /// Machine cycles of the COSMAC VIP interpreter: each operation costs
+    /// its cycles, `DisplayDXYN` waits for the vertical blank and the
+    /// display interrupt steals cycles from every frame
+    ///
+    CosmacVip,
+}
+
+///
+/// Return the machine cycles of an operation on the COSMAC VIP,
+/// including the fetch and decode loop
+///
+/// `skipped` tells if a condition skipped the next operation, and is
+/// ignored for other operations. The costs of data dependent operations
+/// are the ones of their common case. Operations unknown to the VIP,
+/// such as the XO-CHIP ones, only cost the fetch and decode loop.
+///
+/// # Example
+///
+/// ```
+/// use rc201_8::oper::OperCode;
+/// use rc201_8::timing;
+///
+/// assert_eq!(timing::vip_cycles(&OperCode::Const6XNN(0, 1), false), 46);
+/// assert_eq!(timing::vip_cycles(&OperCode::Cond3XNN(0, 1), true), 54);
+/// assert_eq!(timing::vip_cycles(&OperCode::MemFX55(3), false), 110);
+/// ```
+///
+pub fn vip_cycles(oper: &OperCode, skipped: bool) -> u64 {
+    let execute = match oper {
+        OperCode::Display00E0 => 3078,
+        OperCode::Flow00EE => 10,
+        OperCode::Flow1NNN(_) => 12,
+        OperCode::Flow2NNN(_) => 26,
+        OperCode::Cond3XNN(_, _) | OperCode::Cond4XNN(_, _) => 10,
+        OperCode::Cond5XY0(_, _) | OperCode::Cond9XY0(_, _) => 14,
+        OperCode::Const6XNN(_, _) => 6,
+        OperCode::Const7XNN(_, _) => 10,
+        OperCode::Assign8XY0(_, _)
+        | OperCode::BitOp8XY1(_, _)
+        | OperCode::BitOp8XY2(_, _)
+        | OperCode::BitOp8XY3(_, _)
+        | OperCode::Math8XY4(_, _)
+        | OperCode::Math8XY5(_, _)
+        | OperCode::BitOp8XY6(_, _)
+        | OperCode::Math8XY7(_, _)
+        | OperCode::BitOp8XYE(_, _) => 44,
+        OperCode::MemANNN(_) => 12,
+        OperCode::FlowBNNN(_) => 22,
+        OperCode::RandCXNN(_, _) => 36,
+        OperCode::DisplayDXYN(_, _, n) => 26 + 68 * u64::from(*n),
+        OperCode::KeyOpEX9E(_) | OperCode::KeyOpEXA1(_) => 14,
+        OperCode::TimerFX07(_)
+        | OperCode::KeyOpFX0A(_)
+        | OperCode::TimerFX15(_)
+        | OperCode::SoundFX18(_) => 10,
+        OperCode::MemFX1E(_) | OperCode::MemFX29(_) => 16,
+        OperCode::BcdFX33(_) => 116,
+        OperCode::MemFX55(x) | OperCode::MemFX65(x) => 14 + 14 * (*x as u64 + 1),
+        OperCode::SoundF002 | OperCode::SoundFX3A(_) | OperCode::Unknown => 0,
+    };
+    let skip = match oper {
+        OperCode::Cond3XNN(_, _)
+        | OperCode::Cond4XNN(_, _)
+        | OperCode::Cond5XY0(_, _)
+        | OperCode::Cond9XY0(_, _)
+        | OperCode::KeyOpEX9E(_)
+        | OperCode::KeyOpEXA1(_)
+            if skipped =>
+        {
+            VIP_SKIP_CYCLES
+        }
+        _ => 0,
+    };
+    VIP_FETCH_CYCLES + execute + skip
+}
+
+///
+/// Runs frames of a cpu under a given timing model
+///
+/// # Example
+///
+/// ```
+/// use rc201_8::emu::Emu;
+/// use rc201_8::timing::{Clock, Timing};
+/// use rc201_8::display::{Display, DisplayDummy};
+///
+/// // Assign V0 and loop
+/// let rom = [0x60, 0x01, 0x12, 0x00];
+///
+/// let mut emu = Emu::new(DisplayDummy::new());
+/// let mut clock = Clock::new(Timing::InstructionsPerFrame(10));
+/// emu.load_rom(&rom).unwrap();
+/// assert_eq!(clock.frame(&mut emu).unwrap(), 10);
+///
+/// let mut emu = Emu::new(DisplayDummy::new());
+/// let mut clock = Clock::new(Timing::CosmacVip);
+/// emu.load_rom(&rom).unwrap();
+/// assert_eq!(clock.frame(&mut emu).unwrap(), 54);
+///
+/// // The last operation overran the frame, delaying the next one
+/// assert_eq!(clock.frame(&mut emu).unwrap(), 53);
+///
+/// // Drawing waits for the vertical blank, ending the frame
+/// let mut emu = Emu::new(DisplayDummy::new());
+/// let mut clock = Clock::new(Timing::CosmacVip);
+/// emu.load_rom(&[0x60, 0x01, 0xD0, 0x01, 0x12, 0x00]).unwrap();
+/// assert_eq!(clock.frame(&mut emu).unwrap(), 2);
+/// ```
+///
+#[derive(Debug, Clone)]
+pub struct Clock {
+    /// Timing model
+    timing: Timing,
+
+    /// Machine cycles carried to the next frame, negative when the last
+    /// operation overran the frame
+    carry: i64,
+}
+
+impl Clock {
+    ///
+    /// Returns a new Clock instance
+    ///
+    pub fn new(timing: Timing) -> Clock {
+        Clock { timing, carry: 0 }
+    }
+
+    ///
+    /// Return the timing model
+    ///
+    pub fn timing(&self) -> Timing {
+        self.timing
+    }
+
+    ///
+    /// Process the instructions of a single frame followed by a timer
+    /// tick, returning the processed instructions
+    ///
+    pub fn frame<C: Cpu>(&mut self, cpu: &mut C) -> Result<usize, CpuError> {
+        let done = match self.timing {
+            Timing::InstructionsPerFrame(ipf) => {
+                for _ in 0..ipf {
+                    cpu.step()?;
+                }
+                ipf
+            }
+            Timing::CosmacVip => self.vip_frame(cpu)?,
+        };
+        cpu.timers_tick();
+        Ok(done)
+    }
+
+    /// Process the instructions fitting in the cycles of a VIP frame
+    fn vip_frame<C: Cpu>(&mut self, cpu: &mut C) -> Result<usize, CpuError> {
+        let mut budget = self.carry + VIP_FRAME_CYCLES - VIP_INTERRUPT_CYCLES;
+        let mut done = 0;
+        while budget > 0 {
+            let cnt = cpu.cnt_get();
+            let oper = Oper::from_code(&cpu.fetch_opcode()?, &REG_SIZE);
+            cpu.step()?;
+            done += 1;
+
+            let skipped = cpu.cnt_get() == cnt.wrapping_add(4);
+            budget -= vip_cycles(&oper, skipped) as i64;
+            if let OperCode::DisplayDXYN(_, _, _) = oper {
+                budget = budget.min(0);
+                break;
+            }
+        }
+        self.carry = budget;
+        Ok(done)
+    }
+}
@@ -0,0 +1,262 @@
+use crate::cpu::{Cpu, CpuError};
+use crate::emu::REG_SIZE;
+use crate::oper::{Oper, OperCode};
+use std::io::{self, Write};
+
+/// Leading bytes of a binary trace
+const BINARY_MAGIC: &[u8] = b"RC8T";
+
+/// Version of the binary trace format
+const BINARY_VERSION: u8 = 1_u8;
+
+///
+/// Output formats of the tracer
+///
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum TraceFormat {
+    ///
+    /// One line per instruction, with fields separated by a space:
+    ///
+    /// `PPPP OOOO MNEMONIC I=IIII SP=SS DT=DD ST=SS VX=XX...`
+    ///
+    /// `PPPP` is the program counter and `OOOO` the operation code, both
+    /// before the execution. The mnemonic is padded to 16 columns. I, SP,
+    /// DT and ST are the values after the execution, followed by the V
+    /// registers that were changed, in ascending order. All numbers are
+    /// upper case hexadecimal.
+    ///
+    Text,
+
+    ///
+    /// The bytes `RC8T` and the format version (1), followed by one
+    /// record per instruction. Each record holds the program counter,
+    /// the operation code and I as big-endian words, then SP, DT and ST
+    /// as bytes, then a big-endian word with bit X set for each changed
+    /// V register, and finally the new value of each changed register
+    /// in ascending order.
+    ///
+    Binary,
+}
+
+///
+/// State changes of a single processed instruction
+///
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct TraceRecord {
+    /// Program counter before the execution
+    pub cnt: u16,
+
+    /// Operation code
+    pub code: u16,
+
+    /// Decoded operation
+    pub oper: OperCode,
+
+    /// V registers changed by the operation, with their new values
+    pub changes: Vec<(usize, u8)>,
+
+    /// Memory pointer after the execution
+    pub ind: u16,
+
+    /// Stack pointer after the execution
+    pub spt: u8,
+
+    /// Delay timer after the execution
+    pub dtm: u8,
+
+    /// Sound timer after the execution
+    pub stm: u8,
+}
+
+impl TraceRecord {
+    ///
+    /// Process the next instruction of a cpu and record its changes
+    ///
+    /// # Example
+    ///
+    /// ```
+    /// use rc201_8::emu::Emu;
+    /// use rc201_8::mem::Mem;
+    /// use rc201_8::trace::TraceRecord;
+    /// use rc201_8::display::{Display, DisplayDummy};
+    ///
+    /// let mut emu = Emu::new(DisplayDummy::new());
+    /// emu.mem_write(0..2, &[0x6A, 0x02]).unwrap();
+    ///
+    /// let record = TraceRecord::step(&mut emu).unwrap();
+    ///
+    /// assert_eq!(record.changes, vec![(0xA, 0x02)]);
+    /// assert_eq!(
+    ///     record.to_text(),
+    ///     "0000 6A02 LD VA, 0x02      I=0000 SP=00 DT=00 ST=00 VA=02"
+    /// );
+    /// ```
+    ///
+    pub fn step<C: Cpu>(cpu: &mut C) -> Result<TraceRecord, CpuError> {
+        let cnt = cpu.cnt_get();
+        let code = cpu.fetch_opcode()?;
+        let before = registers(cpu);
+        cpu.step()?;
+        let changes = registers(cpu)
+            .iter()
+            .zip(before.iter())
+            .enumerate()
+            .filter(|(_, (a, b))| a != b)
+            .map(|(x, (a, _))| (x, *a))
+            .collect();
+        Ok(TraceRecord {
+            cnt,
+            code,
+            oper: Oper::from_code(&code, &REG_SIZE),
+            changes,
+            ind: cpu.ind_get() as u16,
+            spt: cpu.spt_get() as u8,
+            dtm: cpu.dtm_get(),
+            stm: cpu.stm_get(),
+        })
+    }
+
+    ///
+    /// Return the record in the text format, without line terminator
+    ///
+    pub fn to_text(&self) -> String {
+        let mut text = format!(
+            "{:04X} {:04X} {:<16} I={:04X} SP={:02X} DT={:02X} ST={:02X}",
+            self.cnt,
+            self.code,
+            self.oper.to_string(),
+            self.ind,
+            self.spt,
+            self.dtm,
+            self.stm
+        );
+        for (x, v) in &self.changes {
+            text.push_str(&format!(" V{:X}={:02X}", x, v));
+        }
+        text
+    }
+
+    ///
+    /// Return the record in the binary format
+    ///
+    /// # Example
+    ///
+    /// ```
+    /// use rc201_8::emu::Emu;
+    /// use rc201_8::mem::Mem;
+    /// use rc201_8::trace::TraceRecord;
+    /// use rc201_8::display::{Display, DisplayDummy};
+    ///
+    /// let mut emu = Emu::new(DisplayDummy::new());
+    /// emu.mem_write(0..2, &[0x6A, 0x02]).unwrap();
+    ///
+    /// let record = TraceRecord::step(&mut emu).unwrap();
+    ///
+    /// assert_eq!(
+    ///     record.to_binary(),
+    ///     vec![0x00, 0x00, 0x6A, 0x02, 0x00, 0x00, 0, 0, 0, 0x04, 0x00, 0x02]
+    /// );
+    /// ```
+    ///
+    pub fn to_binary(&self) -> Vec<u8> {
+        let mask = self.changes.iter().fold(0_u16, |m, (x, _)| m | 1 << x);
+        let mut binary = Vec::with_capacity(11 + self.changes.len());
+        binary.extend_from_slice(&self.cnt.to_be_bytes());
+        binary.extend_from_slice(&self.code.to_be_bytes());
+        binary.extend_from_slice(&self.ind.to_be_bytes());
+        binary.extend_from_slice(&[self.spt, self.dtm, self.stm]);
+        binary.extend_from_slice(&mask.to_be_bytes());
+        binary.extend(self.changes.iter().map(|(_, v)| v));
+        binary
+    }
+}
+
+///
+/// Writes trace records to a given sink
+///
+/// # Example
+///
+/// ```
+/// use rc201_8::emu::Emu;
+/// use rc201_8::mem::Mem;
+/// use rc201_8::trace::{TraceFormat, TraceRecord, Tracer};
+/// use rc201_8::display::{Display, DisplayDummy};
+///
+/// let mut emu = Emu::new(DisplayDummy::new());
+/// let mut tracer = Tracer::new(Vec::new(), TraceFormat::Text);
+///
+/// // Set I to 0x300 and jump back
+/// emu.mem_write(0..4, &[0xA3, 0x00, 0x10, 0x00]).unwrap();
+/// for _ in 0..3 {
+///     let record = TraceRecord::step(&mut emu).unwrap();
+///     tracer.write(&record).unwrap();
+/// }
+///
+/// let text = String::from_utf8(tracer.into_inner()).unwrap();
+/// let lines: Vec<&str> = text.lines().collect();
+///
+/// assert_eq!(lines.len(), 3);
+/// assert_eq!(lines[1], "0002 1000 JP 0x000         I=0300 SP=00 DT=00 ST=00");
+/// ```
+///
+pub struct Tracer<W: Write> {
+    /// Output of the records
+    sink: W,
+
+    /// Format of the records
+    format: TraceFormat,
+
+    /// Whether the binary header was written
+    started: bool,
+}
+
+impl<W: Write> Tracer<W> {
+    ///
+    /// Returns a new Tracer instance
+    ///
+    pub fn new(sink: W, format: TraceFormat) -> Tracer<W> {
+        Tracer {
+            sink,
+            format,
+            started: false,
+        }
+    }
+
+    ///
+    /// Write a record on the sink
+    ///
+    pub fn write(&mut self, record: &TraceRecord) -> io::Result<()> {
+        match self.format {
+            TraceFormat::Text => writeln!(self.sink, "{}", record.to_text()),
+            TraceFormat::Binary => {
+                if !self.started {
+                    self.sink.write_all(BINARY_MAGIC)?;
+                    self.sink.write_all(&[BINARY_VERSION])?;
+                    self.started = true;
+                }
+                self.sink.write_all(&record.to_binary())
+            }
+        }
+    }
+
+    ///
+    /// Flush the sink
+    ///
+    pub fn flush(&mut self) -> io::Result<()> {
+        self.sink.flush()
+    }
+
+    ///
+    /// Return the sink
+    ///
+    pub fn into_inner(self) -> W {
+        self.sink
+    }
+}
+
+/// Snapshot of the V registers
+fn registers<C: Cpu>(cpu: &C) -> Vec<u8> {
+    (0..REG_SIZE)
+        .map(|x| cpu.reg_get(&x).unwrap_or(0))
+        .collect()
+}
@@ -0,0 +1,198 @@
+use crate::audio::{Audio, SquareSynth, SynthConfig, PATTERN_SIZE};
+use std::convert::TryFrom;
+use std::io::{self, Seek, SeekFrom, Write};
+
+/// Emulated frames per second
+const FRAME_RATE: u64 = 60_u64;
+
+/// Bytes of the RIFF and format headers before the sample data
+const HEADER_SIZE: u32 = 44_u32;
+
+/// Bytes of a mono 16 bit sample
+const SAMPLE_SIZE: u32 = 2_u32;
+
+/// Bytes of the file the RIFF size field leaves out
+const RIFF_OVERHEAD: u32 = 8_u32;
+
+///
+/// Audio backend writing the synthesised buzzer to a mono 16 bit PCM
+/// WAV file
+///
+/// XO-CHIP audio patterns play at the rate of the pitch register, as on
+/// `SquareSynth`.
+///
+/// Each emulated frame renders the samples up to its end, so frame `n`
+/// always starts on sample `n * sample_rate / 60`, rounded down. Write
+/// errors are kept and returned by `finish`, as is the frame that would
+/// grow the file beyond the 4 GiB a WAV header can describe.
+///
+/// # Example
+///
+/// ```
+/// use rc201_8::emu::Emu;
+/// use rc201_8::cpu::Cpu;
+/// use rc201_8::wav::WavSink;
+/// use rc201_8::audio::SynthConfig;
+/// use rc201_8::display::{Display, DisplayDummy};
+/// use std::cell::RefCell;
+/// use std::io::Cursor;
+/// use std::rc::Rc;
+///
+/// let config = SynthConfig {
+///     sample_rate: 8000,
+///     ..SynthConfig::default()
+/// };
+/// let sink = WavSink::new(Cursor::new(Vec::new()), config).unwrap();
+/// let sink = Rc::new(RefCell::new(sink));
+/// let mut emu = Emu::new(DisplayDummy::new());
+/// emu.audio_set(Box::new(sink.clone()));
+///
+/// // A silent frame, then two frames of buzzer
+/// emu.timers_tick();
+/// emu.stm_put(2);
+/// emu.timers_tick();
+/// emu.timers_tick();
+///
+/// drop(emu);
+/// let sink = Rc::try_unwrap(sink).ok().unwrap().into_inner();
+/// assert_eq!(sink.samples(), 400);
+///
+/// let wav = sink.finish().unwrap().into_inner();
+/// assert_eq!(&wav[0..4], b"RIFF");
+/// assert_eq!(wav.len(), 44 + 2 * 400);
+/// assert!(wav[44..44 + 2 * 133].iter().all(|b| *b == 0));
+/// assert!(wav[44 + 2 * 133..].iter().any(|b| *b != 0));
+/// ```
+///
+pub struct WavSink<W: Write + Seek> {
+    /// Output of the file
+    sink: W,
+
+    /// Synthesiser of the samples
+    synth: SquareSynth,
+
+    /// Emulated frames
+    frames: u64,
+
+    /// Written samples
+    samples: u64,
+
+    /// First write error
+    error: Option<io::Error>,
+}
+
+impl<W: Write + Seek> WavSink<W> {
+    ///
+    /// Returns a new WavSink instance, writing the file header
+    ///
+    pub fn new(mut sink: W, config: SynthConfig) -> io::Result<WavSink<W>> {
+        header_write(&mut sink, config.sample_rate, 0)?;
+        Ok(WavSink {
+            sink,
+            synth: SquareSynth::new(config),
+            frames: 0,
+            samples: 0,
+            error: None,
+        })
+    }
+
+    ///
+    /// Return the written samples
+    ///
+    pub fn samples(&self) -> u64 {
+        self.samples
+    }
+
+    ///
+    /// Complete the header sizes and return the output
+    ///
+    pub fn finish(mut self) -> io::Result<W> {
+        if let Some(e) = self.error.take() {
+            return Err(e);
+        }
+        let data = data_size(self.samples)?;
+        self.sink.seek(SeekFrom::Start(0))?;
+        header_write(&mut self.sink, self.synth.config().sample_rate, data)?;
+        self.sink.seek(SeekFrom::End(0))?;
+        self.sink.flush()?;
+        Ok(self.sink)
+    }
+
+    /// Render and write the samples of the ended frame
+    fn frame_write(&mut self) -> io::Result<()> {
+        self.frames += 1;
+        let rate = u64::from(self.synth.config().sample_rate);
+        let end = self.frames * rate / FRAME_RATE;
+        data_size(end)?;
+        let mut buffer = vec![0; (end - self.samples) as usize];
+        self.synth.render_i16(&mut buffer);
+        let bytes: Vec<u8> = buffer
+            .iter()
+            .flat_map(|s| s.to_le_bytes().to_vec())
+            .collect();
+        self.sink.write_all(&bytes)?;
+        self.samples = end;
+        Ok(())
+    }
+}
+
+impl<W: Write + Seek> Audio for WavSink<W> {
+    fn buzzer_start(&mut self) {
+        self.synth.buzzer_start();
+    }
+
+    fn buzzer_stop(&mut self) {
+        self.synth.buzzer_stop();
+    }
+
+    fn frame(&mut self) {
+        if self.error.is_none() {
+            if let Err(e) = self.frame_write() {
+                self.error = Some(e);
+            }
+        }
+    }
+
+    fn pattern(&mut self, pattern: &[u8; PATTERN_SIZE]) {
+        self.synth.pattern(pattern);
+    }
+
+    fn pitch(&mut self, pitch: u8) {
+        self.synth.pitch(pitch);
+    }
+}
+
+/// Return the bytes of sample data, failing if the file would not fit the
+/// 32 bit sizes of the header
+fn data_size(samples: u64) -> io::Result<u32> {
+    samples
+        .checked_mul(u64::from(SAMPLE_SIZE))
+        .and_then(|data| u32::try_from(data).ok())
+        .filter(|data| data.checked_add(HEADER_SIZE - RIFF_OVERHEAD).is_some())
+        .ok_or_else(|| too_large("WAV data exceeds 4 GiB!"))
+}
+
+/// Return the error of a file too large for its header
+fn too_large(message: &str) -> io::Error {
+    io::Error::new(io::ErrorKind::InvalidInput, message)
+}
+
+/// Write the RIFF and format headers of a mono 16 bit PCM file with a
+/// given size of sample data
+fn header_write<W: Write>(sink: &mut W, sample_rate: u32, data: u32) -> io::Result<()> {
+    let byte_rate = sample_rate
+        .checked_mul(SAMPLE_SIZE)
+        .ok_or_else(|| too_large("WAV byte rate exceeds 4 GiB per second!"))?;
+    sink.write_all(b"RIFF")?;
+    sink.write_all(&(HEADER_SIZE - RIFF_OVERHEAD + data).to_le_bytes())?;
+    sink.write_all(b"WAVEfmt ")?;
+    sink.write_all(&16_u32.to_le_bytes())?;
+    sink.write_all(&1_u16.to_le_bytes())?;
+    sink.write_all(&1_u16.to_le_bytes())?;
+    sink.write_all(&sample_rate.to_le_bytes())?;
+    sink.write_all(&byte_rate.to_le_bytes())?;
+    sink.write_all(&(SAMPLE_SIZE as u16).to_le_bytes())?;
+    sink.write_all(&16_u16.to_le_bytes())?;
+    sink.write_all(b"data")?;
+    sink.write_all(&data.to_le_bytes())
+}
@@ -0,0 +1,109 @@
+use rc201_8::alu::{self, AluOutput};
+use rc201_8::cpu::Cpu;
+use rc201_8::display::{Display, DisplayDummy};
+use rc201_8::emu::Emu;
+use rc201_8::quirk::{QuirkPreset, Quirks};
+
+/// N of every defined `8XYN` operation
+const OPS: [u8; 9] = [0x0, 0x1, 0x2, 0x3, 0x4, 0x5, 0x6, 0x7, 0xE];
+
+/// Expected result of an `8XYN` operation, computed on wider integers
+fn expected(n: u8, vx: u8, vy: u8, quirks: &Quirks) -> AluOutput {
+    let (x, y) = (i32::from(vx), i32::from(vy));
+    let logic = if quirks.vf_reset { Some(0) } else { None };
+    let shifted = i32::from(if quirks.shift_vy { vy } else { vx });
+    let (value, flag) = match n {
+        0x0 => (y, None),
+        0x1 => (x | y, logic),
+        0x2 => (x & y, logic),
+        0x3 => (x ^ y, logic),
+        0x4 => (x + y, Some((x + y > 0xFF) as u8)),
+        0x5 => (x - y, Some((x >= y) as u8)),
+        0x6 => (shifted / 2, Some((shifted % 2) as u8)),
+        0x7 => (y - x, Some((y >= x) as u8)),
+        0xE => (shifted * 2, Some((shifted >= 0x80) as u8)),
+        _ => unreachable!(),
+    };
+    AluOutput {
+        value: value.rem_euclid(0x100) as u8,
+        flag,
+    }
+}
+
+/// Every combination of the quirks used by the ALU
+fn quirk_sets() -> Vec<Quirks> {
+    let mut sets = Vec::new();
+    for shift_vy in &[false, true] {
+        for vf_reset in &[false, true] {
+            sets.push(Quirks {
+                shift_vy: *shift_vy,
+                vf_reset: *vf_reset,
+                ..Quirks::default()
+            });
+        }
+    }
+    sets
+}
+
+#[test]
+fn alu_matches_wide_arithmetic() {
+    for quirks in quirk_sets() {
+        for n in OPS.iter() {
+            let op = alu::op(*n).unwrap();
+            for vx in 0..=0xFF_u8 {
+                for vy in 0..=0xFF_u8 {
+                    assert_eq!(
+                        op(vx, vy, &quirks),
+                        expected(*n, vx, vy, &quirks),
+                        "8XY{:X} with {:02X}, {:02X} under {:?}",
+                        n,
+                        vx,
+                        vy,
+                        quirks
+                    );
+                }
+            }
+        }
+    }
+}
+
+#[test]
+fn add_nn_wraps() {
+    for vx in 0..=0xFF_u8 {
+        for nn in 0..=0xFF_u8 {
+            assert_eq!(
+                u16::from(alu::add_nn(vx, nn)),
+                (u16::from(vx) + u16::from(nn)) % 0x100
+            );
+        }
+    }
+}
+
+#[test]
+fn emu_writes_flag_after_result() {
+    let mut emu = Emu::new(DisplayDummy::new());
+    for preset in QuirkPreset::all() {
+        let quirks = preset.quirks();
+        emu.quirks_put(quirks);
+        for n in OPS.iter() {
+            for vx in 0..=0xFF_u8 {
+                for vy in 0..=0xFF_u8 {
+                    let out = expected(*n, vx, vy, &quirks);
+
+                    // V1 and V2, with VF holding a marker
+                    emu.reg_put(&0xF, 0xAA).unwrap();
+                    emu.reg_put(&1, vx).unwrap();
+                    emu.reg_put(&2, vy).unwrap();
+                    emu.recv_opcode(&(0x8120 | u16::from(*n))).unwrap();
+                    assert_eq!(emu.reg_get(&1).unwrap(), out.value);
+                    assert_eq!(emu.reg_get(&0xF).unwrap(), out.flag.unwrap_or(0xAA));
+
+                    // VF and V2: the flag overwrites the result
+                    emu.reg_put(&0xF, vx).unwrap();
+                    emu.recv_opcode(&(0x8F20 | u16::from(*n))).unwrap();
+                    assert_eq!(emu.reg_get(&0xF).unwrap(), out.flag.unwrap_or(out.value));
+                }
+            }
+        }
+    }
+}
@@ -0,0 +1,95 @@
+mod common;
+
+use rc201_8::analysis::{Cfg, Terminator};
+use rc201_8::cpu::Cpu;
+use rc201_8::display::{Display, DisplayBuffer};
+use rc201_8::emu::{Emu, REG_SIZE};
+use rc201_8::generator::{GenConfig, Generator};
+use rc201_8::oper::{Oper, OperCode};
+use rc201_8::quirk::QuirkPreset;
+use std::collections::BTreeSet;
+
+/// Programs generated for the soundness test
+const PROGRAMS: u64 = 100;
+
+/// Instructions run by each program
+const STEPS: usize = 2000;
+
+/// Run a ROM and check that every executed operation belongs to a block,
+/// or to the unreachable ranges once an indirect jump was taken
+fn check_sound(rom: &[u8], name: &str) {
+    let cfg = Cfg::new(rom);
+    let known: BTreeSet<u16> = cfg
+        .blocks()
+        .iter()
+        .flat_map(|b| b.opers.iter().map(|(a, _)| *a))
+        .collect();
+
+    for preset in QuirkPreset::all() {
+        let mut emu = Emu::new(DisplayBuffer::new());
+        emu.quirks_put(preset.quirks());
+        emu.rng_seed(0);
+        emu.load_rom(rom).unwrap();
+        let mut indirect = false;
+        for _ in 0..STEPS {
+            let cnt = emu.cnt_get();
+            let code = match emu.fetch_opcode() {
+                Ok(code) => code,
+                Err(_) => break,
+            };
+            let hidden = cfg.unreachable().iter().any(|r| r.contains(&cnt));
+            assert!(
+                known.contains(&cnt) || (indirect && hidden),
+                "{} under {}: {:03X} is missing",
+                name,
+                preset.name(),
+                cnt
+            );
+            if let OperCode::FlowBNNN(_) = Oper::from_code(&code, &REG_SIZE) {
+                indirect = true;
+            }
+            if emu.step().is_err() {
+                break;
+            }
+        }
+    }
+}
+
+#[test]
+fn graph_covers_executed_test_roms() {
+    for name in common::ROMS.iter() {
+        let rom = common::rom(name);
+        check_sound(&rom, name);
+    }
+}
+
+#[test]
+fn graph_covers_executed_generated_programs() {
+    let mut gen = Generator::new(0x47, GenConfig::default()).unwrap();
+    for program in 0..PROGRAMS {
+        let rom = gen.program();
+        check_sound(&rom, &format!("program {}", program));
+
+        // Every subroutine of a generated program returns, unless its
+        // return is only reached through an indirect jump
+        let cfg = Cfg::new(&rom);
+        for sub in cfg.subroutines() {
+            let block = |start: &u16| cfg.block(*start).unwrap();
+            if sub
+                .blocks
+                .iter()
+                .any(|b| block(b).terminator == Terminator::Indirect)
+            {
+                continue;
+            }
+            assert!(
+                sub.blocks
+                    .iter()
+                    .any(|b| block(b).terminator == Terminator::Return),
+                "program {}: sub_{:03x} never returns",
+                program,
+                sub.entry
+            );
+        }
+    }
+}
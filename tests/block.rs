@@ -0,0 +1,113 @@
+use rand::rngs::StdRng;
+use rand::{Rng, SeedableRng};
+use rc201_8::block::BlockEngine;
+use rc201_8::cpu::Cpu;
+use rc201_8::debugger::Register;
+use rc201_8::display::{Display, DisplayBuffer, DisplayEmu};
+use rc201_8::emu::Emu;
+use rc201_8::generator::{GenConfig, Generator};
+use rc201_8::keypad::Keypad;
+use rc201_8::mem::Mem;
+use rc201_8::quirk::QuirkPreset;
+
+/// Programs compared by the differential test
+const PROGRAMS: u64 = 300;
+
+/// Frames run by each program
+const FRAMES: usize = 60;
+
+/// Instructions per frame
+const IPF: usize = 16;
+
+/// Return the differences between two emulator states
+fn state_diff(a: &Emu<DisplayBuffer>, b: &Emu<DisplayBuffer>) -> Option<String> {
+    for register in Register::all() {
+        let va = register.read(a).unwrap();
+        let vb = register.read(b).unwrap();
+        if va != vb {
+            return Some(format!("{}: {:04X} != {:04X}", register.name(), va, vb));
+        }
+    }
+    if a.mem_read(..).unwrap() != b.mem_read(..).unwrap() {
+        return Some(String::from("memory"));
+    }
+    if a.get_display() != b.get_display() {
+        return Some(String::from("display"));
+    }
+    None
+}
+
+#[test]
+fn block_engine_matches_interpreter() {
+    let config = GenConfig {
+        key_wait: true,
+        ..GenConfig::default()
+    };
+    let mut gen = Generator::new(0x8C8, config).unwrap();
+    let mut keys = StdRng::seed_from_u64(0x8C8);
+    for program in 0..PROGRAMS {
+        let rom = gen.program();
+
+        let mut a = Emu::new(DisplayBuffer::new());
+        let mut b = Emu::new(DisplayBuffer::new());
+        a.load_rom(&rom).unwrap();
+        b.load_rom(&rom).unwrap();
+        a.rng_seed(program);
+        b.rng_seed(program);
+        let presets = QuirkPreset::all();
+        let quirks = presets[program as usize % presets.len()].quirks();
+        a.quirks_put(quirks);
+        b.quirks_put(quirks);
+        let mut engine = BlockEngine::new(&mut b);
+
+        for frame in 0..FRAMES {
+            // Hold a random key for the frame, or none
+            let key = keys.gen_range(0, 17) as u8;
+            for k in 0..16 {
+                let key_ref = a.key_from_u8(&k);
+                a.key_put(&key_ref, k == key);
+                engine.emu().key_put(&key_ref, k == key);
+            }
+
+            let ra = (0..IPF)
+                .try_for_each(|_| a.step())
+                .map_err(|e| e.to_string());
+            let rb = engine.run(IPF).map(|_| ()).map_err(|e| e.to_string());
+            assert_eq!(ra, rb, "program {} frame {}", program, frame);
+            if let Some(diff) = state_diff(&a, engine.emu()) {
+                panic!("program {} frame {}: {}", program, frame, diff);
+            }
+            if ra.is_err() {
+                break;
+            }
+            a.timers_tick();
+            engine.emu().timers_tick();
+        }
+    }
+}
+
+#[test]
+fn block_engine_retranslates_written_code() {
+    // Overwrite the assignment of V1 on 0x206 with one of V2, then run it
+    let mut emu = Emu::new(DisplayBuffer::new());
+    emu.load_rom(&[
+        0x60, 0x62, 0xA2, 0x06, 0x61, 0x01, 0x61, 0x01, 0xF0, 0x55, 0x12, 0x06,
+    ])
+    .unwrap();
+    let mut engine = BlockEngine::new(&mut emu);
+    assert_eq!(engine.run(10).unwrap(), 10);
+    drop(engine);
+
+    assert_eq!(emu.reg_get(&1).unwrap(), 0x01);
+    assert_eq!(emu.reg_get(&2).unwrap(), 0x01);
+}
+
+#[test]
+fn block_engine_removes_its_observer_on_drop() {
+    let mut emu = Emu::new(DisplayBuffer::new());
+    let engine = BlockEngine::new(&mut emu);
+    drop(engine);
+
+    // The engine registered the first observer
+    assert!(!emu.mem_observer_remove(1));
+}
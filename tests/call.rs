@@ -0,0 +1,22 @@
+use rc201_8::cpu::Cpu;
+use rc201_8::display::{Display, DisplayDummy};
+use rc201_8::emu::Emu;
+
+#[test]
+fn call_pushes_the_return_address() {
+    let mut emu = Emu::new(DisplayDummy::new());
+
+    // Call 0x206, which assigns V1 and returns to assign V0
+    emu.load_rom(&[0x22, 0x06, 0x60, 0x01, 0x12, 0x04, 0x61, 0x02, 0x00, 0xEE])
+        .unwrap();
+    emu.step().unwrap();
+    assert_eq!(emu.cnt_get(), 0x206);
+    assert_eq!(emu.stk_get().unwrap(), 0x202);
+
+    emu.step().unwrap();
+    emu.step().unwrap();
+    assert_eq!(emu.cnt_get(), 0x202);
+    emu.step().unwrap();
+    assert_eq!(emu.reg_get(&0).unwrap(), 1);
+    assert_eq!(emu.reg_get(&1).unwrap(), 2);
+}
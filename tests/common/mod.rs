@@ -0,0 +1,24 @@
+// Helpers shared by the integration tests, not all of which use each one
+#![allow(dead_code)]
+
+use std::fs;
+use std::path::PathBuf;
+
+/// Self-checking ROMs under `tests/roms`, written for this crate
+pub const ROMS: [&str; 4] = ["flags", "keypad", "opcode", "quirks"];
+
+/// Read a ROM of `tests/roms` by name
+pub fn rom(name: &str) -> Vec<u8> {
+    let path: PathBuf = [env!("CARGO_MANIFEST_DIR"), "tests", "roms"]
+        .iter()
+        .collect();
+    fs::read(path.join(format!("{}.ch8", name))).unwrap()
+}
+
+/// Lay out operation codes as a ROM
+pub fn assemble(program: &[u16]) -> Vec<u8> {
+    program
+        .iter()
+        .flat_map(|c| c.to_be_bytes().to_vec())
+        .collect()
+}
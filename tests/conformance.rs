@@ -0,0 +1,134 @@
+mod common;
+
+use rc201_8::cpu::Cpu;
+use rc201_8::display::{Display, DisplayBuffer, DisplayEmu};
+use rc201_8::emu::Emu;
+use rc201_8::keypad::{Key, Keypad};
+use rc201_8::quirk::QuirkPreset;
+use std::env;
+use std::fs;
+use std::path::PathBuf;
+
+/// Frames run by each ROM
+const FRAMES: usize = 120;
+
+/// Instructions per frame
+const IPF: usize = 15;
+
+/// Environment variable that rewrites the golden images instead of
+/// comparing them
+const BLESS: &str = "RC201_8_BLESS";
+
+/// Checks of `quirks.ch8`
+const QUIRK_CHECKS: usize = 8;
+
+/// Width of a check mark, eight marks per row
+const MARK_WIDTH: usize = 8;
+
+/// Column of the top pixel of a tick; the top pixel of a cross is on
+/// column 0
+const TICK_TOP: usize = 6;
+
+/// Return the path of a golden image under `tests/golden`
+fn golden_path(file: &str) -> PathBuf {
+    [env!("CARGO_MANIFEST_DIR"), "tests", "golden", file]
+        .iter()
+        .collect()
+}
+
+/// Run a ROM under a quirk preset, applying the key presses on the
+/// given frames, and return the final display
+fn run_display(rom: &str, preset: QuirkPreset, keys: &[(usize, Key)]) -> DisplayBuffer {
+    let rom = common::rom(rom);
+    let mut emu = Emu::new(DisplayBuffer::new());
+    emu.quirks_put(preset.quirks());
+    emu.stack_depth_put(preset.stack_depth());
+    emu.rng_seed(0);
+    emu.load_rom(&rom).unwrap();
+    for frame in 0..FRAMES {
+        for (_, key) in keys.iter().filter(|(f, _)| *f == frame) {
+            emu.key_put(key, true);
+        }
+        for _ in 0..IPF {
+            emu.step().unwrap();
+        }
+        emu.timers_tick();
+    }
+    emu.get_display().clone()
+}
+
+/// Run a ROM under a quirk preset and return the final display as a PBM
+/// image
+fn run(rom: &str, preset: QuirkPreset, keys: &[(usize, Key)]) -> String {
+    run_display(rom, preset, keys).to_pbm()
+}
+
+/// Compare the display of a ROM with its golden image for every preset
+fn conform(rom: &str, keys: &[(usize, Key)]) {
+    for preset in QuirkPreset::all() {
+        let image = run(rom, preset, keys);
+        let golden = golden_path(&format!("{}.{}.pbm", rom, preset.name()));
+        if env::var_os(BLESS).is_some() {
+            fs::write(&golden, &image).unwrap();
+            continue;
+        }
+        let expected = fs::read_to_string(&golden).unwrap();
+        assert!(
+            image == expected,
+            "{} under {} differs from {}:\n{}",
+            rom,
+            preset.name(),
+            golden.display(),
+            image
+        );
+    }
+}
+
+#[test]
+fn flags() {
+    conform("flags", &[]);
+}
+
+#[test]
+fn quirks() {
+    conform("quirks", &[]);
+}
+
+#[test]
+fn quirks_marks_follow_presets() {
+    for preset in QuirkPreset::all() {
+        let display = run_display("quirks", preset, &[]);
+        let ticks: Vec<bool> = (0..QUIRK_CHECKS)
+            .map(|k| {
+                let tick = display.pixel(&(k * MARK_WIDTH + TICK_TOP), &0);
+                let cross = display.pixel(&(k * MARK_WIDTH), &0);
+                assert_ne!(tick, cross, "quirks under {}: mark {}", preset.name(), k);
+                tick
+            })
+            .collect();
+
+        // The ROM expects the COSMAC VIP behaviour of each check
+        let quirks = preset.quirks();
+        let expected = vec![
+            quirks.vf_reset,
+            quirks.vf_reset,
+            quirks.vf_reset,
+            quirks.shift_vy,
+            quirks.shift_vy,
+            quirks.load_store_inc,
+            quirks.load_store_inc,
+            !quirks.jump_vx,
+        ];
+        assert_eq!(ticks, expected, "quirks under {}", preset.name());
+    }
+}
+
+#[test]
+fn opcode() {
+    conform("opcode", &[]);
+}
+
+#[test]
+fn keypad() {
+    conform("keypad", &[(5, Key::K5)]);
+}
@@ -0,0 +1,29 @@
+use rc201_8::coverage::Coverage;
+use rc201_8::display::{Display, DisplayDummy};
+use rc201_8::emu::Emu;
+
+#[test]
+fn lcov_reports_odd_instruction_addresses() {
+    let mut emu = Emu::new(DisplayDummy::new());
+    let mut coverage = Coverage::new();
+
+    // Jump to 0x203, then run odd-aligned instructions
+    emu.load_rom(&[0x12, 0x03, 0x00, 0x60, 0x01, 0x12, 0x05])
+        .unwrap();
+    for _ in 0..4 {
+        coverage.step(&mut emu).unwrap();
+    }
+
+    let mut lcov = Vec::new();
+    coverage
+        .write_lcov(&mut lcov, "odd.ch8", 0x200..0x207)
+        .unwrap();
+    let lcov = String::from_utf8(lcov).unwrap();
+    let lines: Vec<&str> = lcov.lines().filter(|l| l.starts_with("DA:")).collect();
+
+    assert_eq!(
+        lines,
+        vec!["DA:512,1", "DA:514,0", "DA:515,1", "DA:516,0", "DA:517,2", "DA:518,0"]
+    );
+    assert!(lcov.contains("LH:3\nLF:6\n"));
+}
@@ -0,0 +1,157 @@
+use rc201_8::dap::DapServer;
+use rc201_8::display::{Display, DisplayDummy};
+use rc201_8::emu::Emu;
+use serde_json::{json, Value};
+use std::fs;
+use std::io::Cursor;
+use std::path::PathBuf;
+
+/// Call 0x206, which assigns V0 and returns, then loop forever
+const ROM: [u8; 10] = [0x22, 0x06, 0x12, 0x02, 0x00, 0x00, 0x60, 0x01, 0x00, 0xEE];
+
+/// Body length beyond the largest accepted one
+const CONTENT_OVERSIZED: usize = 0x10_0001_usize;
+
+/// Source lines of the program
+const SOURCE_MAP: &str = "1 200\n2 202\n4 206\n5 208\n";
+
+/// Write the program and its source map, returning the launch arguments
+fn program(name: &str) -> Value {
+    let dir = std::env::temp_dir().join(format!("rc201_8-dap-{}-{}", name, std::process::id()));
+    fs::create_dir_all(&dir).unwrap();
+    let rom: PathBuf = dir.join("main.ch8");
+    let map: PathBuf = dir.join("main.map");
+    fs::write(&rom, ROM).unwrap();
+    fs::write(&map, SOURCE_MAP).unwrap();
+    json!({
+        "program": rom,
+        "sourceMap": map,
+        "source": "main.8o",
+        "stopOnEntry": true,
+    })
+}
+
+/// Frame the requests, serve them and return the messages sent back
+fn serve(requests: &[Value]) -> Vec<Value> {
+    let mut input = Vec::new();
+    for (seq, request) in requests.iter().enumerate() {
+        let mut request = request.clone();
+        request["seq"] = json!(seq + 1);
+        request["type"] = json!("request");
+        let body = request.to_string();
+        input.extend(format!("Content-Length: {}\r\n\r\n{}", body.len(), body).bytes());
+    }
+    messages(input)
+}
+
+/// Serve raw input and return the messages sent back
+fn messages(input: Vec<u8>) -> Vec<Value> {
+    let mut emu = Emu::new(DisplayDummy::new());
+    let mut output = Vec::new();
+    DapServer::new(Cursor::new(input), &mut output)
+        .serve(&mut emu)
+        .unwrap();
+    let output = String::from_utf8(output).unwrap();
+    output
+        .split("Content-Length: ")
+        .filter_map(|m| m.split("\r\n\r\n").nth(1))
+        .map(|m| serde_json::from_str(m).unwrap())
+        .collect()
+}
+
+#[test]
+fn initialized_follows_launch() {
+    let messages = serve(&[
+        json!({ "command": "initialize", "arguments": {} }),
+        json!({ "command": "launch", "arguments": program("initialized") }),
+        json!({ "command": "disconnect" }),
+    ]);
+    let initialized = messages
+        .iter()
+        .position(|m| m["event"] == "initialized")
+        .unwrap();
+    let launch = messages
+        .iter()
+        .position(|m| m["command"] == "launch")
+        .unwrap();
+    assert!(messages[launch]["success"].as_bool().unwrap());
+    assert!(launch < initialized);
+    assert_eq!(
+        messages
+            .iter()
+            .filter(|m| m["event"] == "initialized")
+            .count(),
+        1
+    );
+}
+
+#[test]
+fn initialized_is_not_sent_on_failed_launch() {
+    let messages = serve(&[
+        json!({ "command": "initialize", "arguments": {} }),
+        json!({ "command": "launch", "arguments": {} }),
+        json!({ "command": "disconnect" }),
+    ]);
+    assert!(messages
+        .iter()
+        .position(|m| m["event"] == "initialized")
+        .is_none());
+}
+
+#[test]
+fn breakpoints_only_verified_on_launched_source() {
+    let breakpoints = json!([{ "line": 4 }]);
+    let messages = serve(&[
+        json!({ "command": "launch", "arguments": program("source") }),
+        json!({ "command": "setBreakpoints", "arguments": {
+            "source": { "path": "other.8o" }, "breakpoints": breakpoints,
+        } }),
+        json!({ "command": "setBreakpoints", "arguments": {
+            "source": { "path": "main.8o" }, "breakpoints": breakpoints,
+        } }),
+        json!({ "command": "disconnect" }),
+    ]);
+    let replies: Vec<&Value> = messages
+        .iter()
+        .filter(|m| m["command"] == "setBreakpoints")
+        .collect();
+    assert_eq!(replies[0]["body"]["breakpoints"][0]["verified"], false);
+    assert_eq!(replies[1]["body"]["breakpoints"][0]["verified"], true);
+}
+
+#[test]
+fn next_stops_on_breakpoint_inside_call() {
+    let messages = serve(&[
+        json!({ "command": "launch", "arguments": program("next") }),
+        json!({ "command": "setBreakpoints", "arguments": {
+            "source": { "path": "main.8o" }, "breakpoints": [{ "line": 4 }],
+        } }),
+        json!({ "command": "configurationDone" }),
+        json!({ "command": "next", "arguments": { "threadId": 1 } }),
+        json!({ "command": "stackTrace", "arguments": { "threadId": 1 } }),
+        json!({ "command": "disconnect" }),
+    ]);
+    let next = messages
+        .iter()
+        .position(|m| m["command"] == "next")
+        .unwrap();
+    let stopped = messages[next..]
+        .iter()
+        .find(|m| m["event"] == "stopped")
+        .unwrap();
+    assert_eq!(stopped["body"]["reason"], "breakpoint");
+    let trace = messages
+        .iter()
+        .find(|m| m["command"] == "stackTrace")
+        .unwrap();
+    assert_eq!(trace["body"]["stackFrames"][0]["line"], 4);
+}
+
+#[test]
+fn oversized_content_length_is_rejected() {
+    // A valid request padded beyond the largest accepted body
+    let body = json!({ "seq": 1, "type": "request", "command": "threads" }).to_string();
+    let body = body.clone() + &" ".repeat(CONTENT_OVERSIZED - body.len());
+    let input = format!("Content-Length: {}\r\n\r\n{}", body.len(), body);
+    assert!(messages(input.into_bytes()).is_empty());
+}
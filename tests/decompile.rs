@@ -0,0 +1,172 @@
+mod common;
+
+use rc201_8::decompile::Decompiler;
+use rc201_8::generator::{GenConfig, Generator};
+
+/// Programs generated for the label test
+const PROGRAMS: u64 = 100;
+
+/// Pseudocode of every function of a ROM
+fn pseudocode(rom: &[u8]) -> String {
+    let mut text = Vec::new();
+    Decompiler::new(rom).write_pseudocode(&mut text).unwrap();
+    String::from_utf8(text).unwrap()
+}
+
+/// Check that braces balance and every goto reaches a label of its
+/// function
+fn check_labels(text: &str, name: &str) {
+    for function in text.split("\nvoid ") {
+        let opens = function.matches('{').count();
+        assert_eq!(opens, function.matches('}').count(), "{}", name);
+        for line in function.lines() {
+            if let Some(goto) = line.split("goto ").nth(1) {
+                let label = goto.trim_end_matches(';');
+                if !label.starts_with('*') {
+                    let defined = format!("\n{}:", label);
+                    let nested = format!(" {}:", label);
+                    assert!(
+                        function.contains(&defined) || function.contains(&nested),
+                        "{}: {} is not defined in\n{}",
+                        name,
+                        label,
+                        function
+                    );
+                }
+            }
+        }
+    }
+}
+
+#[test]
+fn conditions_and_jumps_become_structures() {
+    let rom = [
+        0x60, 0x00, // v0 = 0
+        0x40, 0x05, // while (v0 != 5)
+        0x12, 0x0C, //
+        0x70, 0x01, //     v0 += 1
+        0x00, 0xE0, //     cls
+        0x12, 0x02, //
+        0x31, 0x03, // if (v1 == 3)
+        0x12, 0x16, //
+        0x62, 0x01, //     v2 = 1
+        0x63, 0x02, //     v3 = 2
+        0x12, 0x18, // else
+        0x62, 0x03, //     v2 = 3
+        0xE1, 0x9E, // if (!pressed(v1))
+        0x64, 0x04, //     v4 = 4
+        0x51, 0x20, // if (v1 == v2)
+        0x12, 0x22, //
+        0x00, 0xE0, //     cls
+        0x12, 0x22, // for (;;)
+    ];
+
+    assert_eq!(
+        pseudocode(&rom),
+        "void main(void)
+{
+    v0 = 0x00;
+    while (v0 != 0x05) {
+        v0 += 0x01;
+        cls();
+    }
+    if (v1 == 0x03) {
+        v2 = 0x01;
+        v3 = 0x02;
+    } else {
+        v2 = 0x03;
+    }
+    if (!pressed(v1)) v4 = 0x04;
+    if (v1 == v2) {
+        cls();
+    }
+    for (;;) {
+    }
+}
+"
+    );
+}
+
+#[test]
+fn chained_conditions_keep_gotos() {
+    let rom = [
+        0x22, 0x04, // sub_204();
+        0x12, 0x02, // for (;;)
+        0x30, 0x01, // sub_204: skip on v0
+        0x31, 0x02, //     skip on v1
+        0x00, 0xE0, //     cls
+        0x00, 0xEE, //     return
+    ];
+
+    assert_eq!(
+        pseudocode(&rom),
+        "void main(void)
+{
+    sub_204();
+    for (;;) {
+    }
+}
+
+void sub_204(void)
+{
+    if (v0 == 0x01) goto label_208;
+    if (v1 == 0x02) goto label_20a;
+label_208:
+    cls();
+label_20a:
+    return;
+}
+"
+    );
+}
+
+#[test]
+fn gotos_reach_labels_of_test_roms() {
+    for name in common::ROMS.iter() {
+        let rom = common::rom(name);
+        check_labels(&pseudocode(&rom), name);
+    }
+}
+
+#[test]
+fn gotos_reach_labels_of_generated_programs() {
+    let mut gen = Generator::new(0x48, GenConfig::default()).unwrap();
+    for program in 0..PROGRAMS {
+        let rom = gen.program();
+        let text = pseudocode(&rom);
+        check_labels(&text, &format!("program {}", program));
+        for sub in Decompiler::new(&rom).cfg().subroutines() {
+            assert!(text.contains(&format!("void sub_{:03x}(void)", sub.entry)));
+        }
+    }
+}
+
+#[test]
+fn flag_writes_are_shown() {
+    let rom = [
+        0x81, 0x24, // v1 += v2
+        0x81, 0x25, // v1 -= v2
+        0x81, 0x26, // v1 = v2 >> 1
+        0x8F, 0x17, // vf = v1 - vf
+        0x83, 0x3E, // v3 <<= 1
+        0x34, 0x00, // if (v4 != 0)
+        0x85, 0x64, //     v5 += v6
+        0x12, 0x0E, // for (;;)
+    ];
+
+    assert_eq!(
+        pseudocode(&rom),
+        "void main(void)
+{
+    v1 += v2; vf = carry;
+    v1 -= v2; vf = !borrow;
+    v1 = v2 >> 1; vf = shifted;
+    vf = v1 - vf; vf = !borrow;
+    v3 <<= 1; vf = shifted;
+    if (v4 != 0x00) { v5 += v6; vf = carry; }
+    for (;;) {
+    }
+}
+"
+    );
+}
@@ -0,0 +1,87 @@
+use rc201_8::cpu::Cpu;
+use rc201_8::debugger::Register;
+use rc201_8::display::{Display, DisplayDummy};
+use rc201_8::diverge::{self, Divergence};
+use rc201_8::emu::Emu;
+use rc201_8::mem::{MemAccess, MemAccessKind};
+use std::cell::RefCell;
+use std::rc::Rc;
+
+/// Load the pattern on 0x300 and set the pitch from V0, then loop
+const ROM: [u8; 8] = [0xA3, 0x00, 0xF0, 0x02, 0xF0, 0x3A, 0x12, 0x06];
+
+/// Wait for the delay timer, then draw a random number and loop
+const TIMER_ROM: [u8; 14] = [
+    0x60, 0x05, 0xF0, 0x15, 0xF0, 0x07, 0x30, 0x00, 0x12, 0x04, 0xC1, 0xFF, 0x12, 0x0C,
+];
+
+/// Emulators sharing a program
+fn pair_of(rom: &[u8]) -> (Emu<DisplayDummy>, Emu<DisplayDummy>) {
+    let mut a = Emu::new(DisplayDummy::new());
+    let mut b = Emu::new(DisplayDummy::new());
+    a.load_rom(rom).unwrap();
+    b.load_rom(rom).unwrap();
+    (a, b)
+}
+
+/// Emulators sharing the audio program
+fn pair() -> (Emu<DisplayDummy>, Emu<DisplayDummy>) {
+    pair_of(&ROM)
+}
+
+#[test]
+fn audio_pattern_divergence_is_reported() {
+    let (mut a, mut b) = pair();
+    b.pat_put([0xFF; 16]);
+    let report = diverge::find(&mut a, &mut b, &[], 10, 0, 2).unwrap();
+    assert_eq!(report.step, 0);
+    assert_eq!(report.divergence, Divergence::Pattern);
+}
+
+#[test]
+fn pitch_divergence_is_reported() {
+    let (mut a, mut b) = pair();
+    b.pit_put(0x70);
+    let report = diverge::find(&mut a, &mut b, &[], 10, 0, 2).unwrap();
+    assert_eq!(report.step, 0);
+    assert_eq!(
+        report.divergence,
+        Divergence::Register(Register::PITCH, 0x40, 0x70)
+    );
+}
+
+#[test]
+fn timers_tick_while_polled() {
+    let (mut a, mut b) = pair_of(&TIMER_ROM);
+    a.rng_seed(1);
+    b.rng_seed(2);
+    assert!(diverge::find(&mut a, &mut b, &[], 1000, 0, 2).is_none());
+
+    let (mut a, mut b) = pair_of(&TIMER_ROM);
+    a.rng_seed(1);
+    b.rng_seed(2);
+    let report = diverge::find(&mut a, &mut b, &[], 1000, 10, 2).unwrap();
+    assert_eq!(a.cnt_get(), 0x20C);
+    assert_eq!(
+        report.divergence,
+        Divergence::Register(
+            Register::V(1),
+            a.reg_get(&1).unwrap().into(),
+            b.reg_get(&1).unwrap().into()
+        )
+    );
+}
+
+#[test]
+fn comparing_states_reads_no_memory() {
+    let (mut a, mut b) = pair_of(&TIMER_ROM);
+    let accesses = Rc::new(RefCell::new(Vec::<MemAccess>::new()));
+    a.mem_observer_add(Box::new(accesses.clone()));
+    assert!(diverge::find(&mut a, &mut b, &[], 20, 10, 2).is_none());
+    let reads = accesses
+        .borrow()
+        .iter()
+        .filter(|a| a.kind == MemAccessKind::Read)
+        .count();
+    assert!(reads <= 2 * 20, "{} reads", reads);
+}
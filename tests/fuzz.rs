@@ -0,0 +1,114 @@
+use rand::rngs::StdRng;
+use rand::{Rng, SeedableRng};
+use rc201_8::debugger::Register;
+use rc201_8::emu::REG_SIZE;
+use rc201_8::fuzz::{self, EXECUTE_HEADER};
+
+/// Random inputs of the execution and memory targets
+const INPUTS: usize = 2000;
+
+/// Longest random input
+const INPUT_SIZE: usize = 512;
+
+/// Random input of random length
+fn input(rng: &mut StdRng) -> Vec<u8> {
+    let len = rng.gen_range(0, INPUT_SIZE as u32) as usize;
+    (0..len).map(|_| rng.gen()).collect()
+}
+
+/// Input made of a header and a ROM
+fn program(header: &[(usize, u8)], rom: &[u8]) -> Vec<u8> {
+    let mut data = vec![0; EXECUTE_HEADER];
+    for (i, v) in header {
+        data[*i] = *v;
+    }
+    data.extend_from_slice(rom);
+    data
+}
+
+#[test]
+fn header_and_rom_do_not_overlap() {
+    // Preset, seed and keys, then a word per register
+    assert_eq!(EXECUTE_HEADER, 4 + 2 * Register::all().len());
+}
+
+#[test]
+fn decode_every_word() {
+    for rsize in 0..=REG_SIZE as u8 {
+        let mut data = vec![rsize];
+        for code in 0..=0xFFFF_u16 {
+            data.extend_from_slice(&code.to_be_bytes());
+        }
+        fuzz::decode(&data);
+    }
+}
+
+#[test]
+fn execute_random_inputs() {
+    let mut rng = StdRng::seed_from_u64(0xF022);
+    for _ in 0..INPUTS {
+        fuzz::execute(&input(&mut rng));
+    }
+}
+
+#[test]
+fn execute_edge_cases() {
+    // Offsets of the I and PC registers in the header
+    let (i, pc) = (4 + 2 * 16, 4 + 2 * 17);
+    let cases = vec![
+        // Empty input and empty ROM
+        vec![],
+        program(&[], &[]),
+        // Return without a call
+        program(&[], &[0x00, 0xEE]),
+        // Endless recursion
+        program(&[], &[0x22, 0x00]),
+        // Wait for a key with the program counter on 0
+        program(&[(pc, 0x00), (pc + 1, 0x00)], &[0xF0, 0x0A]),
+        // Stores, loads and BCD with I on the last addresses
+        program(
+            &[(i, 0x0F), (i + 1, 0xFE)],
+            &[0xFF, 0x55, 0xFF, 0x65, 0xF0, 0x33],
+        ),
+        program(
+            &[(i, 0xFF), (i + 1, 0xFF)],
+            &[0xF0, 0x1E, 0xFF, 0x55, 0xF0, 0x02],
+        ),
+        // Draw from the end of the memory
+        program(&[(i, 0x0F), (i + 1, 0xFF)], &[0xD0, 0x1F]),
+        // Jump past the end of the memory
+        program(&[(0, 1), (4, 0xFF)], &[0xBF, 0xFF]),
+        // Skip with the program counter on the last address
+        program(&[(pc, 0xFF), (pc + 1, 0xFF)], &[0x30, 0x00]),
+        // Stack pointer past the stack
+        program(&[(4 + 2 * 18 + 1, 0xFF)], &[0x00, 0xEE, 0x22, 0x00]),
+        // ROM filling the memory
+        program(&[], &[0x12; 0x1000]),
+    ];
+    for data in cases {
+        fuzz::execute(&data);
+    }
+}
+
+#[test]
+fn memory_random_ranges() {
+    let mut rng = StdRng::seed_from_u64(0x0F55);
+    for _ in 0..INPUTS {
+        fuzz::memory(&input(&mut rng));
+    }
+    for kind in 0..6 {
+        for (a, b) in &[
+            (0_u16, 0_u16),
+            (0, 0x1000),
+            (0x1000, 0x1000),
+            (0x0FFF, 0x1000),
+            (5, 3),
+            (0xFFFF, 0xFFFF),
+        ] {
+            let mut data = vec![kind];
+            data.extend_from_slice(&a.to_be_bytes());
+            data.extend_from_slice(&b.to_be_bytes());
+            fuzz::memory(&data);
+        }
+    }
+}
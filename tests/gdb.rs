@@ -0,0 +1,87 @@
+use rc201_8::display::{Display, DisplayDummy};
+use rc201_8::emu::Emu;
+use rc201_8::gdb::{GdbConnection, GdbStub};
+use std::cell::RefCell;
+use std::io::{self, Cursor, Read, Write};
+use std::rc::Rc;
+
+/// Bad packets sent before a valid one
+const BAD_PACKETS: usize = 100_000;
+
+/// Connection replaying scripted client bytes and recording the replies
+struct Script {
+    input: Cursor<Vec<u8>>,
+    output: Rc<RefCell<Vec<u8>>>,
+}
+
+impl Read for Script {
+    fn read(&mut self, buf: &mut [u8]) -> io::Result<usize> {
+        self.input.read(buf)
+    }
+}
+
+impl Write for Script {
+    fn write(&mut self, buf: &[u8]) -> io::Result<usize> {
+        self.output.borrow_mut().write(buf)
+    }
+
+    fn flush(&mut self) -> io::Result<()> {
+        Ok(())
+    }
+}
+
+impl GdbConnection for Script {
+    fn poll_interrupt(&mut self) -> io::Result<bool> {
+        Ok(false)
+    }
+}
+
+/// Frame a packet with its checksum
+fn packet(data: &str) -> Vec<u8> {
+    let sum = data.bytes().fold(0_u8, |s, b| s.wrapping_add(b));
+    format!("${}#{:02x}", data, sum).into_bytes()
+}
+
+/// Serve the scripted bytes and return the replies
+fn serve(input: Vec<u8>) -> String {
+    let output = Rc::new(RefCell::new(Vec::new()));
+    let mut stub = GdbStub::new(Script {
+        input: Cursor::new(input),
+        output: Rc::clone(&output),
+    });
+    let mut emu = Emu::new(DisplayDummy::new());
+    stub.serve(&mut emu).unwrap();
+    let replies = output.borrow().clone();
+    String::from_utf8(replies).unwrap()
+}
+
+#[test]
+fn memory_range_overflow_is_an_error() {
+    let mut input = packet("mffffffffffffffff,2");
+    input.extend(b"+");
+    input.extend(packet("Mffffffffffffffff,1:00"));
+    input.extend(b"+");
+    let replies = serve(input);
+    assert_eq!(replies, format!("+{}+{}", "$E01#a6", "$E01#a6"));
+}
+
+#[test]
+fn annex_range_overflow_reads_to_the_end() {
+    let mut input = packet("qXfer:features:read:target.xml:10,ffffffffffffffff");
+    input.extend(b"+");
+    let replies = serve(input);
+    assert!(replies.starts_with("+$l"), "{}", replies);
+    assert!(replies.contains("</target>"), "{}", replies);
+}
+
+#[test]
+fn bad_checksums_are_rejected_without_recursion() {
+    let mut input = Vec::new();
+    for _ in 0..BAD_PACKETS {
+        input.extend(b"$m200,1#00");
+    }
+    input.extend(packet("m200,1"));
+    let replies = serve(input);
+    assert_eq!(replies.matches('-').count(), BAD_PACKETS);
+    assert!(replies.ends_with("+$00#60"));
+}
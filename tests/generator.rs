@@ -0,0 +1,146 @@
+use rc201_8::block::BlockEngine;
+use rc201_8::cpu::Cpu;
+use rc201_8::debugger::Register;
+use rc201_8::display::{Display, DisplayBuffer, DisplayEmu};
+use rc201_8::emu::Emu;
+use rc201_8::generator::{GenConfig, Generator};
+use rc201_8::mem::{Mem, MemAccess, MemObserver};
+use rc201_8::quirk::QuirkPreset;
+use std::cell::RefCell;
+use std::rc::Rc;
+
+/// Programs generated by each test
+const PROGRAMS: u64 = 200;
+
+/// Instructions a program may take to halt
+const MAX_STEPS: usize = 100_000;
+
+/// Instructions per frame
+const IPF: usize = 16;
+
+/// Counter of writes to executed operations
+#[derive(Default)]
+struct Patches(usize);
+
+impl MemObserver for Patches {
+    fn mem_access(&mut self, _: &MemAccess) {}
+
+    fn code_modified(&mut self, _: usize, _: &MemAccess) {
+        self.0 += 1;
+    }
+}
+
+/// Run a program to its halting loop, returning the processed count
+fn run_to_halt(emu: &mut Emu<DisplayBuffer>) -> usize {
+    for step in 0..MAX_STEPS {
+        let cnt = emu.cnt_get();
+        emu.step().unwrap();
+        if emu.cnt_get() == cnt {
+            return step + 1;
+        }
+        if step % IPF == IPF - 1 {
+            emu.timers_tick();
+        }
+    }
+    panic!("program did not halt");
+}
+
+#[test]
+fn programs_halt_under_every_preset() {
+    let depth = QuirkPreset::all()
+        .iter()
+        .map(|p| p.stack_depth())
+        .min()
+        .unwrap();
+    let config = GenConfig {
+        subroutines: depth,
+        stack_depth: depth,
+        ..GenConfig::default()
+    };
+    let mut gen = Generator::new(0x45, config).unwrap();
+    for program in 0..PROGRAMS {
+        let rom = gen.program();
+        for preset in QuirkPreset::all() {
+            let mut emu = Emu::new(DisplayBuffer::new());
+            let patches = Rc::new(RefCell::new(Patches::default()));
+            emu.mem_observer_add(Box::new(patches.clone()));
+            emu.quirks_put(preset.quirks());
+            emu.stack_depth_put(preset.stack_depth());
+            emu.rng_seed(program);
+            emu.load_rom(&rom).unwrap();
+
+            run_to_halt(&mut emu);
+            assert_eq!(emu.spt_get(), 0, "program {} {}", program, preset.name());
+            assert_eq!(
+                patches.borrow().0,
+                0,
+                "program {} {}",
+                program,
+                preset.name()
+            );
+        }
+    }
+}
+
+#[test]
+fn call_chain_must_fit_the_stack() {
+    let config = GenConfig {
+        subroutines: 12,
+        stack_depth: 12,
+        ..GenConfig::default()
+    };
+    assert!(Generator::new(0, config).is_some());
+    let config = GenConfig {
+        subroutines: 13,
+        ..config
+    };
+    assert!(Generator::new(0, config).is_none());
+}
+
+#[test]
+fn programs_are_reproducible() {
+    let config = GenConfig::default();
+    let mut a = Generator::new(3, config).unwrap();
+    let mut b = Generator::new(3, config).unwrap();
+    for _ in 0..PROGRAMS {
+        assert_eq!(a.program(), b.program());
+    }
+}
+
+#[test]
+fn block_engine_matches_interpreter_on_generated_programs() {
+    let mut gen = Generator::new(0x8C8, GenConfig::default()).unwrap();
+    for program in 0..PROGRAMS {
+        let rom = gen.program();
+        let mut a = Emu::new(DisplayBuffer::new());
+        let mut b = Emu::new(DisplayBuffer::new());
+        a.load_rom(&rom).unwrap();
+        b.load_rom(&rom).unwrap();
+        a.rng_seed(program);
+        b.rng_seed(program);
+
+        let steps = run_to_halt(&mut a);
+        let mut engine = BlockEngine::new(&mut b);
+        let mut done = 0;
+        while done < steps {
+            let frame = IPF.min(steps - done);
+            done += engine.run(frame).unwrap();
+            if done % IPF == 0 && done < steps {
+                engine.emu().timers_tick();
+            }
+        }
+        drop(engine);
+
+        for register in Register::all() {
+            assert_eq!(
+                register.read(&a).unwrap(),
+                register.read(&b).unwrap(),
+                "program {} register {}",
+                program,
+                register.name()
+            );
+        }
+        assert!(a.mem_read(..).unwrap() == b.mem_read(..).unwrap());
+        assert!(a.get_display() == b.get_display(), "program {}", program);
+    }
+}
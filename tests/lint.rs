@@ -0,0 +1,155 @@
+mod common;
+
+use rc201_8::lint::{LintKind, LintReport};
+use rc201_8::quirk::QuirkPreset;
+
+/// Address and kind of every lint of a program
+fn lints(program: &[u16]) -> Vec<(u16, LintKind)> {
+    LintReport::new(&common::assemble(program))
+        .lints()
+        .iter()
+        .map(|l| (l.address, l.kind))
+        .collect()
+}
+
+/// Expected quirk setting of every lint of a program
+fn expects(program: &[u16]) -> Vec<Option<bool>> {
+    LintReport::new(&common::assemble(program))
+        .lints()
+        .iter()
+        .map(|l| l.expects)
+        .collect()
+}
+
+/// Recommended preset of a program
+fn recommend(program: &[u16]) -> QuirkPreset {
+    LintReport::new(&common::assemble(program)).recommend()
+}
+
+#[test]
+fn shifts_between_registers_depend_on_quirk() {
+    assert_eq!(lints(&[0x8116, 0x811E, 0x1204]), vec![]);
+    assert_eq!(
+        lints(&[0x8126, 0x821E, 0x1204]),
+        vec![(0x200, LintKind::ShiftVy), (0x202, LintKind::ShiftVy)]
+    );
+}
+
+#[test]
+fn index_used_after_load_store_depends_on_quirk() {
+    // I set again before drawing
+    assert_eq!(lints(&[0xF165, 0xA300, 0xD015, 0x1206]), vec![]);
+    assert_eq!(
+        lints(&[0xF165, 0xD015, 0x1204]),
+        vec![(0x200, LintKind::LoadStoreInc)]
+    );
+
+    // Only the path skipping the assignment of I draws
+    assert_eq!(
+        lints(&[0xF155, 0x3000, 0xA300, 0xD015, 0x1208]),
+        vec![(0x200, LintKind::LoadStoreInc)]
+    );
+}
+
+#[test]
+fn next_use_of_index_tells_the_expected_quirk() {
+    // Loading again walks on from the incremented I
+    assert_eq!(expects(&[0xF165, 0xF165, 0x1204]), vec![Some(true)]);
+
+    // Drawing or adding to I reuses the same address
+    assert_eq!(expects(&[0xF155, 0xD015, 0x1204]), vec![Some(false)]);
+    assert_eq!(expects(&[0xF165, 0xF21E, 0x1204]), vec![Some(false)]);
+
+    // Both uses, on different paths
+    assert_eq!(
+        expects(&[0xF165, 0x3000, 0xF165, 0xD015, 0x1208]),
+        vec![None, Some(false)]
+    );
+}
+
+#[test]
+fn indirect_jumps_on_high_nibble_depend_on_quirk() {
+    assert_eq!(lints(&[0xB0F0]), vec![]);
+
+    // V0 is never written, so the jump most likely adds V3
+    let report = LintReport::new(&[0x63, 0x00, 0xB3, 0x04, 0x13, 0x04]);
+    assert_eq!(report.lints()[0].kind, LintKind::JumpVx);
+    assert_eq!(report.lints()[0].expects, Some(true));
+    assert_eq!(report.recommend(), QuirkPreset::SuperChip);
+
+    let report = LintReport::new(&[0x60, 0x00, 0xB3, 0x04, 0x13, 0x04]);
+    assert_eq!(report.lints()[0].expects, None);
+    assert_eq!(report.recommend(), QuirkPreset::Chip8);
+}
+
+#[test]
+fn flag_read_after_logic_depends_on_quirk() {
+    assert_eq!(
+        lints(&[0x8121, 0x3F00, 0x1204]),
+        vec![(0x200, LintKind::VfRead)]
+    );
+    assert_eq!(lints(&[0x8122, 0x6F00, 0x3F00, 0x1206]), vec![]);
+    assert_eq!(lints(&[0x8F23, 0x1202]), vec![(0x200, LintKind::VfRead)]);
+
+    // Only a result kept in VF tells which setting the code expects
+    assert_eq!(expects(&[0x8121, 0x3F00, 0x1204]), vec![None]);
+    assert_eq!(expects(&[0x8F23, 0x1202]), vec![Some(false)]);
+    assert_eq!(expects(&[0x8FF3, 0x3F00, 0x1204]), vec![None]);
+}
+
+#[test]
+fn sprites_across_edges_depend_on_interpreter() {
+    assert_eq!(lints(&[0x6000, 0x6100, 0xD015, 0x1206]), vec![]);
+    assert_eq!(
+        lints(&[0x603C, 0x6100, 0xD015, 0x1206]),
+        vec![(0x204, LintKind::SpriteEdge)]
+    );
+    assert_eq!(
+        lints(&[0x6000, 0x611E, 0xD015, 0x1206]),
+        vec![(0x204, LintKind::SpriteEdge)]
+    );
+}
+
+#[test]
+fn quirks_test_rom_lints_every_quirk() {
+    let report = LintReport::new(&common::rom("quirks"));
+    for kind in [
+        LintKind::ShiftVy,
+        LintKind::LoadStoreInc,
+        LintKind::JumpVx,
+        LintKind::VfRead,
+    ]
+    .iter()
+    {
+        assert!(report.lints().iter().any(|l| l.kind == *kind), "{:?}", kind);
+    }
+
+    let mut text = Vec::new();
+    report.write_report(&mut text).unwrap();
+    let text = String::from_utf8(text).unwrap();
+    assert!(text.contains("  shift-vy        SHR V1, V2\n"));
+    assert!(text.contains("\nrecommend "));
+}
+
+#[test]
+fn programs_written_for_each_platform_get_its_preset() {
+    // COSMAC VIP style: shift a copy of V2, then read two records of a
+    // table at I with consecutive loads
+    assert_eq!(
+        recommend(&[0x8126, 0xA300, 0xF165, 0xF165, 0x8010, 0x1200]),
+        QuirkPreset::Chip8
+    );
+
+    // SUPER-CHIP style: shift in place, save the registers and draw the
+    // saved bytes, then step I by hand and draw the loaded bytes
+    assert_eq!(
+        recommend(&[0x8116, 0xA300, 0xF155, 0xD012, 0xF31E, 0xF165, 0xD012, 0x1200]),
+        QuirkPreset::SuperChip
+    );
+
+    // No quirk-dependent code keeps the default
+    assert_eq!(
+        recommend(&[0x00E0, 0xA20A, 0x6000, 0xD005, 0x1208]),
+        QuirkPreset::Chip8
+    );
+}
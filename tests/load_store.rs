@@ -0,0 +1,27 @@
+use rc201_8::cpu::Cpu;
+use rc201_8::display::{Display, DisplayDummy};
+use rc201_8::emu::Emu;
+use rc201_8::mem::Mem;
+
+#[test]
+fn load_store_transfer_v0_to_vx() {
+    let mut emu = Emu::new(DisplayDummy::new());
+    for (i, v) in [0x10, 0x11, 0x12, 0x13].iter().enumerate() {
+        emu.reg_put(&i, *v).unwrap();
+    }
+
+    // Store V0 to V2, whatever V2 holds
+    emu.ind_put(0x300);
+    emu.recv_opcode(&0xF255).unwrap();
+    assert_eq!(
+        emu.mem_read(0x300..0x304).unwrap(),
+        &[0x10, 0x11, 0x12, 0x00]
+    );
+
+    // Load V0 and V1 back, leaving V2 and V3
+    emu.mem_write(0x300..0x302, &[0xA0, 0xA1]).unwrap();
+    emu.ind_put(0x300);
+    emu.recv_opcode(&0xF165).unwrap();
+    let regs: Vec<u8> = (0..4).map(|i| emu.reg_get(&i).unwrap()).collect();
+    assert_eq!(regs, vec![0xA0, 0xA1, 0x12, 0x13]);
+}
@@ -0,0 +1,96 @@
+mod common;
+
+use rc201_8::emu::{MEM_SIZE, PRG_START};
+use rc201_8::platform::{Detection, Platform};
+
+/// Detection of a program
+fn detect(program: &[u16]) -> Detection {
+    Detection::new(&common::assemble(program))
+}
+
+#[test]
+fn standard_roms_are_chip8() {
+    for name in common::ROMS.iter() {
+        let detection = Detection::new(&common::rom(name));
+        assert_eq!(detection.platform, Platform::Chip8, "{}", name);
+        assert!(detection.confidence > 0.5, "{}", name);
+    }
+}
+
+#[test]
+fn extension_operations_select_their_platform() {
+    // High resolution and a large sprite
+    assert_eq!(
+        detect(&[0x6000, 0xD010, 0x00FF, 0x1206]).platform,
+        Platform::SuperChip
+    );
+
+    // Long I with an operand that looks like 00FF, and a plane
+    let detection = detect(&[0xF000, 0x00FF, 0xF101, 0x1206]);
+    assert_eq!(detection.platform, Platform::XoChip);
+    assert!(detection
+        .evidence
+        .iter()
+        .all(|e| e.platform != Platform::SuperChip));
+
+    // Mega on
+    assert_eq!(detect(&[0x0011, 0x1202]).platform, Platform::MegaChip);
+}
+
+#[test]
+fn operations_after_an_extension_are_reached() {
+    // High resolution, long I, audio pattern, plane, draw and pitch
+    let detection = detect(&[
+        0x00FF, 0x00E0, 0xF000, 0x2400, 0xF002, 0xF101, 0xD015, 0xF03A, 0x120E,
+    ]);
+    assert_eq!(detection.platform, Platform::XoChip);
+    assert!(detection.confidence > 0.75);
+    for evidence in detection.evidence.iter().filter(|e| e.address.is_some()) {
+        assert_eq!(evidence.weight, 4.0, "{:?}", evidence);
+    }
+
+    // A skip steps over long I, whose operand would jump to a scroll
+    let detection = detect(&[0x3001, 0xF000, 0x120A, 0x1206, 0x0000, 0x00FB]);
+    assert_eq!(detection.platform, Platform::XoChip);
+    let scroll = detection.evidence.iter().find(|e| e.address == Some(0x20A));
+    assert!(scroll.unwrap().weight < 1.0);
+}
+
+#[test]
+fn size_beyond_memory_selects_larger_platforms() {
+    let xo = vec![0x12, 0x00]
+        .into_iter()
+        .chain(vec![0xAA; MEM_SIZE - PRG_START])
+        .collect::<Vec<u8>>();
+    assert_eq!(Detection::new(&xo).platform, Platform::XoChip);
+
+    let mega = vec![0x12, 0x00]
+        .into_iter()
+        .chain(vec![0xAA; 0x10000])
+        .collect::<Vec<u8>>();
+    assert_eq!(Detection::new(&mega).platform, Platform::MegaChip);
+}
+
+#[test]
+fn data_and_conflicts_weigh_less() {
+    // 00FF in the unreached sprite data
+    let mut program = vec![0x6000, 0x6100, 0xA20C, 0xD012, 0x7001, 0x1206];
+    program.extend_from_slice(&[0x00FF, 0x00FF]);
+    assert_eq!(detect(&program).platform, Platform::Chip8);
+
+    // A MegaChip pattern in data lowers the confidence in XO-CHIP
+    let clean = detect(&[0xF101, 0x1202]);
+    let conflict = detect(&[0xF101, 0x1202, 0x0011, 0x0011, 0x0011, 0x0011]);
+    assert_eq!(clean.platform, Platform::XoChip);
+    assert_eq!(conflict.platform, Platform::XoChip);
+    assert!(conflict.confidence < clean.confidence);
+
+    // Sprite rows matching MegaChip operations, however many
+    let mut program = vec![0xA204, 0x1202];
+    for _ in 0..16 {
+        program.extend_from_slice(&[0x03C0, 0x07E0]);
+    }
+    assert_eq!(detect(&program).platform, Platform::Chip8);
+    program.extend(vec![0x03C0; 0x400]);
+    assert_eq!(detect(&program).platform, Platform::Chip8);
+}
@@ -0,0 +1,104 @@
+use rand::rngs::StdRng;
+use rand::{Rng, SeedableRng};
+use rc201_8::cpu::Cpu;
+use rc201_8::display::{Display, DisplayBuffer};
+use rc201_8::emu::{Emu, REG_SIZE};
+use rc201_8::generator::{GenConfig, Generator};
+use rc201_8::oper::{Oper, OperCode};
+use rc201_8::quirk::QuirkPreset;
+use rc201_8::reference::{self, Reference};
+
+/// Programs run under each preset
+const PROGRAMS: u64 = 100;
+
+/// Instructions compared on each program
+const STEPS: u64 = 2000;
+
+/// Instructions per frame
+const IPF: usize = 16;
+
+/// Emulator and reference interpreter sharing a ROM, a preset and the
+/// random numbers of a seed
+fn pair(rom: &[u8], preset: QuirkPreset, seed: u64) -> (Emu<DisplayBuffer>, Reference) {
+    let mut emu = Emu::new(DisplayBuffer::new());
+    emu.quirks_put(preset.quirks());
+    emu.stack_depth_put(preset.stack_depth());
+    emu.rng_seed(seed);
+    emu.load_rom(rom).unwrap();
+
+    // Same draws as RandCXNN of the emulator
+    let mut rng = StdRng::seed_from_u64(seed);
+    let mut reference = Reference::new(Box::new(move || rng.gen::<u8>()));
+    reference.quirks = preset.quirks();
+    reference.stack_depth = preset.stack_depth();
+    reference.load_rom(rom).unwrap();
+    (emu, reference)
+}
+
+#[test]
+fn reference_decodes_like_oper() {
+    for code in 0..=0xFFFF_u16 {
+        let name = match Oper::from_code(&code, &REG_SIZE) {
+            OperCode::Unknown => None,
+            oper => Some(oper.name()),
+        };
+        assert_eq!(Reference::name(code), name, "{:04X}", code);
+    }
+}
+
+#[test]
+fn emu_matches_reference_on_generated_programs() {
+    let mut gen = Generator::new(0x46, GenConfig::default()).unwrap();
+    for program in 0..PROGRAMS {
+        let rom = gen.program();
+        for preset in QuirkPreset::all() {
+            let (mut emu, mut reference) = pair(&rom, preset, program);
+            if let Some((step, divergence)) =
+                reference::lockstep(&mut emu, &mut reference, STEPS, IPF)
+            {
+                panic!(
+                    "program {} {} step {}: {}",
+                    program,
+                    preset.name(),
+                    step,
+                    divergence
+                );
+            }
+        }
+    }
+}
+
+#[test]
+fn emu_matches_reference_on_random_roms() {
+    let mut rng = StdRng::seed_from_u64(0x4E6);
+    for program in 0..PROGRAMS {
+        let len = rng.gen_range(0, 0x200_u32) as usize;
+        let rom: Vec<u8> = (0..len).map(|_| rng.gen()).collect();
+        for preset in QuirkPreset::all() {
+            let (mut emu, mut reference) = pair(&rom, preset, program);
+            if let Some((step, divergence)) =
+                reference::lockstep(&mut emu, &mut reference, STEPS, IPF)
+            {
+                panic!(
+                    "rom {} {} step {}: {}",
+                    program,
+                    preset.name(),
+                    step,
+                    divergence
+                );
+            }
+        }
+    }
+}
+
+#[test]
+fn rand_draws_every_byte() {
+    let mut emu = Emu::new(DisplayBuffer::new());
+    emu.rng_seed(0x46);
+    let mut drawn = [false; 256];
+    for _ in 0..0x4000 {
+        emu.recv_opcode(&0xC0FF).unwrap();
+        drawn[usize::from(emu.reg_get(&0).unwrap())] = true;
+    }
+    assert!(drawn.iter().all(|d| *d));
+}
@@ -0,0 +1,92 @@
+use std::fs;
+use std::path::PathBuf;
+use std::process::{Command, Output};
+
+/// Exit code of a passed run
+const EXIT_PASS: i32 = 0;
+
+/// Exit code of a failed run
+const EXIT_FAIL: i32 = 1;
+
+/// Exit code of a usage error
+const EXIT_USAGE: i32 = 2;
+
+/// Assign V0, store it on 0x300, then loop on 0x206
+const ROM: [u8; 10] = [0x60, 0x2A, 0xA3, 0x00, 0xF0, 0x55, 0x12, 0x06, 0x00, 0x00];
+
+/// Write a ROM to a file of its own, returning the path
+fn rom_file(name: &str, rom: &[u8]) -> PathBuf {
+    let dir = std::env::temp_dir().join(format!("rc201_8-run-{}", std::process::id()));
+    fs::create_dir_all(&dir).unwrap();
+    let path = dir.join(format!("{}.ch8", name));
+    fs::write(&path, rom).unwrap();
+    path
+}
+
+/// Run the binary on a ROM with given options
+fn run(rom: &PathBuf, args: &[&str]) -> Output {
+    Command::new(env!("CARGO_BIN_EXE_rc201_8-run"))
+        .args(args)
+        .arg(rom)
+        .output()
+        .unwrap()
+}
+
+#[test]
+fn conditions_pass_and_fail() {
+    let rom = rom_file("conditions", &ROM);
+    let output = run(&rom, &["--until-pc", "0x206", "--frames", "1"]);
+    assert_eq!(output.status.code(), Some(EXIT_PASS));
+    let stdout = String::from_utf8(output.stdout).unwrap();
+    assert!(stdout.contains("v0  2A\n"));
+    assert!(stdout.ends_with("pass\n"));
+
+    let output = run(&rom, &["--until-mem", "0x300=42", "--quiet"]);
+    assert_eq!(output.status.code(), Some(EXIT_PASS));
+    assert!(output.stdout.is_empty());
+
+    let output = run(&rom, &["--until-pc", "0x208", "--frames", "2"]);
+    assert_eq!(output.status.code(), Some(EXIT_FAIL));
+}
+
+#[test]
+fn numbers_out_of_range_are_usage_errors() {
+    let rom = rom_file("range", &ROM);
+    for args in [
+        ["--until-pc", "0x10206"],
+        ["--until-mem", "0x300=0x12A"],
+        ["--frames", "18446744073709551616"],
+    ]
+    .iter()
+    {
+        let output = run(&rom, args);
+        assert_eq!(output.status.code(), Some(EXIT_USAGE), "{:?}", args);
+        let stderr = String::from_utf8(output.stderr).unwrap();
+        assert!(stderr.starts_with("rc201_8-run: "), "{:?}", args);
+    }
+}
+
+#[test]
+fn missing_rom_is_a_usage_error() {
+    let output = run(&PathBuf::from("missing.ch8"), &["--quiet"]);
+    assert_eq!(output.status.code(), Some(EXIT_USAGE));
+}
+
+#[test]
+fn auto_quirks_report_the_platform() {
+    let rom = rom_file("auto", &ROM);
+    let output = run(&rom, &["--quirks", "auto", "--until-pc", "0x206"]);
+    assert_eq!(output.status.code(), Some(EXIT_PASS));
+    let stdout = String::from_utf8(output.stdout).unwrap();
+    assert!(stdout.starts_with("platform chip8 "), "{}", stdout);
+
+    // Beyond the memory of the emulator
+    let large: Vec<u8> = ROM.iter().cloned().chain(vec![0; 0x10000]).collect();
+    let rom = rom_file("auto-large", &large);
+    let output = run(&rom, &["--quirks", "auto"]);
+    assert_eq!(output.status.code(), Some(EXIT_USAGE));
+    let stdout = String::from_utf8(output.stdout).unwrap();
+    assert!(stdout.starts_with("platform megachip "), "{}", stdout);
+    let stderr = String::from_utf8(output.stderr).unwrap();
+    assert!(stderr.contains("unsupported platform megachip"));
+}
@@ -0,0 +1,79 @@
+use rc201_8::cpu::{Cpu, CpuError, CpuErrorVariant};
+use rc201_8::display::{Display, DisplayDummy};
+use rc201_8::emu::{Emu, PRG_START, STK_SIZE};
+use rc201_8::quirk::QuirkPreset;
+
+/// Program calling itself forever, one level per instruction
+const RECURSE: [u8; 2] = [0x22, 0x00];
+
+/// Load the recursive program and run a given number of nested calls
+fn recurse(emu: &mut Emu<DisplayDummy>, calls: usize) -> Result<(), CpuError> {
+    emu.load_rom(&RECURSE).unwrap();
+    for _ in 0..calls {
+        emu.step()?;
+    }
+    Ok(())
+}
+
+#[test]
+fn stack_holds_its_full_depth() {
+    let mut emu = Emu::new(DisplayDummy::new());
+    recurse(&mut emu, STK_SIZE).unwrap();
+    assert_eq!(emu.spt_get(), STK_SIZE);
+    assert_eq!(emu.call_stack(), &[PRG_START as u16 + 2; STK_SIZE][..]);
+
+    assert!(matches!(
+        emu.step().unwrap_err().variant(),
+        CpuErrorVariant::StackOverflow(17)
+    ));
+    assert_eq!(emu.spt_get(), STK_SIZE);
+}
+
+#[test]
+fn stack_depth_follows_the_preset() {
+    for preset in QuirkPreset::all() {
+        let mut emu = Emu::new(DisplayDummy::new());
+        emu.stack_depth_put(preset.stack_depth());
+        let depth = preset.stack_depth();
+        recurse(&mut emu, depth).unwrap();
+        assert!(matches!(
+            emu.step().unwrap_err().variant(),
+            CpuErrorVariant::StackOverflow(_)
+        ));
+    }
+}
+
+#[test]
+fn returns_unwind_in_order() {
+    let mut emu = Emu::new(DisplayDummy::new());
+
+    // Call 0x206, which calls 0x20A, which returns twice
+    emu.load_rom(&[
+        0x22, 0x06, 0x12, 0x04, 0x12, 0x04, 0x22, 0x0A, 0x00, 0xEE, 0x00, 0xEE,
+    ])
+    .unwrap();
+    emu.step().unwrap();
+    emu.step().unwrap();
+    assert_eq!(emu.call_stack(), &[0x202, 0x208]);
+
+    emu.step().unwrap();
+    assert_eq!(emu.cnt_get(), 0x208);
+    emu.step().unwrap();
+    assert_eq!(emu.cnt_get(), 0x202);
+    assert!(emu.call_stack().is_empty());
+}
+
+#[test]
+fn return_on_empty_stack_underflows() {
+    let mut emu = Emu::new(DisplayDummy::new());
+    emu.load_rom(&[0x00, 0xEE]).unwrap();
+
+    let err = emu.step().unwrap_err();
+    assert!(matches!(err.variant(), CpuErrorVariant::StackUnderflow));
+    assert_eq!(emu.spt_get(), 0);
+    assert!(matches!(
+        emu.spt_dec().unwrap_err().variant(),
+        CpuErrorVariant::StackUnderflow
+    ));
+    assert!(emu.stk_get().is_err());
+}
@@ -0,0 +1,17 @@
+use rc201_8::display::{Display, DisplayDummy};
+use rc201_8::emu::{Emu, STK_SIZE};
+use rc201_8::trace::TraceRecord;
+
+#[test]
+fn text_stack_pointer_has_two_digits() {
+    let mut emu = Emu::new(DisplayDummy::new());
+
+    // Call itself until the stack is full
+    emu.load_rom(&[0x22, 0x00]).unwrap();
+    let mut record = TraceRecord::step(&mut emu).unwrap();
+    assert!(record.to_text().contains(" SP=01 "));
+    for _ in 1..STK_SIZE {
+        record = TraceRecord::step(&mut emu).unwrap();
+    }
+    assert!(record.to_text().contains(&format!(" SP={:02X} ", STK_SIZE)));
+}
@@ -0,0 +1,85 @@
+use rc201_8::audio::{self, SquareSynth, SynthConfig, PATTERN_SIZE};
+use rc201_8::cpu::Cpu;
+use rc201_8::display::{Display, DisplayDummy};
+use rc201_8::emu::Emu;
+use rc201_8::mem::Mem;
+use rc201_8::wav::WavSink;
+use std::cell::RefCell;
+use std::io::{Cursor, ErrorKind};
+use std::rc::Rc;
+
+/// Bytes of the WAV header before the samples
+const HEADER_SIZE: usize = 44;
+
+#[test]
+fn byte_rate_beyond_header_is_an_error() {
+    let config = SynthConfig {
+        sample_rate: u32::MAX,
+        ..SynthConfig::default()
+    };
+    let error = WavSink::new(Cursor::new(Vec::new()), config).err().unwrap();
+    assert_eq!(error.kind(), ErrorKind::InvalidInput);
+}
+
+#[test]
+fn pattern_plays_at_pitch_rate() {
+    let config = SynthConfig {
+        sample_rate: 8000,
+        frequency: 440.0,
+        volume: 1.0,
+        attack: 0.0,
+        release: 0.0,
+    };
+    let sink = WavSink::new(Cursor::new(Vec::new()), config).unwrap();
+    let sink = Rc::new(RefCell::new(sink));
+    let mut emu = Emu::new(DisplayDummy::new());
+    emu.audio_set(Box::new(sink.clone()));
+
+    // Load the pattern on 0x300, play 8000 bits per second and sound
+    emu.load_rom(&[
+        0xA3, 0x00, 0xF0, 0x02, 0x60, 0x70, 0xF0, 0x3A, 0x60, 0x02, 0xF0, 0x18,
+    ])
+    .unwrap();
+    emu.mem_write(0x300..0x302, &[0xF0, 0x0F]).unwrap();
+    for _ in 0..6 {
+        emu.step().unwrap();
+    }
+    assert_eq!(audio::pattern_rate(emu.pit_get()), 8000.0);
+    emu.timers_tick();
+
+    drop(emu);
+    let sink = Rc::try_unwrap(sink).ok().unwrap().into_inner();
+    let wav = sink.finish().unwrap().into_inner();
+    let samples: Vec<i16> = wav[HEADER_SIZE..]
+        .chunks(2)
+        .map(|s| i16::from_le_bytes([s[0], s[1]]))
+        .collect();
+
+    // One sample per bit: four high, eight low, four high
+    assert_eq!(&samples[..4], &[i16::MAX; 4]);
+    assert_eq!(&samples[4..12], &[-i16::MAX; 8]);
+    assert_eq!(&samples[12..16], &[i16::MAX; 4]);
+    // The remaining zero bytes of the pattern are low
+    assert!(samples[16..128].iter().all(|s| *s == -i16::MAX));
+}
+
+#[test]
+fn pattern_is_passed_to_a_later_sink() {
+    let mut emu = Emu::new(DisplayDummy::new());
+    emu.pat_put([0xFF; PATTERN_SIZE]);
+    emu.pit_put(112);
+
+    let config = SynthConfig {
+        sample_rate: 8000,
+        volume: 1.0,
+        attack: 0.0,
+        ..SynthConfig::default()
+    };
+    let synth = Rc::new(RefCell::new(SquareSynth::new(config)));
+    emu.audio_set(Box::new(synth.clone()));
+    emu.stm_put(1);
+
+    let mut samples = [0.0; 256];
+    synth.borrow_mut().render_f32(&mut samples);
+    assert!(samples.iter().all(|s| *s == 1.0));
+}